@@ -0,0 +1,178 @@
+//! Free disk space queries for download and install preflight checks
+//!
+//! Large patches that fill a disk mid-write leave truncated files behind,
+//! so downloads and installs check the target filesystem up front and fail
+//! with a structured error before any bytes are written.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+
+/// Extra headroom kept free on top of the expected size (16MB)
+pub const SPACE_MARGIN: u64 = 16 * 1024 * 1024;
+
+/// Space needed versus available on one filesystem
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpaceCheck {
+    pub path: PathBuf,
+    pub needed: u64,
+    pub available: u64,
+}
+
+impl SpaceCheck {
+    /// Query the filesystem holding `path`
+    pub fn new(path: &Path, needed: u64) -> io::Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            needed,
+            available: available_space(path)?,
+        })
+    }
+
+    pub fn is_sufficient(&self) -> bool {
+        self.needed == 0 || self.needed.saturating_add(SPACE_MARGIN) <= self.available
+    }
+}
+
+/// Available bytes for unprivileged users on the filesystem holding `path`
+///
+/// `path` does not need to exist yet; its nearest existing ancestor is
+/// queried instead.
+pub fn available_space(path: &Path) -> io::Result<u64> {
+    let existing = path
+        .ancestors()
+        .find(|p| p.exists())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{:?} has no existing ancestor", path)))?;
+    query_available(existing)
+}
+
+#[cfg(unix)]
+fn query_available(path: &Path) -> io::Result<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+
+    // SAFETY: c_path is NUL-terminated and stat is a valid out pointer
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(windows)]
+fn query_available(path: &Path) -> io::Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    let mut available = 0u64;
+
+    // SAFETY: wide is NUL-terminated; unused out pointers may be null
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, std::ptr::null_mut(), std::ptr::null_mut())
+    };
+    if ok == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(available)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn query_available(_path: &Path) -> io::Result<u64> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "free space query not supported"))
+}
+
+/// Check whether two existing paths live on the same filesystem
+///
+/// Returns false when unknown, so callers plan for a full copy.
+pub fn same_filesystem(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let dev = |p: &Path| {
+            p.ancestors()
+                .find_map(|p| std::fs::metadata(p).ok())
+                .map(|m| m.dev())
+        };
+        matches!((dev(a), dev(b)), (Some(x), Some(y)) if x == y)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (a, b);
+        false
+    }
+}
+
+/// Parse a human readable size ("1.2 GB", "512MB", "4 GiB", "1048576")
+///
+/// Both decimal and binary suffixes are treated as powers of 1024, which
+/// is how patch authors usually mean them and errs on the large side.
+pub fn parse_size(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.replace(',', ".").parse().ok()?;
+
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" | "bytes" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        _ => return None,
+    };
+
+    if number < 0.0 {
+        return None;
+    }
+    Some((number * multiplier as f64).ceil() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1048576"), Some(1_048_576));
+        assert_eq!(parse_size("512MB"), Some(512 * 1024 * 1024));
+        assert_eq!(parse_size("1.5 GB"), Some(3 * 512 * 1024 * 1024));
+        assert_eq!(parse_size("2,5 kb"), Some(2560));
+        assert_eq!(parse_size("4 GiB"), Some(4 << 30));
+        assert_eq!(parse_size("huge"), None);
+        assert_eq!(parse_size("12 parsecs"), None);
+    }
+
+    #[test]
+    fn test_available_space_of_missing_path_uses_ancestor() {
+        let dir = tempdir().unwrap();
+        let missing = dir.path().join("not/yet/created");
+
+        assert!(available_space(&missing).unwrap() > 0);
+        assert!(available_space(Path::new("relative/missing")).is_err());
+    }
+
+    #[test]
+    fn test_space_check_sufficiency() {
+        let dir = tempdir().unwrap();
+
+        assert!(SpaceCheck::new(dir.path(), 0).unwrap().is_sufficient());
+        assert!(SpaceCheck::new(dir.path(), 1).unwrap().is_sufficient());
+        assert!(!SpaceCheck::new(dir.path(), u64::MAX).unwrap().is_sufficient());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_same_filesystem() {
+        let dir = tempdir().unwrap();
+        assert!(same_filesystem(dir.path(), &dir.path().join("missing")));
+    }
+}
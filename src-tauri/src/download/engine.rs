@@ -7,7 +7,11 @@ use futures_util::StreamExt;
 use tauri::ipc::Channel;
 use tokio::io::AsyncWriteExt;
 
-use crate::download::{DownloadError, progress::{DownloadEvent, ProgressTracker}};
+use crate::download::{
+    DownloadError,
+    progress::{DownloadEvent, ProgressTracker},
+    sniff::{check_content, read_head, ExpectedFormat},
+};
 
 /// Download a file from a URL with progress reporting
 ///
@@ -57,6 +61,19 @@ pub async fn download_file(
         return Err(DownloadError::ChannelError("Failed to send Started event".to_string()));
     }
 
+    // Reject HTML pages and other unexpected content before creating the file
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let mut stream = response.bytes_stream();
+    let head = read_head(&mut stream).await?;
+    if let Err(e) = check_content(ExpectedFormat::from_path(dest_path), content_type.as_deref(), &head) {
+        let _ = on_event.send(progress_tracker.failed_event(e.to_string()));
+        return Err(e);
+    }
+
     // Create parent directories if they don't exist
     if let Some(parent) = dest_path.parent() {
         tokio::fs::create_dir_all(parent).await?;
@@ -71,8 +88,13 @@ pub async fn download_file(
         }
     };
 
-    // Stream the response body
-    let mut stream = response.bytes_stream();
+    if let Err(e) = file.write_all(&head).await {
+        let _ = on_event.send(progress_tracker.failed_event(e.to_string()));
+        return Err(DownloadError::IoError(e));
+    }
+    progress_tracker.update(head.len() as u64);
+
+    // Stream the rest of the response body
 
     while let Some(chunk_result) = stream.next().await {
         match chunk_result {
@@ -1,19 +1,24 @@
 //! Download manager with parallel execution
 //!
 //! Provides a centralized download manager that limits concurrent downloads
-//! using a Semaphore, resolves URLs via providers, and downloads with resume support.
+//! using a Semaphore, resolves URLs via providers, checks free disk space and
+//! downloads with resume support. Running downloads can be cancelled by id.
 
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use tauri::ipc::Channel;
 use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
 
 use crate::download::{
     progress::DownloadEvent,
-    providers::{GoogleDriveProvider, MediafireProvider, DropboxProvider, TransferProvider, MegaProvider, DirectDownloadInfo, DownloadProvider},
+    providers::{select_folder_entry, DownloadProvider, FolderEntry, ProviderRegistry},
     resume::download_with_resume,
     DownloadError,
 };
+use crate::disk::SpaceCheck;
 use crate::models::DownloadProvider as ProviderType;
 
 /// Maximum number of concurrent downloads
@@ -24,12 +29,43 @@ const MAX_CONCURRENT_DOWNLOADS: usize = 3;
 /// This struct manages parallel downloads with:
 /// - Shared HTTP client for connection pooling
 /// - Semaphore-based concurrency limiting
-/// - Provider-based URL resolution
+/// - Provider-based URL resolution through a shared registry
+/// - Disk space preflight that accounts for downloads already in flight
 /// - Resume support for interrupted downloads
+/// - Cancellation of queued or running downloads
 #[derive(Clone)]
 pub struct DownloadManager {
     client: reqwest::Client,
     semaphore: Arc<Semaphore>,
+    registry: Arc<ProviderRegistry>,
+    /// Bytes still expected by running downloads
+    reserved: Arc<AtomicU64>,
+    /// Cancellation tokens of queued and running downloads by id
+    cancellations: Arc<Mutex<HashMap<String, CancellationToken>>>,
+}
+
+/// Forgets a download's cancellation token when dropped
+struct CancellationGuard {
+    cancellations: Arc<Mutex<HashMap<String, CancellationToken>>>,
+    download_id: String,
+}
+
+impl Drop for CancellationGuard {
+    fn drop(&mut self) {
+        self.cancellations.lock().unwrap().remove(&self.download_id);
+    }
+}
+
+/// Releases a download's space reservation when dropped
+struct SpaceReservation {
+    reserved: Arc<AtomicU64>,
+    bytes: u64,
+}
+
+impl Drop for SpaceReservation {
+    fn drop(&mut self) {
+        self.reserved.fetch_sub(self.bytes, Ordering::SeqCst);
+    }
 }
 
 impl Default for DownloadManager {
@@ -53,23 +89,31 @@ impl DownloadManager {
             .expect("Failed to create HTTP client");
 
         Self {
+            registry: Arc::new(ProviderRegistry::with_defaults(client.clone())),
             client,
             semaphore: Arc::new(Semaphore::new(MAX_CONCURRENT_DOWNLOADS)),
+            reserved: Arc::new(AtomicU64::new(0)),
+            cancellations: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
     /// Start a download, acquiring semaphore permit for concurrency limiting
     ///
     /// # Arguments
-    /// * `share_url` - The share URL to download from (Google Drive or Mediafire)
+    /// * `share_url` - The share URL to download from
     /// * `provider_type` - Which provider to use for URL resolution
     /// * `dest_dir` - Directory to save the downloaded file
     /// * `download_id` - Unique identifier for this download
     /// * `on_event` - Channel to send progress events
     /// * `target_filename` - Optional custom filename (e.g., "Patch-A.mpq")
+    /// * `expected_size` - Size from the catalog, used when the provider
+    ///   does not report a content length
+    /// * `folder_file` - Name or glob of the file to take from a folder link
     ///
     /// # Returns
-    /// The full path to the downloaded file on success
+    /// The full path to the downloaded file on success, or
+    /// `DownloadError::Cancelled` if `cancel` was called for `download_id`
+    #[allow(clippy::too_many_arguments)]
     pub async fn download(
         &self,
         share_url: String,
@@ -78,24 +122,39 @@ impl DownloadManager {
         download_id: String,
         on_event: Channel<DownloadEvent>,
         target_filename: Option<String>,
+        expected_size: Option<u64>,
+        folder_file: Option<String>,
     ) -> Result<String, DownloadError> {
         log::info!("[Download] Starting download for: {}", share_url);
         log::info!("[Download] Provider: {:?}", provider_type);
         log::info!("[Download] Dest dir: {:?}", dest_dir);
 
+        let (cancel, _guard) = self.register_cancellation(&download_id);
+
         // Acquire semaphore permit (blocks if MAX_CONCURRENT reached)
-        let _permit = self
-            .semaphore
-            .clone()
-            .acquire_owned()
-            .await
-            .map_err(|e| DownloadError::ProviderError(format!("Semaphore error: {}", e)))?;
+        let _permit = tokio::select! {
+            _ = cancel.cancelled() => return Err(DownloadError::Cancelled),
+            permit = self.semaphore.clone().acquire_owned() => permit
+                .map_err(|e| DownloadError::ProviderError(format!("Semaphore error: {}", e)))?,
+        };
 
         log::info!("[Download] Acquired semaphore permit");
 
         // Resolve direct URL based on provider
         log::info!("[Download] Resolving URL...");
-        let info = match self.resolve_url(&share_url, provider_type).await {
+        let provider = self.provider(provider_type)?;
+        log::info!("[Download] Using {} (resume: {})", provider.name(), provider.supports_resume());
+
+        // Folder links are narrowed down to the one file the link names
+        let share_url = if provider.is_folder_url(&share_url) {
+            let entries = provider.list_folder(&share_url).await?;
+            let entry = select_folder_entry(&entries, folder_file.as_deref())?;
+            log::info!("[Download] Selected {} from folder", entry.name);
+            entry.url
+        } else {
+            share_url
+        };
+        let info = match provider.resolve_direct_url(&share_url).await {
             Ok(info) => {
                 log::info!("[Download] Resolved URL: {}", info.url);
                 log::info!("[Download] File name: {:?}", info.file_name);
@@ -122,10 +181,13 @@ impl DownloadManager {
         });
         let dest_path = dest_dir.join(&file_name);
 
-        // MEGA downloads need special handling - use the mega crate directly
-        if provider_type == ProviderType::Mega {
-            let provider = MegaProvider::new(self.client.clone());
+        // Fail before writing anything if the file cannot fit
+        let _reservation = self
+            .reserve_space(&dest_path, info.content_length.or(expected_size))
+            .await?;
 
+        // Providers like MEGA download (and decrypt) files themselves
+        if provider.streams_itself() {
             // Send started event
             let _ = on_event.send(DownloadEvent::Started {
                 download_id: download_id.clone(),
@@ -133,8 +195,9 @@ impl DownloadManager {
                 total_bytes: info.content_length.unwrap_or(0),
             });
 
-            // Download using MEGA provider
-            let _bytes_downloaded = provider.download_to_file(&share_url, &dest_path).await?;
+            let _bytes_downloaded = provider
+                .download_to(&share_url, &dest_path, download_id.clone(), on_event.clone(), &cancel)
+                .await?;
 
             // Send completed event
             let _ = on_event.send(DownloadEvent::Completed {
@@ -146,44 +209,96 @@ impl DownloadManager {
         }
 
         // Perform download with resume support for other providers
-        download_with_resume(&self.client, &info.url, &dest_path, download_id, on_event).await?;
+        download_with_resume(&self.client, &info.url, &dest_path, download_id, on_event, &cancel).await?;
 
         Ok(dest_path.to_string_lossy().to_string())
     }
 
-    /// Resolve share URL to direct download URL
+    /// Check free space for a download and reserve it until the download ends
+    ///
+    /// Bytes already on disk from an interrupted download are subtracted;
+    /// bytes still expected by other running downloads are added, since
+    /// they will land on the same filesystem in parallel. Unknown sizes
+    /// skip the check.
+    async fn reserve_space(
+        &self,
+        dest_path: &Path,
+        total_size: Option<u64>,
+    ) -> Result<Option<SpaceReservation>, DownloadError> {
+        let Some(total_size) = total_size else {
+            log::info!("[Download] Size unknown, skipping disk space check");
+            return Ok(None);
+        };
+
+        let existing = tokio::fs::metadata(dest_path).await.map(|m| m.len()).unwrap_or(0);
+        let remaining = total_size.saturating_sub(existing);
+        let in_flight = self.reserved.fetch_add(remaining, Ordering::SeqCst);
+        let reservation = SpaceReservation { reserved: self.reserved.clone(), bytes: remaining };
+
+        let dir = dest_path.parent().unwrap_or(dest_path);
+        let check = SpaceCheck::new(dir, remaining + in_flight)?;
+        log::info!("[Download] Disk space: {} needed, {} available", check.needed, check.available);
+        if !check.is_sufficient() {
+            return Err(check.into());
+        }
+
+        Ok(Some(reservation))
+    }
+
+    /// Create the cancellation token for a download
     ///
-    /// Dispatches to the appropriate provider based on provider_type.
-    async fn resolve_url(
+    /// The token is forgotten when the returned guard is dropped.
+    fn register_cancellation(&self, download_id: &str) -> (CancellationToken, CancellationGuard) {
+        let token = CancellationToken::new();
+        self.cancellations
+            .lock()
+            .unwrap()
+            .insert(download_id.to_string(), token.clone());
+        let guard = CancellationGuard {
+            cancellations: self.cancellations.clone(),
+            download_id: download_id.to_string(),
+        };
+        (token, guard)
+    }
+
+    /// Cancel a queued or running download
+    ///
+    /// The partial file is kept so that starting the download again resumes
+    /// it. Returns false if no download with that id is active.
+    pub fn cancel(&self, download_id: &str) -> bool {
+        match self.cancellations.lock().unwrap().get(download_id) {
+            Some(token) => {
+                log::info!("[Download] Cancelling {}", download_id);
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// List the files in a folder link
+    pub async fn list_folder(
         &self,
         share_url: &str,
         provider_type: ProviderType,
-    ) -> Result<DirectDownloadInfo, DownloadError> {
-        match provider_type {
-            ProviderType::GoogleDrive => {
-                let provider = GoogleDriveProvider::new(self.client.clone());
-                provider.resolve_direct_url(share_url).await
-            }
-            ProviderType::Mediafire => {
-                let provider = MediafireProvider::new(self.client.clone());
-                provider.resolve_direct_url(share_url).await
-            }
-            ProviderType::Dropbox => {
-                let provider = DropboxProvider::new(self.client.clone());
-                provider.resolve_direct_url(share_url).await
-            }
-            ProviderType::Transfer => {
-                let provider = TransferProvider::new(self.client.clone());
-                provider.resolve_direct_url(share_url).await
-            }
-            ProviderType::Mega => {
-                let provider = MegaProvider::new(self.client.clone());
-                provider.resolve_direct_url(share_url).await
-            }
-            ProviderType::Unknown => Err(DownloadError::ProviderError(
-                "Unknown download provider".to_string(),
-            )),
+    ) -> Result<Vec<FolderEntry>, DownloadError> {
+        let provider = self.provider(provider_type)?;
+        if !provider.is_folder_url(share_url) {
+            return Err(DownloadError::ProviderError(format!("Not a {} folder link: {}", provider.name(), share_url)));
         }
+        provider.list_folder(share_url).await
+    }
+
+    /// Get the registry used to parse provider names and classify URLs
+    pub fn registry(&self) -> &ProviderRegistry {
+        &self.registry
+    }
+
+    /// Look up the provider for a type
+    fn provider(&self, provider_type: ProviderType) -> Result<Arc<dyn DownloadProvider>, DownloadError> {
+        self.registry
+            .get(provider_type)
+            .ok_or_else(|| DownloadError::ProviderError("Unknown download provider".to_string()))
     }
 
     /// Get current number of active downloads
@@ -218,6 +333,90 @@ mod tests {
         assert_eq!(cloned.active_downloads(), 0);
     }
 
+    #[tokio::test]
+    async fn test_reserve_space_tracks_in_flight_downloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = DownloadManager::new();
+        let dest = dir.path().join("Patch-A.mpq");
+
+        // Unknown size skips the check
+        assert!(manager.reserve_space(&dest, None).await.unwrap().is_none());
+
+        let reservation = manager.reserve_space(&dest, Some(1024)).await.unwrap();
+        assert_eq!(manager.reserved.load(Ordering::SeqCst), 1024);
+        drop(reservation);
+        assert_eq!(manager.reserved.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_reserve_space_rejects_oversized_download() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = DownloadManager::new();
+        let dest = dir.path().join("Patch-A.mpq");
+
+        let result = manager.reserve_space(&dest, Some(u64::MAX / 2)).await;
+        assert!(matches!(result, Err(DownloadError::InsufficientSpace { .. })));
+        assert_eq!(manager.reserved.load(Ordering::SeqCst), 0);
+        assert!(!dest.exists());
+    }
+
+    #[tokio::test]
+    async fn test_reserve_space_subtracts_partial_download() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = DownloadManager::new();
+        let dest = dir.path().join("Patch-A.mpq");
+        tokio::fs::write(&dest, vec![0u8; 100]).await.unwrap();
+
+        let _reservation = manager.reserve_space(&dest, Some(150)).await.unwrap();
+        assert_eq!(manager.reserved.load(Ordering::SeqCst), 50);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_registered_download() {
+        let manager = DownloadManager::new();
+        assert!(!manager.cancel("missing"));
+
+        let (token, guard) = manager.register_cancellation("dl-1");
+        assert!(manager.clone().cancel("dl-1"));
+        assert!(token.is_cancelled());
+
+        drop(guard);
+        assert!(!manager.cancel("dl-1"));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_queued_download() {
+        let manager = DownloadManager::new();
+        let dir = tempfile::tempdir().unwrap();
+
+        // Fill every slot so the next download waits for a permit
+        let _permits = manager.semaphore.clone().acquire_many_owned(MAX_CONCURRENT_DOWNLOADS as u32).await.unwrap();
+
+        let task = {
+            let manager = manager.clone();
+            let dest = dir.path().to_path_buf();
+            tokio::spawn(async move {
+                manager
+                    .download(
+                        "https://example.com/Patch-A.mpq".to_string(),
+                        ProviderType::Http,
+                        dest,
+                        "queued".to_string(),
+                        Channel::new(|_| Ok(())),
+                        None,
+                        None,
+                        None,
+                    )
+                    .await
+            })
+        };
+
+        while !manager.cancel("queued") {
+            tokio::task::yield_now().await;
+        }
+        assert!(matches!(task.await.unwrap(), Err(DownloadError::Cancelled)));
+    }
+
     #[test]
     fn test_active_downloads_starts_at_zero() {
         let manager = DownloadManager::new();
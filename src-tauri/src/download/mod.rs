@@ -3,20 +3,28 @@
 //! This module provides:
 //! - Streaming download engine for memory-efficient large file downloads
 //! - Progress tracking with throttled event emission
-//! - Provider abstraction for different download hosts (Google Drive, Mediafire)
+//! - Content sniffing that rejects HTML pages served instead of files
+//! - Provider abstraction for different download hosts (Google Drive, Mediafire,
+//!   GitHub releases, plain HTTP, ...)
 
 pub mod engine;
 pub mod manager;
 pub mod progress;
 pub mod providers;
 pub mod resume;
+pub mod sniff;
+#[cfg(test)]
+pub(crate) mod test_server;
 
+use std::path::PathBuf;
 use thiserror::Error;
 
+use crate::disk::SpaceCheck;
+
 // Re-export key types
 pub use engine::download_file;
 pub use progress::{DownloadEvent, ProgressTracker};
-pub use providers::{DirectDownloadInfo, DownloadProvider};
+pub use providers::{classify_url, DirectDownloadInfo, DownloadProvider, FolderEntry};
 pub use resume::download_with_resume;
 pub use manager::DownloadManager;
 
@@ -50,4 +58,51 @@ pub enum DownloadError {
     /// Mediafire direct URL could not be resolved
     #[error("Direct download URL not found in provider page")]
     DirectUrlNotFound,
+
+    /// The host's download quota for the file is used up
+    #[error("{0} download quota exceeded for this file, try again later")]
+    QuotaExceeded(String),
+
+    /// The host refused access (private or removed file, sign-in required)
+    #[error("{0} denied access to the file; it may be private or require sign-in")]
+    AccessDenied(String),
+
+    /// The server sent something other than the expected file
+    /// (e.g. an HTML login, captcha or quota page)
+    #[error("Expected {expected} but received {content_type}: {snippet}")]
+    UnexpectedContent {
+        expected: String,
+        content_type: String,
+        snippet: String,
+    },
+
+    /// A folder link did not name exactly one of its files
+    #[error("{matches} files in folder match '{pattern}' (folder contains: {})", available.join(", "))]
+    FolderSelection {
+        pattern: String,
+        matches: usize,
+        available: Vec<String>,
+    },
+
+    /// Download was cancelled by the user
+    #[error("Download cancelled")]
+    Cancelled,
+
+    /// Not enough free space for the expected download size
+    #[error("Not enough disk space on {path:?}: {needed} bytes needed, {available} available")]
+    InsufficientSpace {
+        needed: u64,
+        available: u64,
+        path: PathBuf,
+    },
+}
+
+impl From<SpaceCheck> for DownloadError {
+    fn from(check: SpaceCheck) -> Self {
+        DownloadError::InsufficientSpace {
+            needed: check.needed,
+            available: check.available,
+            path: check.path,
+        }
+    }
 }
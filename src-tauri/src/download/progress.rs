@@ -37,6 +37,10 @@ pub enum DownloadEvent {
         download_id: String,
         error: String,
     },
+    /// Download was cancelled; any partial file is kept for resuming
+    Cancelled {
+        download_id: String,
+    },
 }
 
 /// Tracks download progress and throttles event emission
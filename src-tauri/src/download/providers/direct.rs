@@ -0,0 +1,219 @@
+//! Direct HTTP(S) provider implementation
+//!
+//! Handles plain links on web servers without a dedicated provider. A HEAD
+//! request (following redirects) provides the size, Range support and the
+//! file name from Content-Disposition. Servers that reject HEAD are probed
+//! with a one-byte ranged GET instead.
+
+use async_trait::async_trait;
+use reqwest::header::{ACCEPT_RANGES, CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_RANGE, RANGE};
+
+use super::{DirectDownloadInfo, DownloadProvider};
+use crate::download::DownloadError;
+use crate::models::DownloadProvider as ProviderType;
+
+/// Direct HTTP(S) download provider
+pub struct DirectHttpProvider {
+    client: reqwest::Client,
+}
+
+impl DirectHttpProvider {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    /// Probe a URL with a one-byte ranged GET
+    async fn probe_with_range(&self, url: &str) -> Result<DirectDownloadInfo, DownloadError> {
+        let response = self.client.get(url).header(RANGE, "bytes=0-0").send().await?;
+        if !response.status().is_success() {
+            return Err(DownloadError::HttpError(response.status()));
+        }
+
+        let headers = response.headers();
+        let total = headers
+            .get(CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit('/').next())
+            .and_then(|v| v.parse().ok());
+        let supports_range = response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+
+        Ok(DirectDownloadInfo {
+            url: response.url().to_string(),
+            file_name: file_name_from_headers(headers),
+            content_length: if supports_range { total } else { response.content_length() },
+            supports_range,
+        })
+    }
+}
+
+/// Get the file name from a Content-Disposition header, if any
+pub fn file_name_from_headers(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    headers
+        .get(CONTENT_DISPOSITION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_content_disposition)
+}
+
+/// Parse the file name from a Content-Disposition value
+///
+/// Prefers the RFC 5987 `filename*=UTF-8''...` form over `filename=`.
+/// Path components are stripped so a server cannot pick the directory.
+pub fn parse_content_disposition(value: &str) -> Option<String> {
+    let mut plain = None;
+    let mut extended = None;
+
+    for param in value.split(';').map(str::trim) {
+        let Some((key, val)) = param.split_once('=') else {
+            continue;
+        };
+        match key.trim().to_ascii_lowercase().as_str() {
+            "filename*" => {
+                let encoded = val.trim().splitn(3, '\'').nth(2).unwrap_or(val.trim());
+                extended = Some(percent_decode(encoded));
+            }
+            "filename" => plain = Some(val.trim().trim_matches('"').to_string()),
+            _ => {}
+        }
+    }
+
+    extended
+        .or(plain)
+        .map(|name| name.rsplit(['/', '\\']).next().unwrap_or("").to_string())
+        .filter(|name| !name.is_empty() && name != "." && name != "..")
+}
+
+/// Decode %XX escapes (invalid escapes are kept as-is)
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+#[async_trait]
+impl DownloadProvider for DirectHttpProvider {
+    async fn resolve_direct_url(&self, share_url: &str) -> Result<DirectDownloadInfo, DownloadError> {
+        log::info!("[Direct] URL: {}", share_url);
+
+        let response = match self.client.head(share_url).send().await {
+            Ok(response) if response.status().is_success() => response,
+            Ok(response) => {
+                log::info!("[Direct] HEAD returned {}, probing with GET", response.status());
+                return self.probe_with_range(share_url).await;
+            }
+            Err(e) => {
+                log::info!("[Direct] HEAD failed ({}), probing with GET", e);
+                return self.probe_with_range(share_url).await;
+            }
+        };
+
+        let headers = response.headers();
+        let supports_range = headers
+            .get(ACCEPT_RANGES)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.eq_ignore_ascii_case("bytes"))
+            .unwrap_or(false);
+
+        // Read the header directly: reqwest reports 0 for bodiless HEAD responses
+        let content_length = headers
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok())
+            .filter(|len: &u64| *len > 0);
+
+        Ok(DirectDownloadInfo {
+            url: response.url().to_string(),
+            file_name: file_name_from_headers(headers),
+            content_length,
+            supports_range,
+        })
+    }
+
+    fn supports_resume(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "Direct HTTP"
+    }
+
+    fn provider_type(&self) -> ProviderType {
+        ProviderType::Http
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["http", "https", "direct"]
+    }
+
+    /// Plain links are the fallback for any http(s) URL, never a match
+    fn url_hosts(&self) -> &'static [&'static str] {
+        &[]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::download::test_server::{serve, Route};
+
+    #[test]
+    fn test_parse_content_disposition() {
+        assert_eq!(
+            parse_content_disposition(r#"attachment; filename="Patch-A.mpq""#),
+            Some("Patch-A.mpq".to_string())
+        );
+        assert_eq!(
+            parse_content_disposition("attachment; filename=patch.zip; size=10"),
+            Some("patch.zip".to_string())
+        );
+        assert_eq!(
+            parse_content_disposition(
+                r#"attachment; filename="fallback.mpq"; filename*=UTF-8''Patch%20A.mpq"#
+            ),
+            Some("Patch A.mpq".to_string())
+        );
+        assert_eq!(
+            parse_content_disposition(r#"attachment; filename="../../evil.dll""#),
+            Some("evil.dll".to_string())
+        );
+        assert_eq!(parse_content_disposition("inline"), None);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_uses_head() {
+        let (base, _) = serve(vec![(
+            "/files/patch.bin",
+            Route::ok(vec![0u8; 1234])
+                .header("Accept-Ranges", "bytes")
+                .header("Content-Disposition", r#"attachment; filename="Patch-A.mpq""#),
+        )])
+        .await;
+
+        let provider = DirectHttpProvider::new(reqwest::Client::new());
+        let info = provider.resolve_direct_url(&format!("{}/files/patch.bin", base)).await.unwrap();
+
+        assert_eq!(info.content_length, Some(1234));
+        assert!(info.supports_range);
+        assert_eq!(info.file_name.as_deref(), Some("Patch-A.mpq"));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_missing_file_fails() {
+        let (base, _) = serve(vec![]).await;
+        let provider = DirectHttpProvider::new(reqwest::Client::new());
+        let result = provider.resolve_direct_url(&format!("{}/missing.mpq", base)).await;
+        assert!(matches!(result, Err(DownloadError::HttpError(_))));
+    }
+}
@@ -5,6 +5,7 @@
 use async_trait::async_trait;
 use super::{DirectDownloadInfo, DownloadProvider};
 use crate::download::DownloadError;
+use crate::models::DownloadProvider as ProviderType;
 
 /// Dropbox download provider
 pub struct DropboxProvider {
@@ -70,4 +71,12 @@ impl DownloadProvider for DropboxProvider {
     fn name(&self) -> &'static str {
         "Dropbox"
     }
+
+    fn provider_type(&self) -> ProviderType {
+        ProviderType::Dropbox
+    }
+
+    fn url_hosts(&self) -> &'static [&'static str] {
+        &["dropbox.com", "dropboxusercontent.com"]
+    }
 }
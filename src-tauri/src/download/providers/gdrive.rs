@@ -2,15 +2,19 @@
 //!
 //! Resolves Google Drive share URLs to direct download URLs, handling:
 //! - Various URL formats (file/d/, open?id=, uc?id=)
-//! - Virus scan confirmation page for large files (>100MB)
+//! - Virus scan confirmation page for large files (>100MB), both the legacy
+//!   `uc?confirm=` link and the `drive.usercontent.google.com/download` form
+//!   with `uuid` and `at` fields
+//! - Quota exceeded, access denied and not found pages, reported as errors
 
 use async_trait::async_trait;
 use lazy_static::lazy_static;
 use regex::Regex;
 use scraper::{Html, Selector};
 
-use super::{DirectDownloadInfo, DownloadProvider};
+use super::{host_matches, DirectDownloadInfo, DownloadProvider};
 use crate::download::DownloadError;
+use crate::models::DownloadProvider as ProviderType;
 
 lazy_static! {
     /// Matches: drive.google.com/file/d/FILE_ID/...
@@ -23,6 +27,50 @@ lazy_static! {
     static ref CONFIRM_REGEX: Regex = Regex::new(r"confirm=([0-9A-Za-z_-]+)").unwrap();
 }
 
+/// Provider name used in errors
+const PROVIDER_NAME: &str = "Google Drive";
+
+/// Kind of HTML page Google Drive returned instead of the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DrivePage {
+    /// "Can't scan this file for viruses" page with a download anyway button
+    VirusScanWarning,
+    /// Too many downloads of the file recently
+    QuotaExceeded,
+    /// "You need access", sign-in or 403 page
+    AccessDenied,
+    /// The file was deleted or the ID is wrong
+    NotFound,
+    /// Anything else
+    Other,
+}
+
+impl DrivePage {
+    /// Classify a page by the messages Google shows on it
+    fn classify(html: &str) -> Self {
+        let text = html.to_lowercase();
+        let has = |markers: &[&str]| markers.iter().any(|m| text.contains(m));
+
+        if has(&["quota exceeded", "too many users have viewed or downloaded this file"]) {
+            DrivePage::QuotaExceeded
+        } else if has(&["the file you have requested does not exist", "page not found"]) {
+            DrivePage::NotFound
+        } else if has(&[
+            "you need access",
+            "request access",
+            "you do not have access",
+            "accounts.google.com/servicelogin",
+            "error 403",
+        ]) {
+            DrivePage::AccessDenied
+        } else if has(&["uc-download-link", "download-form", "download anyway", "confirm="]) {
+            DrivePage::VirusScanWarning
+        } else {
+            DrivePage::Other
+        }
+    }
+}
+
 /// Google Drive download provider
 ///
 /// Handles resolving Google Drive share URLs to direct download URLs,
@@ -84,6 +132,12 @@ impl GoogleDriveProvider {
 
         log::info!("[GDrive] Response status: {}", response.status());
 
+        // Private files redirect to the sign-in page
+        if response.url().host_str() == Some("accounts.google.com") {
+            return Err(DownloadError::AccessDenied(PROVIDER_NAME.to_string()));
+        }
+
+        let status = response.status();
         let content_type = response
             .headers()
             .get("content-type")
@@ -95,9 +149,18 @@ impl GoogleDriveProvider {
         // If we got HTML, it's the virus scan warning page
         if content_type.contains("text/html") {
             log::info!("[GDrive] Got HTML response, parsing confirmation page...");
+            // Relative links resolve against the host that served the page
+            let page_url = response.url().clone();
             let html = response.text().await?;
             log::info!("[GDrive] HTML length: {} bytes", html.len());
-            return self.parse_confirmation_page(&html, file_id);
+            return self.parse_confirmation_page(&html, file_id, &page_url);
+        }
+
+        if status == reqwest::StatusCode::FORBIDDEN {
+            return Err(DownloadError::AccessDenied(PROVIDER_NAME.to_string()));
+        }
+        if !status.is_success() {
+            return Err(DownloadError::HttpError(status));
         }
 
         // No confirmation needed - extract info from response
@@ -120,65 +183,76 @@ impl GoogleDriveProvider {
     }
 
     /// Parse the virus scan warning page to extract the confirmation URL
+    ///
+    /// Quota exceeded, access denied and not found pages are turned into
+    /// their own errors instead. Relative links and form actions are
+    /// resolved against `page_url`, the URL the page was served from.
     fn parse_confirmation_page(
         &self,
         html: &str,
         file_id: &str,
+        page_url: &url::Url,
     ) -> Result<DirectDownloadInfo, DownloadError> {
+        match DrivePage::classify(html) {
+            DrivePage::QuotaExceeded => {
+                log::info!("[GDrive] Quota exceeded page");
+                return Err(DownloadError::QuotaExceeded(PROVIDER_NAME.to_string()));
+            }
+            DrivePage::AccessDenied => {
+                log::info!("[GDrive] Access denied page");
+                return Err(DownloadError::AccessDenied(PROVIDER_NAME.to_string()));
+            }
+            DrivePage::NotFound => {
+                return Err(DownloadError::ProviderError(format!(
+                    "Google Drive file {} does not exist",
+                    file_id
+                )));
+            }
+            DrivePage::VirusScanWarning | DrivePage::Other => {}
+        }
+
         let document = Html::parse_document(html);
+        let file_name = Self::extract_filename_from_page(&document);
 
         // Method 1: Look for download link with confirm parameter
         let link_selector = Selector::parse("a[href*='confirm=']").unwrap();
         if let Some(element) = document.select(&link_selector).next() {
             if let Some(href) = element.value().attr("href") {
-                let url = if href.starts_with('/') {
-                    format!("https://drive.google.com{}", href)
-                } else if href.starts_with("http") {
-                    href.to_string()
-                } else {
-                    return Err(DownloadError::ConfirmationFailed);
-                };
+                let url = page_url.join(href).map_err(|_| DownloadError::ConfirmationFailed)?;
 
                 return Ok(DirectDownloadInfo {
-                    url,
-                    file_name: None,
+                    url: url.to_string(),
+                    file_name,
                     content_length: None,
                     supports_range: true, // Assume yes after confirmation
                 });
             }
         }
 
-        // Method 2: Look for form with action containing download URL
-        let form_selector = Selector::parse("form[action*='/uc']").unwrap();
+        // Method 2: Submit the download form with its hidden fields
+        // (`confirm`, plus `uuid` and `at` on drive.usercontent.google.com)
+        let form_selector =
+            Selector::parse("form#download-form, form[action*='/uc'], form[action*='/download']").unwrap();
         if let Some(form) = document.select(&form_selector).next() {
             if let Some(action) = form.value().attr("action") {
-                // Check for hidden input fields with confirm parameter
-                let input_selector = Selector::parse("input[name='confirm']").unwrap();
-                if let Some(confirm_input) = document.select(&input_selector).next() {
-                    if let Some(confirm_value) = confirm_input.value().attr("value") {
-                        let url = format!(
-                            "https://drive.google.com/uc?export=download&confirm={}&id={}",
-                            confirm_value, file_id
-                        );
-                        return Ok(DirectDownloadInfo {
-                            url,
-                            file_name: None,
-                            content_length: None,
-                            supports_range: true,
-                        });
-                    }
+                let mut url = page_url.join(action).map_err(|_| DownloadError::ConfirmationFailed)?;
+
+                let input_selector = Selector::parse("input[type='hidden'][name]").unwrap();
+                let fields: Vec<(&str, &str)> = form
+                    .select(&input_selector)
+                    .filter_map(|input| Some((input.value().attr("name")?, input.value().attr("value")?)))
+                    .collect();
+                let existing: Vec<String> = url.query_pairs().map(|(k, _)| k.into_owned()).collect();
+                url.query_pairs_mut().extend_pairs(
+                    fields.iter().filter(|(name, _)| !existing.iter().any(|k| k == name)),
+                );
+                if !url.query_pairs().any(|(k, _)| k == "id") {
+                    url.query_pairs_mut().append_pair("id", file_id);
                 }
 
-                // Fallback: use form action directly
-                let url = if action.starts_with('/') {
-                    format!("https://drive.google.com{}", action)
-                } else {
-                    action.to_string()
-                };
-
                 return Ok(DirectDownloadInfo {
-                    url,
-                    file_name: None,
+                    url: url.to_string(),
+                    file_name,
                     content_length: None,
                     supports_range: true,
                 });
@@ -193,7 +267,7 @@ impl GoogleDriveProvider {
             );
             return Ok(DirectDownloadInfo {
                 url,
-                file_name: None,
+                file_name,
                 content_length: None,
                 supports_range: true,
             });
@@ -202,6 +276,16 @@ impl GoogleDriveProvider {
         Err(DownloadError::ConfirmationFailed)
     }
 
+    /// Extract the file name shown on the virus scan warning page
+    fn extract_filename_from_page(document: &Html) -> Option<String> {
+        let selector = Selector::parse(".uc-name-size a").unwrap();
+        document
+            .select(&selector)
+            .next()
+            .map(|a| a.text().collect::<String>().trim().to_string())
+            .filter(|name| !name.is_empty())
+    }
+
     /// Extract filename from Content-Disposition header
     fn extract_filename_from_headers(
         headers: &reqwest::header::HeaderMap,
@@ -251,7 +335,23 @@ impl DownloadProvider for GoogleDriveProvider {
 
     /// Provider name for logging and display
     fn name(&self) -> &'static str {
-        "Google Drive"
+        PROVIDER_NAME
+    }
+
+    fn provider_type(&self) -> ProviderType {
+        ProviderType::GoogleDrive
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["googledrive", "google_drive", "gdrive"]
+    }
+
+    fn url_hosts(&self) -> &'static [&'static str] {
+        &["drive.google.com", "docs.google.com", "drive.usercontent.google.com"]
+    }
+
+    fn matches_url(&self, url: &str) -> bool {
+        host_matches(url, self.url_hosts()) || Self::extract_file_id(url).is_some()
     }
 }
 
@@ -336,7 +436,7 @@ mod tests {
         </html>
         "#;
 
-        let result = provider.parse_confirmation_page(html, "FILE123");
+        let result = provider.parse_confirmation_page(html, "FILE123", &drive_page("FILE123"));
         assert!(result.is_ok());
         let info = result.unwrap();
         assert!(info.url.contains("confirm=ABC123"));
@@ -355,7 +455,7 @@ mod tests {
         </html>
         "#;
 
-        let result = provider.parse_confirmation_page(html, "FILE123");
+        let result = provider.parse_confirmation_page(html, "FILE123", &drive_page("FILE123"));
         assert!(result.is_ok());
         let info = result.unwrap();
         assert_eq!(
@@ -380,7 +480,7 @@ mod tests {
         </html>
         "#;
 
-        let result = provider.parse_confirmation_page(html, "FILE123");
+        let result = provider.parse_confirmation_page(html, "FILE123", &drive_page("FILE123"));
         assert!(result.is_ok());
         let info = result.unwrap();
         assert!(info.url.contains("confirm=TOKEN123"));
@@ -401,7 +501,7 @@ mod tests {
         </html>
         "#;
 
-        let result = provider.parse_confirmation_page(html, "FILE123");
+        let result = provider.parse_confirmation_page(html, "FILE123", &drive_page("FILE123"));
         assert!(result.is_ok());
         let info = result.unwrap();
         assert!(info.url.contains("confirm=REGEX_TOKEN"));
@@ -420,7 +520,7 @@ mod tests {
         </html>
         "#;
 
-        let result = provider.parse_confirmation_page(html, "FILE123");
+        let result = provider.parse_confirmation_page(html, "FILE123", &drive_page("FILE123"));
         assert!(result.is_err());
     }
 
@@ -437,4 +537,108 @@ mod tests {
         let provider = GoogleDriveProvider::new(client);
         assert!(provider.supports_resume());
     }
+
+    /// Real-world pages saved from Google Drive (trimmed)
+    mod fixtures {
+        pub const VIRUS_SCAN_LEGACY: &str = include_str!("fixtures/gdrive/virus_scan_legacy.html");
+        pub const VIRUS_SCAN_USERCONTENT: &str = include_str!("fixtures/gdrive/virus_scan_usercontent.html");
+        pub const VIRUS_SCAN_USERCONTENT_RELATIVE: &str =
+            include_str!("fixtures/gdrive/virus_scan_usercontent_relative.html");
+        pub const QUOTA_EXCEEDED: &str = include_str!("fixtures/gdrive/quota_exceeded.html");
+        pub const ACCESS_DENIED: &str = include_str!("fixtures/gdrive/access_denied.html");
+        pub const FORBIDDEN: &str = include_str!("fixtures/gdrive/forbidden.html");
+        pub const NOT_FOUND: &str = include_str!("fixtures/gdrive/not_found.html");
+    }
+
+    const FIXTURE_ID: &str = "1AbCdEfGhIjKlMnOpQrStUvWxYz012345";
+
+    /// URL a confirmation page for `file_id` is first served from
+    fn drive_page(file_id: &str) -> url::Url {
+        url::Url::parse(&GoogleDriveProvider::get_direct_url(file_id)).unwrap()
+    }
+
+    #[test]
+    fn test_classify_fixture_pages() {
+        assert_eq!(DrivePage::classify(fixtures::VIRUS_SCAN_LEGACY), DrivePage::VirusScanWarning);
+        assert_eq!(DrivePage::classify(fixtures::VIRUS_SCAN_USERCONTENT), DrivePage::VirusScanWarning);
+        assert_eq!(DrivePage::classify(fixtures::QUOTA_EXCEEDED), DrivePage::QuotaExceeded);
+        assert_eq!(DrivePage::classify(fixtures::ACCESS_DENIED), DrivePage::AccessDenied);
+        assert_eq!(DrivePage::classify(fixtures::FORBIDDEN), DrivePage::AccessDenied);
+        assert_eq!(DrivePage::classify(fixtures::NOT_FOUND), DrivePage::NotFound);
+        assert_eq!(DrivePage::classify("<html><body>Hello</body></html>"), DrivePage::Other);
+    }
+
+    #[test]
+    fn test_fixture_legacy_virus_scan_warning() {
+        let provider = GoogleDriveProvider::new(reqwest::Client::new());
+        let info = provider
+            .parse_confirmation_page(fixtures::VIRUS_SCAN_LEGACY, FIXTURE_ID, &drive_page(FIXTURE_ID))
+            .unwrap();
+        assert_eq!(
+            info.url,
+            format!("https://drive.google.com/uc?export=download&confirm=Xk3f&id={}", FIXTURE_ID)
+        );
+        assert_eq!(info.file_name.as_deref(), Some("Patch-A.mpq"));
+    }
+
+    #[test]
+    fn test_fixture_usercontent_virus_scan_warning() {
+        let provider = GoogleDriveProvider::new(reqwest::Client::new());
+        let info = provider
+            .parse_confirmation_page(fixtures::VIRUS_SCAN_USERCONTENT, FIXTURE_ID, &drive_page(FIXTURE_ID))
+            .unwrap();
+
+        let url = url::Url::parse(&info.url).unwrap();
+        assert_eq!(url.host_str(), Some("drive.usercontent.google.com"));
+        assert_eq!(url.path(), "/download");
+        let query: std::collections::HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(query["id"], FIXTURE_ID);
+        assert_eq!(query["export"], "download");
+        assert_eq!(query["confirm"], "t");
+        assert_eq!(query["uuid"], "6f1f0c5e-8b2a-4c7d-9e3f-2a1b0c9d8e7f");
+        assert_eq!(query["at"], "APZUnTXq3kR2mF8vL0wY7sN1cB4d:1718031234567");
+        assert_eq!(info.file_name.as_deref(), Some("Patch-A.mpq"));
+    }
+
+    #[test]
+    fn test_fixture_usercontent_relative_action() {
+        // After the redirect the page comes from drive.usercontent.google.com
+        let provider = GoogleDriveProvider::new(reqwest::Client::new());
+        let page_url = url::Url::parse(&format!(
+            "https://drive.usercontent.google.com/download?id={}&export=download",
+            FIXTURE_ID
+        ))
+        .unwrap();
+        let info = provider
+            .parse_confirmation_page(fixtures::VIRUS_SCAN_USERCONTENT_RELATIVE, FIXTURE_ID, &page_url)
+            .unwrap();
+
+        let url = url::Url::parse(&info.url).unwrap();
+        assert_eq!(url.host_str(), Some("drive.usercontent.google.com"));
+        assert_eq!(url.path(), "/download");
+        let query: std::collections::HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(query["id"], FIXTURE_ID);
+        assert_eq!(query["uuid"], "6f1f0c5e-8b2a-4c7d-9e3f-2a1b0c9d8e7f");
+    }
+
+    #[test]
+    fn test_fixture_error_pages() {
+        let provider = GoogleDriveProvider::new(reqwest::Client::new());
+        assert!(matches!(
+            provider.parse_confirmation_page(fixtures::QUOTA_EXCEEDED, FIXTURE_ID, &drive_page(FIXTURE_ID)),
+            Err(DownloadError::QuotaExceeded(_))
+        ));
+        assert!(matches!(
+            provider.parse_confirmation_page(fixtures::ACCESS_DENIED, FIXTURE_ID, &drive_page(FIXTURE_ID)),
+            Err(DownloadError::AccessDenied(_))
+        ));
+        assert!(matches!(
+            provider.parse_confirmation_page(fixtures::FORBIDDEN, FIXTURE_ID, &drive_page(FIXTURE_ID)),
+            Err(DownloadError::AccessDenied(_))
+        ));
+        assert!(matches!(
+            provider.parse_confirmation_page(fixtures::NOT_FOUND, FIXTURE_ID, &drive_page(FIXTURE_ID)),
+            Err(DownloadError::ProviderError(_))
+        ));
+    }
 }
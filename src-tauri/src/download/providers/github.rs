@@ -0,0 +1,402 @@
+//! GitHub Releases provider implementation
+//!
+//! Resolves `github:owner/repo@tag/asset` references through the releases
+//! API. `tag` may be `latest`, and `asset` may contain `*` wildcards (e.g.
+//! `github:isfir/VanillaHelpers@latest/*.dll`). Plain release download URLs
+//! (`https://github.com/owner/repo/releases/download/tag/asset`) are also
+//! accepted and resolved with a HEAD request.
+//!
+//! Published checksums come from the digest GitHub computes for each asset,
+//! or else from a `<asset>.sha256` or `SHA256SUMS`-style asset in the same
+//! release.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::direct::DirectHttpProvider;
+use super::{glob_match, DirectDownloadInfo, DownloadProvider};
+use crate::download::DownloadError;
+use crate::models::DownloadProvider as ProviderType;
+
+/// Default GitHub API base URL
+pub const GITHUB_API_BASE: &str = "https://api.github.com";
+
+/// Reference scheme prefix
+const GITHUB_SCHEME: &str = "github:";
+
+/// Release assets listing checksums for the other assets
+const CHECKSUM_ASSETS: &[&str] = &["SHA256SUMS", "SHA256SUMS.txt", "sha256sums.txt", "checksums.txt", "checksums.sha256"];
+
+/// A parsed `github:owner/repo@tag/asset` reference
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitHubRef {
+    pub owner: String,
+    pub repo: String,
+    /// Release tag, `None` for the latest release
+    pub tag: Option<String>,
+    /// Asset name pattern (`*` matches any run of characters)
+    pub asset: String,
+}
+
+impl GitHubRef {
+    /// Parse a reference, returning None if it is malformed
+    pub fn parse(reference: &str) -> Option<Self> {
+        let rest = reference.strip_prefix(GITHUB_SCHEME)?;
+        let (owner, rest) = rest.split_once('/')?;
+        let (repo, rest) = rest.split_once('@')?;
+        let (tag, asset) = rest.split_once('/')?;
+
+        if [owner, repo, tag, asset].iter().any(|s| s.is_empty()) {
+            return None;
+        }
+
+        Some(Self {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            tag: (tag != "latest").then(|| tag.to_string()),
+            asset: asset.to_string(),
+        })
+    }
+}
+
+/// A release asset as returned by the GitHub API
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub size: u64,
+    pub browser_download_url: String,
+    /// `sha256:<hex>` digest GitHub computed on upload; missing for
+    /// assets uploaded before GitHub started publishing them
+    #[serde(default)]
+    pub digest: Option<String>,
+}
+
+impl ReleaseAsset {
+    /// sha256 GitHub published for the asset
+    pub fn sha256(&self) -> Option<&str> {
+        self.digest.as_deref()?.strip_prefix("sha256:").filter(|h| is_sha256(h))
+    }
+}
+
+fn is_sha256(hash: &str) -> bool {
+    hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Find a file's sha256 in a checksum listing
+///
+/// Accepts `sha256sum` output (`<hash>  <name>` or `<hash> *<name>`), and
+/// a lone hash as found in per-asset `.sha256` files.
+pub fn parse_checksum(text: &str, name: &str) -> Option<String> {
+    let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#'));
+    let listed = lines.clone().find_map(|line| {
+        let (hash, file) = line.split_once(char::is_whitespace)?;
+        let file = file.trim_start().trim_start_matches('*');
+        (is_sha256(hash) && file.eq_ignore_ascii_case(name)).then(|| hash.to_lowercase())
+    });
+    listed.or_else(|| {
+        let only = lines.next()?;
+        (lines.next().is_none() && is_sha256(only)).then(|| only.to_lowercase())
+    })
+}
+
+/// A release as returned by the GitHub API
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+/// GitHub Releases download provider
+pub struct GitHubReleaseProvider {
+    client: reqwest::Client,
+    api_base: String,
+}
+
+impl GitHubReleaseProvider {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self::with_api_base(client, GITHUB_API_BASE)
+    }
+
+    /// Use a different API base URL (e.g. a local server in tests)
+    pub fn with_api_base(client: reqwest::Client, api_base: &str) -> Self {
+        Self {
+            client,
+            api_base: api_base.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Fetch a release by tag, or the latest release
+    pub async fn fetch_release(
+        &self,
+        owner: &str,
+        repo: &str,
+        tag: Option<&str>,
+    ) -> Result<Release, DownloadError> {
+        let url = match tag {
+            Some(tag) => format!("{}/repos/{}/{}/releases/tags/{}", self.api_base, owner, repo, tag),
+            None => format!("{}/repos/{}/{}/releases/latest", self.api_base, owner, repo),
+        };
+        log::info!("[GitHub] Fetching release: {}", url);
+        self.get_json(&url).await
+    }
+
+    /// Fetch the most recent releases of a repository, newest first
+    pub async fn fetch_releases(&self, owner: &str, repo: &str) -> Result<Vec<Release>, DownloadError> {
+        let url = format!("{}/repos/{}/{}/releases", self.api_base, owner, repo);
+        log::info!("[GitHub] Fetching releases: {}", url);
+        self.get_json(&url).await
+    }
+
+    /// sha256 published for a release asset, if the release has one
+    ///
+    /// Prefers GitHub's own digest, then a `<asset>.sha256` asset, then a
+    /// checksum list such as `SHA256SUMS`.
+    pub async fn published_sha256(&self, release: &Release, asset: &ReleaseAsset) -> Result<Option<String>, DownloadError> {
+        if let Some(sha256) = asset.sha256() {
+            return Ok(Some(sha256.to_lowercase()));
+        }
+
+        let own = format!("{}.sha256", asset.name);
+        let listings = release.assets.iter().filter(|a| {
+            a.name.eq_ignore_ascii_case(&own) || CHECKSUM_ASSETS.iter().any(|c| a.name.eq_ignore_ascii_case(c))
+        });
+        for listing in listings {
+            log::info!("[GitHub] Reading checksums from {}", listing.name);
+            let response = self
+                .client
+                .get(&listing.browser_download_url)
+                .header("User-Agent", "WoW-HD-Patcher")
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                return Err(DownloadError::HttpError(response.status()));
+            }
+            if let Some(sha256) = parse_checksum(&response.text().await?, &asset.name) {
+                return Ok(Some(sha256));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T, DownloadError> {
+        let response = self
+            .client
+            .get(url)
+            .header("User-Agent", "WoW-HD-Patcher")
+            .header("Accept", "application/vnd.github+json")
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(DownloadError::HttpError(response.status()));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// Resolve a reference to its release and matching asset
+    pub async fn resolve_ref(&self, reference: &GitHubRef) -> Result<(Release, ReleaseAsset), DownloadError> {
+        let release = self
+            .fetch_release(&reference.owner, &reference.repo, reference.tag.as_deref())
+            .await?;
+
+        let asset = release
+            .assets
+            .iter()
+            .find(|a| glob_match(&reference.asset, &a.name))
+            .cloned()
+            .ok_or_else(|| {
+                DownloadError::ProviderError(format!(
+                    "No asset matching '{}' in {}/{} release {}",
+                    reference.asset, reference.owner, reference.repo, release.tag_name
+                ))
+            })?;
+
+        Ok((release, asset))
+    }
+}
+
+#[async_trait]
+impl DownloadProvider for GitHubReleaseProvider {
+    async fn resolve_direct_url(&self, share_url: &str) -> Result<DirectDownloadInfo, DownloadError> {
+        log::info!("[GitHub] Reference: {}", share_url);
+
+        let Some(reference) = GitHubRef::parse(share_url) else {
+            // Plain release download URL
+            return DirectHttpProvider::new(self.client.clone())
+                .resolve_direct_url(share_url)
+                .await;
+        };
+
+        let (release, asset) = self.resolve_ref(&reference).await?;
+        log::info!("[GitHub] Resolved {} from release {}", asset.name, release.tag_name);
+
+        Ok(DirectDownloadInfo {
+            url: asset.browser_download_url,
+            file_name: Some(asset.name),
+            content_length: Some(asset.size),
+            supports_range: true,
+        })
+    }
+
+    fn supports_resume(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "GitHub"
+    }
+
+    fn provider_type(&self) -> ProviderType {
+        ProviderType::GitHub
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["github_release", "githubrelease"]
+    }
+
+    fn url_hosts(&self) -> &'static [&'static str] {
+        &["github.com"]
+    }
+
+    fn matches_url(&self, url: &str) -> bool {
+        GitHubRef::parse(url).is_some()
+            || (super::host_matches(url, self.url_hosts()) && url.contains("/releases/download/"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::download::test_server::{serve, Route};
+
+    const RELEASE_JSON: &str = r#"{
+        "tag_name": "v1.2.0",
+        "assets": [
+            {"name": "VanillaHelpers-src.zip", "size": 10, "browser_download_url": "https://example.com/src.zip"},
+            {"name": "VanillaHelpers.dll", "size": 4096, "browser_download_url": "https://example.com/VanillaHelpers.dll"}
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_reference() {
+        let parsed = GitHubRef::parse("github:isfir/VanillaHelpers@latest/*.dll").unwrap();
+        assert_eq!(parsed.owner, "isfir");
+        assert_eq!(parsed.repo, "VanillaHelpers");
+        assert_eq!(parsed.tag, None);
+        assert_eq!(parsed.asset, "*.dll");
+
+        let tagged = GitHubRef::parse("github:owner/repo@v2.0/Patch-A.mpq").unwrap();
+        assert_eq!(tagged.tag.as_deref(), Some("v2.0"));
+
+        assert!(GitHubRef::parse("github:owner/repo/Patch-A.mpq").is_none());
+        assert!(GitHubRef::parse("github:owner/repo@v1/").is_none());
+        assert!(GitHubRef::parse("https://github.com/owner/repo").is_none());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.dll", "VanillaHelpers.DLL"));
+        assert!(glob_match("Patch-A.mpq", "patch-a.mpq"));
+        assert!(glob_match("dxvk-*.tar.gz", "dxvk-2.3.tar.gz"));
+        assert!(!glob_match("*.dll", "VanillaHelpers-src.zip"));
+        assert!(!glob_match("dxvk-*.tar.gz", "dxvk-2.3.zip"));
+    }
+
+    #[test]
+    fn test_matches_url() {
+        let provider = GitHubReleaseProvider::new(reqwest::Client::new());
+        assert!(provider.matches_url("github:owner/repo@latest/a.mpq"));
+        assert!(provider.matches_url("https://github.com/owner/repo/releases/download/v1/a.mpq"));
+        assert!(!provider.matches_url("https://github.com/owner/repo"));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_latest_release_asset() {
+        let (base, log) = serve(vec![(
+            "/repos/isfir/VanillaHelpers/releases/latest",
+            Route::ok(RELEASE_JSON),
+        )])
+        .await;
+
+        let provider = GitHubReleaseProvider::with_api_base(reqwest::Client::new(), &base);
+        let info = provider
+            .resolve_direct_url("github:isfir/VanillaHelpers@latest/*.dll")
+            .await
+            .unwrap();
+
+        assert_eq!(info.url, "https://example.com/VanillaHelpers.dll");
+        assert_eq!(info.file_name.as_deref(), Some("VanillaHelpers.dll"));
+        assert_eq!(info.content_length, Some(4096));
+        assert_eq!(log.lock().unwrap()[0].2.get("user-agent").map(String::as_str), Some("WoW-HD-Patcher"));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_missing_asset_fails() {
+        let (base, _) = serve(vec![(
+            "/repos/owner/repo/releases/tags/v1.2.0",
+            Route::ok(RELEASE_JSON),
+        )])
+        .await;
+
+        let provider = GitHubReleaseProvider::with_api_base(reqwest::Client::new(), &base);
+        let result = provider.resolve_direct_url("github:owner/repo@v1.2.0/*.mpq").await;
+        assert!(matches!(result, Err(DownloadError::ProviderError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_releases() {
+        let (base, _) = serve(vec![(
+            "/repos/doitsujin/dxvk/releases",
+            Route::ok(r#"[{"tag_name": "v2.8-rc1", "prerelease": true}, {"tag_name": "v2.7.1"}]"#),
+        )])
+        .await;
+
+        let provider = GitHubReleaseProvider::with_api_base(reqwest::Client::new(), &base);
+        let releases = provider.fetch_releases("doitsujin", "dxvk").await.unwrap();
+        assert_eq!(releases.len(), 2);
+        assert!(releases[0].prerelease);
+        assert!(!releases[1].prerelease && releases[1].assets.is_empty());
+    }
+
+    #[test]
+    fn test_parse_checksum() {
+        let hash = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        let listing = format!("{}  VanillaHelpers-src.zip\n{} *VanillaHelpers.dll\n", "0".repeat(64), hash.to_uppercase());
+        assert_eq!(parse_checksum(&listing, "vanillahelpers.dll").as_deref(), Some(hash));
+        assert_eq!(parse_checksum(&listing, "Other.dll"), None);
+        assert_eq!(parse_checksum(&format!("{}\n", hash), "VanillaHelpers.dll").as_deref(), Some(hash));
+        assert_eq!(parse_checksum("not a hash", "VanillaHelpers.dll"), None);
+    }
+
+    #[tokio::test]
+    async fn test_published_sha256() {
+        let hash = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        let (base, _) = serve(vec![(
+            "/SHA256SUMS",
+            Route::ok(format!("{}  VanillaHelpers.dll\n", hash)),
+        )])
+        .await;
+        let provider = GitHubReleaseProvider::with_api_base(reqwest::Client::new(), &base);
+
+        let mut release: Release = serde_json::from_str(RELEASE_JSON).unwrap();
+        let asset = release.assets[1].clone();
+        assert_eq!(provider.published_sha256(&release, &asset).await.unwrap(), None);
+
+        release.assets.push(ReleaseAsset {
+            name: "SHA256SUMS".to_string(),
+            size: 100,
+            browser_download_url: format!("{}/SHA256SUMS", base),
+            digest: None,
+        });
+        assert_eq!(provider.published_sha256(&release, &asset).await.unwrap().as_deref(), Some(hash));
+
+        let digested = ReleaseAsset { digest: Some(format!("sha256:{}", "a".repeat(64))), ..asset };
+        assert_eq!(provider.published_sha256(&release, &digested).await.unwrap(), Some("a".repeat(64)));
+    }
+}
@@ -2,14 +2,18 @@
 //!
 //! Resolves Mediafire share URLs to direct download URLs by parsing the share page HTML.
 //! Handles dynamic numbered subdomains (download1, download2, etc.) that Mediafire uses.
+//! Folder links are listed through the public folder API so that a single
+//! file can be picked from them.
 
 use async_trait::async_trait;
 use lazy_static::lazy_static;
 use regex::Regex;
 use scraper::{Html, Selector};
+use serde::Deserialize;
 
-use super::{DirectDownloadInfo, DownloadProvider};
+use super::{host_matches, DirectDownloadInfo, DownloadProvider, FolderEntry};
 use crate::download::DownloadError;
+use crate::models::DownloadProvider as ProviderType;
 
 lazy_static! {
     /// Matches mediafire.com share URLs
@@ -17,6 +21,11 @@ lazy_static! {
         r"(?:www\.)?mediafire\.com/(?:file|view|download|folder)/([a-zA-Z0-9]+)"
     ).unwrap();
 
+    /// Matches mediafire.com folder URLs
+    static ref MEDIAFIRE_FOLDER_PATTERN: Regex = Regex::new(
+        r"(?:www\.|app\.)?mediafire\.com/folder/([a-zA-Z0-9]+)"
+    ).unwrap();
+
     /// Matches the actual download URL with numbered subdomain
     static ref MEDIAFIRE_DOWNLOAD_URL: Regex = Regex::new(
         r#"https://download\d+\.mediafire\.com/[^'"<>\s]+"#
@@ -28,18 +37,91 @@ lazy_static! {
     ).unwrap();
 }
 
+/// Default Mediafire API base URL
+const MEDIAFIRE_API_BASE: &str = "https://www.mediafire.com/api/1.5";
+
+/// Envelope of a `folder/get_content` API response
+#[derive(Debug, Deserialize)]
+struct FolderContentEnvelope {
+    response: FolderContentResponse,
+}
+
+#[derive(Debug, Deserialize)]
+struct FolderContentResponse {
+    result: String,
+    message: Option<String>,
+    folder_content: Option<FolderContent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FolderContent {
+    #[serde(default)]
+    files: Vec<FolderFile>,
+    /// "yes" when another chunk follows
+    #[serde(default)]
+    more_chunks: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FolderFile {
+    quickkey: String,
+    filename: String,
+    /// Size in bytes, sent as a string
+    size: Option<String>,
+}
+
 /// Mediafire download provider
 ///
 /// Resolves Mediafire share URLs by fetching the share page and extracting
 /// the direct download URL with its numbered subdomain.
 pub struct MediafireProvider {
     client: reqwest::Client,
+    api_base: String,
 }
 
 impl MediafireProvider {
     /// Create a new Mediafire provider with the given HTTP client
     pub fn new(client: reqwest::Client) -> Self {
-        Self { client }
+        Self::with_api_base(client, MEDIAFIRE_API_BASE)
+    }
+
+    /// Use a different API base URL (e.g. a local server in tests)
+    pub fn with_api_base(client: reqwest::Client, api_base: &str) -> Self {
+        Self {
+            client,
+            api_base: api_base.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Get the folder key from a Mediafire folder URL
+    pub fn folder_key(url: &str) -> Option<String> {
+        MEDIAFIRE_FOLDER_PATTERN.captures(url).map(|cap| cap[1].to_string())
+    }
+
+    /// Fetch one chunk of a folder's file listing
+    async fn fetch_folder_chunk(&self, folder_key: &str, chunk: u32) -> Result<FolderContent, DownloadError> {
+        let url = format!(
+            "{}/folder/get_content.php?folder_key={}&content_type=files&chunk={}&response_format=json",
+            self.api_base, folder_key, chunk
+        );
+        log::info!("[MediaFire] Listing folder: {}", url);
+
+        let response = self.client.get(&url).send().await?;
+        if !response.status().is_success() {
+            return Err(DownloadError::HttpError(response.status()));
+        }
+
+        let envelope: FolderContentEnvelope = response.json().await?;
+        let response = envelope.response;
+        if response.result != "Success" {
+            return Err(DownloadError::ProviderError(format!(
+                "Mediafire folder listing failed: {}",
+                response.message.unwrap_or(response.result)
+            )));
+        }
+        response
+            .folder_content
+            .ok_or_else(|| DownloadError::ProviderError("Mediafire folder listing is empty".to_string()))
     }
 
     /// Validate that URL is a Mediafire share URL
@@ -198,11 +280,51 @@ impl DownloadProvider for MediafireProvider {
     fn name(&self) -> &'static str {
         "Mediafire"
     }
+
+    fn provider_type(&self) -> ProviderType {
+        ProviderType::Mediafire
+    }
+
+    fn url_hosts(&self) -> &'static [&'static str] {
+        &["mediafire.com"]
+    }
+
+    fn matches_url(&self, url: &str) -> bool {
+        host_matches(url, self.url_hosts()) || Self::is_mediafire_url(url)
+    }
+
+    fn is_folder_url(&self, url: &str) -> bool {
+        Self::folder_key(url).is_some()
+    }
+
+    async fn list_folder(&self, share_url: &str) -> Result<Vec<FolderEntry>, DownloadError> {
+        let folder_key = Self::folder_key(share_url)
+            .ok_or_else(|| DownloadError::ProviderError("Not a Mediafire folder URL".to_string()))?;
+
+        let mut entries = Vec::new();
+        let mut chunk = 1;
+        loop {
+            let content = self.fetch_folder_chunk(&folder_key, chunk).await?;
+            entries.extend(content.files.into_iter().map(|file| FolderEntry {
+                url: format!("https://www.mediafire.com/file/{}/{}/file", file.quickkey, file.filename),
+                size: file.size.and_then(|s| s.parse().ok()),
+                name: file.filename,
+            }));
+            if content.more_chunks != "yes" {
+                break;
+            }
+            chunk += 1;
+        }
+
+        log::info!("[MediaFire] Folder {} has {} files", folder_key, entries.len());
+        Ok(entries)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::download::test_server::{serve, Route};
 
     #[test]
     fn test_is_mediafire_url_valid_file() {
@@ -311,4 +433,58 @@ mod tests {
         "#;
         assert_eq!(MediafireProvider::extract_filename_from_page(html), None);
     }
+
+    #[test]
+    fn test_folder_key() {
+        assert_eq!(
+            MediafireProvider::folder_key("https://app.mediafire.com/folder/abc123def"),
+            Some("abc123def".to_string())
+        );
+        assert_eq!(MediafireProvider::folder_key("https://www.mediafire.com/file/abc123/patch.rar"), None);
+    }
+
+    #[tokio::test]
+    async fn test_list_folder_follows_chunks() {
+        let chunk = |n: u32| format!(
+            "/folder/get_content.php?folder_key=abc&content_type=files&chunk={}&response_format=json",
+            n
+        );
+        let (base, _) = serve(vec![
+            (
+                chunk(1).as_str(),
+                Route::ok(r#"{"response": {"result": "Success", "folder_content": {
+                    "files": [{"quickkey": "q1", "filename": "Patch-A.mpq", "size": "1024"}],
+                    "more_chunks": "yes"}}}"#),
+            ),
+            (
+                chunk(2).as_str(),
+                Route::ok(r#"{"response": {"result": "Success", "folder_content": {
+                    "files": [{"quickkey": "q2", "filename": "Patch-A-Lite.mpq"}],
+                    "more_chunks": "no"}}}"#),
+            ),
+        ])
+        .await;
+
+        let provider = MediafireProvider::with_api_base(reqwest::Client::new(), &base);
+        let entries = provider.list_folder("https://www.mediafire.com/folder/abc").await.unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].size, Some(1024));
+        assert_eq!(entries[0].url, "https://www.mediafire.com/file/q1/Patch-A.mpq/file");
+        assert_eq!(entries[1].name, "Patch-A-Lite.mpq");
+        assert!(provider.matches_url(&entries[1].url));
+    }
+
+    #[tokio::test]
+    async fn test_list_folder_api_error() {
+        let (base, _) = serve(vec![(
+            "/folder/get_content.php?folder_key=gone&content_type=files&chunk=1&response_format=json",
+            Route::ok(r#"{"response": {"result": "Error", "message": "Unknown or invalid FolderKey"}}"#),
+        )])
+        .await;
+
+        let provider = MediafireProvider::with_api_base(reqwest::Client::new(), &base);
+        let result = provider.list_folder("https://www.mediafire.com/folder/gone").await;
+        assert!(matches!(result, Err(DownloadError::ProviderError(msg)) if msg.contains("FolderKey")));
+    }
 }
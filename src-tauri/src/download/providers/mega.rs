@@ -1,105 +1,373 @@
 //! MEGA.nz provider implementation
 //!
-//! Downloads files from MEGA.nz shared links using the mega crate.
-//! Handles URL parsing, decryption, and streaming downloads.
+//! Downloads files from MEGA.nz shared links. The mega crate parses links and
+//! decrypts node metadata; the file itself is fetched here so that it can be
+//! requested from an offset. MEGA encrypts file contents with AES-128-CTR, so
+//! an interrupted download resumes by seeking the keystream to the size of
+//! the partial file and appending. The condensed MAC of the whole file is
+//! checked once it is complete.
+//!
+//! Folder links list every file in the folder; a single file is addressed as
+//! `https://mega.nz/folder/<id>#<key>/file/<handle>`.
 
+use aes::Aes128;
 use async_trait::async_trait;
+use ctr::cipher::{KeyIvInit, StreamCipher, StreamCipherSeek};
+use futures_util::io::{AsyncWrite, AsyncWriteExt};
+use futures_util::StreamExt;
 use std::path::Path;
-use tokio::fs::File;
-use tokio_util::compat::TokioAsyncWriteCompatExt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tauri::ipc::Channel;
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncSeekExt;
+use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
+use tokio_util::sync::CancellationToken;
+
+use super::{DirectDownloadInfo, DownloadProvider, FolderEntry};
+use crate::download::{DownloadError, DownloadEvent, ProgressTracker};
+use crate::models::DownloadProvider as ProviderType;
+
+/// Default MEGA API origin
+const MEGA_API_ORIGIN: &str = "https://g.api.mega.co.nz";
+
+type Aes128Ctr = ctr::Ctr128BE<Aes128>;
 
-use super::{DirectDownloadInfo, DownloadProvider};
-use crate::download::DownloadError;
+/// Metadata of a file node needed to download and decrypt it
+#[derive(Debug, Clone)]
+pub struct MegaFile {
+    pub name: String,
+    pub size: u64,
+    handle: String,
+    download_id: Option<String>,
+    key: [u8; 16],
+    iv: [u8; 8],
+    condensed_mac: Option<[u8; 8]>,
+}
+
+impl MegaFile {
+    fn from_node(node: &mega::Node) -> Self {
+        Self {
+            name: node.name().to_string(),
+            size: node.size(),
+            handle: node.handle().to_string(),
+            download_id: node.download_id().map(str::to_string),
+            key: *node.aes_key(),
+            iv: node.aes_iv().copied().unwrap_or_default(),
+            condensed_mac: node.condensed_mac().copied(),
+        }
+    }
+}
 
 /// MEGA.nz download provider
 pub struct MegaProvider {
     client: reqwest::Client,
+    api_origin: String,
 }
 
 impl MegaProvider {
     pub fn new(client: reqwest::Client) -> Self {
-        Self { client }
+        Self::with_api_origin(client, MEGA_API_ORIGIN)
+    }
+
+    /// Use a different API origin (e.g. a local server in tests)
+    pub fn with_api_origin(client: reqwest::Client, api_origin: &str) -> Self {
+        Self {
+            client,
+            api_origin: api_origin.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Fetch the nodes behind a public file or folder link
+    async fn fetch_nodes(&self, url: &str) -> Result<mega::Nodes, DownloadError> {
+        let origin = url::Url::parse(&self.api_origin)
+            .map_err(|e| DownloadError::ProviderError(format!("Invalid MEGA API origin: {}", e)))?;
+        let mega_client = mega::Client::builder()
+            .origin(origin)
+            .build(self.client.clone())
+            .map_err(|e| DownloadError::ProviderError(format!("Failed to create MEGA client: {}", e)))?;
+
+        mega_client
+            .fetch_public_nodes(url)
+            .await
+            .map_err(|e| {
+                log::info!("[MEGA] Failed to fetch nodes: {}", e);
+                DownloadError::ProviderError(format!("Failed to fetch MEGA file info: {}", e))
+            })
+    }
+
+    /// Fetch the file a public link points at
+    ///
+    /// For folder links this is the file named by a `/file/<handle>` suffix,
+    /// or the only file in the folder.
+    async fn fetch_file(&self, url: &str) -> Result<MegaFile, DownloadError> {
+        let nodes = self.fetch_nodes(url).await?;
+
+        let node = match selected_handle(url) {
+            Some(handle) => nodes.get_node_by_handle(handle),
+            None => nodes.roots().find(|n| n.kind().is_file()).or_else(|| {
+                let mut files = nodes.iter().filter(|n| n.kind().is_file());
+                files.next().filter(|_| files.next().is_none())
+            }),
+        };
+        node.filter(|n| n.kind().is_file())
+            .map(MegaFile::from_node)
+            .ok_or_else(|| DownloadError::ProviderError("No file found at MEGA URL".to_string()))
+    }
+
+    /// Ask the API for a temporary download URL for a file node
+    ///
+    /// Nodes from public links are addressed through their link handle:
+    /// the file itself (`p`) for file links, or a node (`n`) inside a folder
+    /// link.
+    async fn request_download_url(&self, handle: &str, download_id: Option<&str>) -> Result<String, DownloadError> {
+        let mut url = format!("{}/cs?id=0", self.api_origin);
+        let node_key = match download_id {
+            Some(id) => {
+                url.push_str(&format!("&n={}", id));
+                if id == handle { "p" } else { "n" }
+            }
+            None => "n",
+        };
+        let body = serde_json::json!([{ "a": "g", "g": 1, "ssl": 2, node_key: handle }]);
+
+        let response = self.client.post(&url).json(&body).send().await?;
+        if !response.status().is_success() {
+            return Err(DownloadError::HttpError(response.status()));
+        }
+
+        let value: serde_json::Value = response.json().await?;
+        let result = value.as_array().and_then(|a| a.first()).unwrap_or(&value);
+        if let Some(code) = result.as_i64().or_else(|| result.get("e").and_then(|e| e.as_i64())) {
+            return Err(api_error(code));
+        }
+
+        result
+            .get("g")
+            .and_then(|g| g.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| DownloadError::ProviderError("MEGA returned no download URL".to_string()))
     }
 
     /// Download a file from MEGA to the specified path
+    ///
+    /// An existing partial file is kept and only the remaining bytes are
+    /// fetched. A file larger than the remote one is discarded.
     pub async fn download_to_file(
         &self,
         url: &str,
         dest_path: &Path,
+        download_id: String,
+        on_event: Channel<DownloadEvent>,
+        cancel: &CancellationToken,
     ) -> Result<u64, DownloadError> {
         log::info!("[MEGA] download_to_file called");
         log::info!("[MEGA] URL: {}", url);
         log::info!("[MEGA] Dest: {:?}", dest_path);
 
-        // Create MEGA client with HTTP client
-        log::info!("[MEGA] Creating MEGA client...");
-        let mut mega_client = mega::Client::builder()
-            .build(self.client.clone())
-            .map_err(|e| DownloadError::ProviderError(format!("Failed to create MEGA client: {}", e)))?;
+        let file = self.fetch_file(url).await?;
+        log::info!("[MEGA] Found file: {} ({} bytes)", file.name, file.size);
 
-        // Fetch public nodes from the URL
-        log::info!("[MEGA] Fetching public nodes...");
-        let nodes = mega_client
-            .fetch_public_nodes(url)
-            .await
-            .map_err(|e| {
-                log::info!("[MEGA] Failed to fetch nodes: {}", e);
-                DownloadError::ProviderError(format!("Failed to fetch MEGA file info: {}", e))
-            })?;
+        let mut start = tokio::fs::metadata(dest_path).await.map(|m| m.len()).unwrap_or(0);
+        if start > file.size {
+            log::info!("[MEGA] Partial file is larger than remote file, restarting");
+            start = 0;
+        }
+
+        if start < file.size {
+            if cancel.is_cancelled() {
+                return Err(DownloadError::Cancelled);
+            }
+            let download_url = self
+                .request_download_url(&file.handle, file.download_id.as_deref())
+                .await?;
+            log::info!("[MEGA] Downloading bytes {}-{}", start, file.size);
 
-        // Get the first file node
-        let node = nodes
-            .roots()
-            .find(|n| n.kind().is_file())
-            .ok_or_else(|| DownloadError::ProviderError("No file found at MEGA URL".to_string()))?;
+            let mut tracker = ProgressTracker::new(download_id, file.size);
+            tracker.set_downloaded(start);
+            self.download_range(&download_url, &file, dest_path, start, tracker, on_event, cancel)
+                .await?;
+        }
 
-        let file_size = node.size();
-        log::info!("[MEGA] Found file: {} ({} bytes)", node.name(), file_size);
+        if let Err(e) = verify_condensed_mac(&file, dest_path).await {
+            // A corrupt file must not be resumed from next time
+            let _ = tokio::fs::remove_file(dest_path).await;
+            return Err(e);
+        }
+
+        Ok(file.size)
+    }
 
-        // Create destination file
-        let file = File::create(dest_path)
+    /// Fetch, decrypt and append bytes `start..` of a file to `dest_path`
+    ///
+    /// Network reads feed a sluice pipe whose reader is copied into the file
+    /// through a `ProgressWriter`, so progress reflects bytes on disk. On
+    /// cancellation or error, data already received is flushed to the file
+    /// before returning so the next attempt can resume after it.
+    #[allow(clippy::too_many_arguments)]
+    async fn download_range(
+        &self,
+        download_url: &str,
+        file: &MegaFile,
+        dest_path: &Path,
+        start: u64,
+        tracker: ProgressTracker,
+        on_event: Channel<DownloadEvent>,
+        cancel: &CancellationToken,
+    ) -> Result<(), DownloadError> {
+        let response = self
+            .client
+            .get(format!("{}/{}-{}", download_url, start, file.size - 1))
+            .send()
             .await?;
+        if !response.status().is_success() {
+            return Err(DownloadError::HttpError(response.status()));
+        }
+
+        if let Some(parent) = dest_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut dest = OpenOptions::new().create(true).write(true).truncate(false).open(dest_path).await?;
+        dest.set_len(start).await?;
+        dest.seek(std::io::SeekFrom::End(0)).await?;
+
+        let mut cipher = decryptor(&file.key, &file.iv, start);
 
         // Create a pipe for streaming
-        let (reader, writer) = sluice::pipe::pipe();
+        let (reader, mut writer) = sluice::pipe::pipe();
 
         // Spawn task to write data to file
+        let mut progress = ProgressWriter::new(dest.compat_write(), tracker, on_event);
         let handle = tokio::spawn(async move {
-            futures_util::io::copy(reader, &mut file.compat_write()).await
+            futures_util::io::copy(reader, &mut progress).await
         });
 
-        // Download the node to the writer
-        mega_client
-            .download_node(node, writer)
-            .await
-            .map_err(|e| DownloadError::ProviderError(format!("Failed to download from MEGA: {}", e)))?;
+        let mut stream = response.bytes_stream();
+        let streamed = async {
+            loop {
+                let next = tokio::select! {
+                    biased;
+                    _ = cancel.cancelled() => return Err(DownloadError::Cancelled),
+                    next = stream.next() => next,
+                };
+                let Some(chunk) = next else {
+                    return Ok(());
+                };
+                let mut chunk = chunk?.to_vec();
+                cipher.apply_keystream(&mut chunk);
+                writer.write_all(&chunk).await?;
+            }
+        }
+        .await;
 
-        // Wait for file write to complete
-        handle
+        // Closing the pipe lets the copy drain everything received so far
+        drop(writer);
+        let copied = handle
             .await
             .map_err(|e| DownloadError::ProviderError(format!("Task join error: {}", e)))?
             .map_err(|e| DownloadError::ProviderError(format!("Copy error: {}", e)))?;
+        log::info!("[MEGA] Wrote {} bytes from offset {}", copied, start);
 
-        Ok(file_size)
+        if let Err(DownloadError::Cancelled) = streamed {
+            log::info!("[MEGA] Cancelled at {} bytes", start + copied);
+        }
+        streamed
     }
 
     /// Get file info from a MEGA URL without downloading
     pub async fn get_file_info(&self, url: &str) -> Result<(String, u64), DownloadError> {
-        let mut mega_client = mega::Client::builder()
-            .build(self.client.clone())
-            .map_err(|e| DownloadError::ProviderError(format!("Failed to create MEGA client: {}", e)))?;
+        let file = self.fetch_file(url).await?;
+        Ok((file.name, file.size))
+    }
+}
 
-        let nodes = mega_client
-            .fetch_public_nodes(url)
-            .await
-            .map_err(|e| DownloadError::ProviderError(format!("Failed to fetch MEGA file info: {}", e)))?;
+/// Whether a MEGA link points at a folder
+fn is_folder_link(url: &str) -> bool {
+    url.contains("/folder/")
+}
+
+/// Handle of the file selected inside a folder link, if any
+fn selected_handle(url: &str) -> Option<&str> {
+    let (_, fragment) = url.split_once('#')?;
+    let (_, handle) = fragment.split_once("/file/")?;
+    Some(handle.trim_end_matches('/')).filter(|h| !h.is_empty())
+}
 
-        let node = nodes
-            .roots()
-            .find(|n| n.kind().is_file())
-            .ok_or_else(|| DownloadError::ProviderError("No file found at MEGA URL".to_string()))?;
+/// AES-128-CTR decryptor positioned at byte `offset` of a file
+///
+/// The counter block is the node's 8-byte IV followed by a 64-bit
+/// big-endian block counter, so any offset can be reached by seeking.
+fn decryptor(key: &[u8; 16], iv: &[u8; 8], offset: u64) -> Aes128Ctr {
+    let mut nonce = [0u8; 16];
+    nonce[..8].copy_from_slice(iv);
+    let mut cipher = Aes128Ctr::new(key.into(), (&nonce).into());
+    cipher.seek(offset);
+    cipher
+}
 
-        Ok((node.name().to_string(), node.size()))
+/// Check a complete download against the node's condensed MAC
+async fn verify_condensed_mac(file: &MegaFile, path: &Path) -> Result<(), DownloadError> {
+    let Some(expected) = file.condensed_mac else {
+        return Ok(());
+    };
+
+    let reader = tokio::fs::File::open(path).await?.compat();
+    let actual = mega::compute_condensed_mac(reader, file.size, &file.key, &file.iv)
+        .await
+        .map_err(|e| DownloadError::ProviderError(format!("Failed to verify MEGA download: {}", e)))?;
+
+    if actual != expected {
+        return Err(DownloadError::ProviderError(format!(
+            "MEGA download of {} failed integrity check",
+            file.name
+        )));
+    }
+    Ok(())
+}
+
+/// Describe a MEGA API error code
+fn api_error(code: i64) -> DownloadError {
+    let reason = match code {
+        -9 => "file not found",
+        -11 => "access denied",
+        -16 => "file is blocked",
+        -17 => "transfer quota exceeded",
+        _ => "request failed",
+    };
+    DownloadError::ProviderError(format!("MEGA {} (error {})", reason, code))
+}
+
+/// Writer that reports bytes written through a `ProgressTracker`
+pub struct ProgressWriter<W> {
+    inner: W,
+    tracker: ProgressTracker,
+    on_event: Channel<DownloadEvent>,
+}
+
+impl<W> ProgressWriter<W> {
+    pub fn new(inner: W, tracker: ProgressTracker, on_event: Channel<DownloadEvent>) -> Self {
+        Self { inner, tracker, on_event }
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for ProgressWriter<W> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        let this = &mut *self;
+        let result = Pin::new(&mut this.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = result {
+            if let Some(event) = this.tracker.update(written as u64) {
+                let _ = this.on_event.send(event); // Ignore throttled sends
+            }
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
     }
 }
 
@@ -115,15 +383,202 @@ impl DownloadProvider for MegaProvider {
             url: share_url.to_string(),
             file_name: Some(file_name),
             content_length: Some(file_size),
-            supports_range: false, // MEGA handles its own chunking
+            supports_range: true, // Ranges are requested by download_to
         })
     }
 
     fn supports_resume(&self) -> bool {
-        false // MEGA handles downloads differently
+        true // Partial files are continued by seeking the CTR keystream
     }
 
     fn name(&self) -> &'static str {
         "MEGA"
     }
+
+    fn provider_type(&self) -> ProviderType {
+        ProviderType::Mega
+    }
+
+    fn url_hosts(&self) -> &'static [&'static str] {
+        &["mega.nz", "mega.co.nz"]
+    }
+
+    fn is_folder_url(&self, url: &str) -> bool {
+        is_folder_link(url) && selected_handle(url).is_none()
+    }
+
+    async fn list_folder(&self, share_url: &str) -> Result<Vec<FolderEntry>, DownloadError> {
+        let nodes = self.fetch_nodes(share_url).await?;
+        let folder_url = share_url.trim_end_matches('/');
+
+        let entries: Vec<FolderEntry> = nodes
+            .iter()
+            .filter(|n| n.kind().is_file())
+            .map(|n| FolderEntry {
+                name: n.name().to_string(),
+                size: Some(n.size()),
+                url: format!("{}/file/{}", folder_url, n.handle()),
+            })
+            .collect();
+
+        log::info!("[MEGA] Folder has {} files", entries.len());
+        Ok(entries)
+    }
+
+    fn streams_itself(&self) -> bool {
+        true // Files are encrypted and must be decrypted while downloading
+    }
+
+    async fn download_to(
+        &self,
+        share_url: &str,
+        dest_path: &Path,
+        download_id: String,
+        on_event: Channel<DownloadEvent>,
+        cancel: &CancellationToken,
+    ) -> Result<u64, DownloadError> {
+        self.download_to_file(share_url, dest_path, download_id, on_event, cancel)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::download::test_server::{serve, Route};
+
+    const KEY: [u8; 16] = *b"0123456789abcdef";
+    const IV: [u8; 8] = *b"nonce-iv";
+
+    fn test_file(size: u64) -> MegaFile {
+        MegaFile {
+            name: "Patch-A.mpq".to_string(),
+            size,
+            handle: "abc".to_string(),
+            download_id: Some("abc".to_string()),
+            key: KEY,
+            iv: IV,
+            condensed_mac: None,
+        }
+    }
+
+    fn encrypt(plain: &[u8]) -> Vec<u8> {
+        let mut data = plain.to_vec();
+        decryptor(&KEY, &IV, 0).apply_keystream(&mut data);
+        data
+    }
+
+    #[test]
+    fn test_decryptor_seeks_to_unaligned_offset() {
+        let plain: Vec<u8> = (0..100u8).collect();
+        let encrypted = encrypt(&plain);
+
+        let mut tail = encrypted[37..].to_vec();
+        decryptor(&KEY, &IV, 37).apply_keystream(&mut tail);
+        assert_eq!(tail, plain[37..]);
+    }
+
+    #[test]
+    fn test_folder_links() {
+        let provider = MegaProvider::new(reqwest::Client::new());
+        let folder = "https://mega.nz/folder/AbCd#key123";
+        assert!(provider.is_folder_url(folder));
+        assert_eq!(selected_handle(folder), None);
+
+        let file_in_folder = format!("{}/file/XyZ9", folder);
+        assert!(!provider.is_folder_url(&file_in_folder));
+        assert_eq!(selected_handle(&file_in_folder), Some("XyZ9"));
+
+        assert!(!provider.is_folder_url("https://mega.nz/file/AbCd#key123"));
+    }
+
+    #[tokio::test]
+    async fn test_progress_writer_counts_from_resumed_offset() {
+        let mut tracker = ProgressTracker::new("id".to_string(), 100);
+        tracker.set_downloaded(40);
+        let mut writer = ProgressWriter::new(Vec::new(), tracker, Channel::new(|_| Ok(())));
+
+        writer.write_all(&[0u8; 25]).await.unwrap();
+        assert_eq!(writer.tracker.downloaded_bytes(), 65);
+        assert_eq!(writer.inner.len(), 25);
+    }
+
+    #[tokio::test]
+    async fn test_request_download_url() {
+        let (base, log) = serve(vec![(
+            "/cs?id=0&n=abc",
+            Route::ok(r#"[{"s": 11, "g": "https://gfs.example/dl/xyz"}]"#),
+        )])
+        .await;
+
+        let provider = MegaProvider::with_api_origin(reqwest::Client::new(), &base);
+        let url = provider.request_download_url("abc", Some("abc")).await.unwrap();
+        assert_eq!(url, "https://gfs.example/dl/xyz");
+        assert_eq!(log.lock().unwrap()[0].0, "POST");
+    }
+
+    #[tokio::test]
+    async fn test_request_download_url_error_code() {
+        let (base, _) = serve(vec![("/cs?id=0&n=abc", Route::ok("[-17]"))]).await;
+
+        let provider = MegaProvider::with_api_origin(reqwest::Client::new(), &base);
+        let result = provider.request_download_url("abc", Some("abc")).await;
+        assert!(matches!(result, Err(DownloadError::ProviderError(msg)) if msg.contains("quota")));
+    }
+
+    #[tokio::test]
+    async fn test_download_range_resumes_partial_file() {
+        let plain = b"MPQ\x1a encrypted patch contents".to_vec();
+        let encrypted = encrypt(&plain);
+        let size = plain.len() as u64;
+        let path = format!("/dl/xyz/10-{}", size - 1);
+        let (base, _) = serve(vec![(path.as_str(), Route::ok(encrypted[10..].to_vec()))]).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("Patch-A.mpq");
+        std::fs::write(&dest, &plain[..10]).unwrap();
+
+        let provider = MegaProvider::new(reqwest::Client::new());
+        let tracker = ProgressTracker::new("id".to_string(), size);
+        provider
+            .download_range(
+                &format!("{}/dl/xyz", base),
+                &test_file(size),
+                &dest,
+                10,
+                tracker,
+                Channel::new(|_| Ok(())),
+                &CancellationToken::new(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(std::fs::read(&dest).unwrap(), plain);
+    }
+
+    #[tokio::test]
+    async fn test_download_range_cancelled() {
+        let plain = vec![7u8; 64];
+        let (base, _) = serve(vec![("/dl/xyz/0-63", Route::ok(encrypt(&plain)))]).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("Patch-A.mpq");
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        let provider = MegaProvider::new(reqwest::Client::new());
+        let result = provider
+            .download_range(
+                &format!("{}/dl/xyz", base),
+                &test_file(64),
+                &dest,
+                0,
+                ProgressTracker::new("id".to_string(), 64),
+                Channel::new(|_| Ok(())),
+                &cancel,
+            )
+            .await;
+
+        assert!(matches!(result, Err(DownloadError::Cancelled)));
+    }
 }
@@ -1,22 +1,35 @@
 //! Download provider abstraction
 //!
-//! This module defines the trait that download providers (Google Drive, Mediafire, etc.)
-//! must implement to resolve share URLs to direct download URLs.
+//! This module defines the trait that download providers (Google Drive, Mediafire,
+//! GitHub releases, plain HTTP, etc.)
+//! must implement to resolve share URLs to direct download URLs, and the
+//! registry that dispatches to them by name, alias or URL.
 
 pub mod gdrive;
 pub mod mediafire;
 pub mod dropbox;
 pub mod transfer;
 pub mod mega;
+pub mod direct;
+pub mod github;
+pub mod registry;
 
 pub use gdrive::GoogleDriveProvider;
 pub use mediafire::MediafireProvider;
 pub use dropbox::DropboxProvider;
 pub use transfer::TransferProvider;
 pub use mega::MegaProvider;
+pub use direct::DirectHttpProvider;
+pub use github::GitHubReleaseProvider;
+pub use registry::ProviderRegistry;
 
+use std::path::Path;
 use async_trait::async_trait;
-use crate::download::DownloadError;
+use serde::Serialize;
+use tauri::ipc::Channel;
+use tokio_util::sync::CancellationToken;
+use crate::download::{DownloadError, DownloadEvent};
+use crate::models::DownloadProvider as ProviderType;
 
 /// Information about a resolved direct download URL
 #[derive(Debug, Clone)]
@@ -31,6 +44,18 @@ pub struct DirectDownloadInfo {
     pub supports_range: bool,
 }
 
+/// A file inside a folder share
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderEntry {
+    /// File name as shown by the provider
+    pub name: String,
+    /// Size in bytes (if the listing reports it)
+    pub size: Option<u64>,
+    /// Share URL of this single file, resolvable by the same provider
+    pub url: String,
+}
+
 /// Trait for download providers that resolve share URLs to direct download URLs
 ///
 /// Different hosting providers (Google Drive, Mediafire) have different URL formats
@@ -51,4 +76,169 @@ pub trait DownloadProvider: Send + Sync {
 
     /// Get the provider's name for logging/display purposes
     fn name(&self) -> &'static str;
+
+    /// Catalog/model type this provider handles
+    fn provider_type(&self) -> ProviderType;
+
+    /// Lowercase names accepted for this provider besides `name()`
+    /// (e.g. "gdrive" in the catalog or frontend)
+    fn aliases(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Hosts whose URLs belong to this provider (subdomains match too)
+    fn url_hosts(&self) -> &'static [&'static str];
+
+    /// Check whether a share URL belongs to this provider
+    fn matches_url(&self, url: &str) -> bool {
+        host_matches(url, self.url_hosts())
+    }
+
+    /// Whether a share URL points at a folder rather than a single file
+    fn is_folder_url(&self, _url: &str) -> bool {
+        false
+    }
+
+    /// List the files in a folder share
+    ///
+    /// Only called when `is_folder_url()` is true.
+    async fn list_folder(&self, share_url: &str) -> Result<Vec<FolderEntry>, DownloadError> {
+        Err(DownloadError::ProviderError(format!(
+            "{} cannot list folder {}",
+            self.name(),
+            share_url
+        )))
+    }
+
+    /// Whether the provider downloads files itself via `download_to`
+    /// instead of handing a direct URL to the HTTP engine
+    fn streams_itself(&self) -> bool {
+        false
+    }
+
+    /// Download a share URL straight to `dest_path`, returning the byte count
+    ///
+    /// Only called when `streams_itself()` is true. Implementations send
+    /// `Progress` events on `on_event`, resume from an existing partial file
+    /// where they can, and return `DownloadError::Cancelled` once `cancel`
+    /// fires.
+    async fn download_to(
+        &self,
+        share_url: &str,
+        _dest_path: &Path,
+        _download_id: String,
+        _on_event: Channel<DownloadEvent>,
+        _cancel: &CancellationToken,
+    ) -> Result<u64, DownloadError> {
+        Err(DownloadError::ProviderError(format!(
+            "{} cannot download {} directly",
+            self.name(),
+            share_url
+        )))
+    }
+}
+
+/// Check whether a URL's host is one of `hosts` or a subdomain of one
+pub fn host_matches(url: &str, hosts: &[&str]) -> bool {
+    let Some(host) = url::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_lowercase)) else {
+        return false;
+    };
+    hosts
+        .iter()
+        .any(|h| host == *h || host.ends_with(&format!(".{}", h)))
+}
+
+/// Case-insensitive match where `*` matches any run of characters
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let name = name.to_lowercase();
+    let parts: Vec<&str> = pattern.split('*').collect();
+
+    if parts.len() == 1 {
+        return pattern == name;
+    }
+
+    let mut rest = name.as_str();
+    for (i, part) in parts.iter().enumerate() {
+        if i == 0 {
+            match rest.strip_prefix(part) {
+                Some(r) => rest = r,
+                None => return false,
+            }
+        } else if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        } else {
+            match rest.find(part) {
+                Some(pos) => rest = &rest[pos + part.len()..],
+                None => return false,
+            }
+        }
+    }
+    true
+}
+
+/// Pick the file a link names from a folder listing
+///
+/// An exact name wins; otherwise the pattern must glob-match exactly one
+/// file. Without a pattern the folder must hold a single file.
+pub fn select_folder_entry(entries: &[FolderEntry], pattern: Option<&str>) -> Result<FolderEntry, DownloadError> {
+    let matches: Vec<&FolderEntry> = match pattern {
+        Some(pattern) => match entries.iter().find(|e| e.name == pattern) {
+            Some(exact) => vec![exact],
+            None => entries.iter().filter(|e| glob_match(pattern, &e.name)).collect(),
+        },
+        None => entries.iter().collect(),
+    };
+
+    match matches.as_slice() {
+        [entry] => Ok((*entry).clone()),
+        _ => Err(DownloadError::FolderSelection {
+            pattern: pattern.unwrap_or("*").to_string(),
+            matches: matches.len(),
+            available: entries.iter().map(|e| e.name.clone()).collect(),
+        }),
+    }
+}
+
+/// Work out the provider for a share URL
+///
+/// A thin wrapper over `ProviderRegistry::classify` for callers without a
+/// registry at hand; the download manager classifies through its own.
+pub fn classify_url(url: &str) -> ProviderType {
+    ProviderRegistry::with_defaults(reqwest::Client::new()).classify(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries() -> Vec<FolderEntry> {
+        ["Patch-A.mpq", "Patch-A-Lite.mpq", "readme.txt"]
+            .iter()
+            .map(|name| FolderEntry {
+                name: name.to_string(),
+                size: None,
+                url: format!("https://example.com/{}", name),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_select_folder_entry_by_name_or_glob() {
+        let entries = entries();
+        assert_eq!(select_folder_entry(&entries, Some("Patch-A.mpq")).unwrap().name, "Patch-A.mpq");
+        assert_eq!(select_folder_entry(&entries, Some("*Lite*")).unwrap().name, "Patch-A-Lite.mpq");
+        assert_eq!(select_folder_entry(&entries, Some("*.TXT")).unwrap().name, "readme.txt");
+    }
+
+    #[test]
+    fn test_select_folder_entry_rejects_ambiguous_or_missing() {
+        let entries = entries();
+        let ambiguous = select_folder_entry(&entries, Some("*.mpq"));
+        assert!(matches!(ambiguous, Err(DownloadError::FolderSelection { matches: 2, .. })));
+        let missing = select_folder_entry(&entries, Some("Patch-B.mpq"));
+        assert!(matches!(missing, Err(DownloadError::FolderSelection { matches: 0, .. })));
+        assert!(select_folder_entry(&entries, None).is_err());
+        assert_eq!(select_folder_entry(&entries[..1], None).unwrap().name, "Patch-A.mpq");
+    }
 }
@@ -0,0 +1,195 @@
+//! Provider registry
+//!
+//! Keeps every `DownloadProvider` in one list so that provider names from
+//! the catalog or frontend, and bare share URLs, are resolved in a single
+//! place. Adding a provider means implementing the trait in its own file
+//! and registering it in `with_defaults`.
+
+use std::sync::Arc;
+
+use super::{
+    DirectHttpProvider, DownloadProvider, DropboxProvider, GitHubReleaseProvider,
+    GoogleDriveProvider, MediafireProvider, MegaProvider, TransferProvider,
+};
+use crate::models::DownloadProvider as ProviderType;
+
+/// All known download providers
+#[derive(Clone, Default)]
+pub struct ProviderRegistry {
+    providers: Vec<Arc<dyn DownloadProvider>>,
+}
+
+impl ProviderRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with all built-in providers sharing one HTTP client
+    pub fn with_defaults(client: reqwest::Client) -> Self {
+        let mut registry = Self::new();
+        registry.register(GoogleDriveProvider::new(client.clone()));
+        registry.register(MediafireProvider::new(client.clone()));
+        registry.register(DropboxProvider::new(client.clone()));
+        registry.register(TransferProvider::new(client.clone()));
+        registry.register(MegaProvider::new(client.clone()));
+        registry.register(GitHubReleaseProvider::new(client.clone()));
+        registry.register(DirectHttpProvider::new(client));
+        registry
+    }
+
+    /// Add a provider, replacing any provider of the same type
+    pub fn register(&mut self, provider: impl DownloadProvider + 'static) {
+        self.providers.retain(|p| p.provider_type() != provider.provider_type());
+        self.providers.push(Arc::new(provider));
+    }
+
+    /// Get the provider for a type
+    pub fn get(&self, provider_type: ProviderType) -> Option<Arc<dyn DownloadProvider>> {
+        self.providers
+            .iter()
+            .find(|p| p.provider_type() == provider_type)
+            .cloned()
+    }
+
+    /// Parse a provider name or alias (case-insensitive)
+    pub fn parse_name(&self, name: &str) -> ProviderType {
+        let name = name.trim().to_lowercase();
+        self.providers
+            .iter()
+            .find(|p| p.name().to_lowercase() == name || p.aliases().contains(&name.as_str()))
+            .map(|p| p.provider_type())
+            .unwrap_or(ProviderType::Unknown)
+    }
+
+    /// Work out the provider from a share URL
+    ///
+    /// Other http(s) URLs classify as `ProviderType::Http`.
+    pub fn classify(&self, url: &str) -> ProviderType {
+        if let Some(provider) = self.providers.iter().find(|p| p.matches_url(url)) {
+            return provider.provider_type();
+        }
+        match url::Url::parse(url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => ProviderType::Http,
+            _ => ProviderType::Unknown,
+        }
+    }
+
+    /// Resolve an optional provider name against the URL
+    ///
+    /// A URL on a known provider's host wins over a missing or different
+    /// declared name; a declared name wins for generic http(s) URLs.
+    pub fn resolve(&self, name: Option<&str>, url: &str) -> ProviderType {
+        let declared = name.map(|n| self.parse_name(n)).unwrap_or(ProviderType::Unknown);
+        match self.classify(url) {
+            ProviderType::Http | ProviderType::Unknown if declared != ProviderType::Unknown => declared,
+            classified => classified,
+        }
+    }
+
+    /// Describe a declared provider that disagrees with its URL
+    pub fn check_declared(&self, name: Option<&str>, url: &str) -> Option<String> {
+        let name = name?;
+        let declared = self.parse_name(name);
+        let classified = self.classify(url);
+
+        if declared == ProviderType::Unknown {
+            return Some(format!("Unknown provider '{}' for {}, detected {:?}", name, url, classified));
+        }
+        if declared != classified && !matches!(classified, ProviderType::Http | ProviderType::Unknown) {
+            return Some(format!("Provider '{}' does not match {}, detected {:?}", name, url, classified));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> ProviderRegistry {
+        ProviderRegistry::with_defaults(reqwest::Client::new())
+    }
+
+    #[test]
+    fn test_parse_names_and_aliases() {
+        let registry = registry();
+        assert_eq!(registry.parse_name("mediafire"), ProviderType::Mediafire);
+        assert_eq!(registry.parse_name("GDrive"), ProviderType::GoogleDrive);
+        assert_eq!(registry.parse_name("google_drive"), ProviderType::GoogleDrive);
+        assert_eq!(registry.parse_name("Google Drive"), ProviderType::GoogleDrive);
+        assert_eq!(registry.parse_name("transfer"), ProviderType::Transfer);
+        assert_eq!(registry.parse_name("mega"), ProviderType::Mega);
+        assert_eq!(registry.parse_name("github"), ProviderType::GitHub);
+        assert_eq!(registry.parse_name("https"), ProviderType::Http);
+        assert_eq!(registry.parse_name("ftp"), ProviderType::Unknown);
+    }
+
+    #[test]
+    fn test_classify_urls() {
+        let registry = registry();
+        assert_eq!(
+            registry.classify("https://drive.google.com/file/d/abc/view"),
+            ProviderType::GoogleDrive
+        );
+        assert_eq!(
+            registry.classify("https://www.mediafire.com/file/abc/Patch-A.mpq/file"),
+            ProviderType::Mediafire
+        );
+        assert_eq!(
+            registry.classify("https://www.dropbox.com/s/abc/patch-8.mpq?dl=0"),
+            ProviderType::Dropbox
+        );
+        assert_eq!(registry.classify("https://mega.nz/file/abc#key"), ProviderType::Mega);
+        assert_eq!(registry.classify("https://transfer.it/t/abc"), ProviderType::Transfer);
+        assert_eq!(
+            registry.classify("github:isfir/VanillaHelpers@latest/*.dll"),
+            ProviderType::GitHub
+        );
+        assert_eq!(registry.classify("https://notmega.nz/file"), ProviderType::Http);
+        assert_eq!(registry.classify("ftp://example.com/Patch-A.mpq"), ProviderType::Unknown);
+        assert_eq!(registry.classify("not a url"), ProviderType::Unknown);
+    }
+
+    #[test]
+    fn test_resolve_falls_back_to_url() {
+        let registry = registry();
+        let url = "https://mega.nz/file/abc#key";
+        assert_eq!(registry.resolve(None, url), ProviderType::Mega);
+        assert_eq!(registry.resolve(Some("bogus"), url), ProviderType::Mega);
+        // A misstated provider is corrected from the URL
+        assert_eq!(registry.resolve(Some("dropbox"), url), ProviderType::Mega);
+
+        // Generic hosts keep the declared provider
+        let generic = "https://files.example.com/Patch-A.mpq";
+        assert_eq!(registry.resolve(Some("transfer"), generic), ProviderType::Transfer);
+        assert_eq!(registry.resolve(None, generic), ProviderType::Http);
+    }
+
+    #[test]
+    fn test_check_declared() {
+        let registry = registry();
+        let url = "https://mega.nz/file/abc#key";
+        assert!(registry.check_declared(None, url).is_none());
+        assert!(registry.check_declared(Some("MEGA"), url).is_none());
+        assert!(registry.check_declared(Some("dropbox"), url).is_some());
+        assert!(registry.check_declared(Some("bogus"), url).is_some());
+        assert!(registry.check_declared(Some("transfer"), "https://files.example.com/a.mpq").is_none());
+    }
+
+    #[test]
+    fn test_get_and_streaming_hook() {
+        let registry = registry();
+        assert!(registry.get(ProviderType::Mega).unwrap().streams_itself());
+        assert!(!registry.get(ProviderType::Dropbox).unwrap().streams_itself());
+        assert!(registry.get(ProviderType::Unknown).is_none());
+    }
+
+    #[test]
+    fn test_classify_url_without_registry() {
+        use super::super::classify_url;
+        assert_eq!(classify_url("https://drive.google.com/open?id=abc"), ProviderType::GoogleDrive);
+        assert_eq!(classify_url("https://example.com/Patch-A.mpq"), ProviderType::Http);
+        assert_eq!(classify_url("not a url"), ProviderType::Unknown);
+    }
+}
@@ -5,6 +5,7 @@
 use async_trait::async_trait;
 use super::{DirectDownloadInfo, DownloadProvider};
 use crate::download::DownloadError;
+use crate::models::DownloadProvider as ProviderType;
 
 /// Transfer.it download provider
 pub struct TransferProvider {
@@ -51,4 +52,16 @@ impl DownloadProvider for TransferProvider {
     fn name(&self) -> &'static str {
         "Transfer.it"
     }
+
+    fn provider_type(&self) -> ProviderType {
+        ProviderType::Transfer
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["transfer"]
+    }
+
+    fn url_hosts(&self) -> &'static [&'static str] {
+        &["transfer.it"]
+    }
 }
@@ -4,14 +4,16 @@
 //! allowing interrupted downloads to resume from where they left off.
 
 use futures_util::StreamExt;
-use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::header::{CONTENT_RANGE, CONTENT_TYPE, RANGE};
 use std::path::Path;
 use tauri::ipc::Channel;
 use tokio::fs::OpenOptions;
 use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tokio_util::sync::CancellationToken;
 
 use crate::download::{
     progress::{DownloadEvent, ProgressTracker},
+    sniff::{check_content, read_head, ExpectedFormat},
     DownloadError,
 };
 
@@ -26,22 +28,35 @@ use crate::download::{
 /// * `dest_path` - Local path to save the file
 /// * `download_id` - Unique identifier for this download
 /// * `on_event` - Channel to send progress events to frontend
+/// * `cancel` - Token that stops the download, keeping the partial file
 ///
 /// # Returns
-/// Ok(()) on success, or DownloadError on failure
+/// Ok(()) on success, `DownloadError::Cancelled` if cancelled, or another
+/// DownloadError on failure
 ///
 /// # Resume Behavior
 /// - If file exists, sends `Range: bytes=<size>-` header
 /// - 206 Partial Content: Resume from existing position
 /// - 200 OK: Server doesn't support Range, restart from scratch
 /// - 416 Range Not Satisfiable: File already complete
+///
+/// # Content Check
+/// The first bytes are checked against the format implied by the file name
+/// (see `sniff`) before the file is opened, so an HTML error page neither
+/// replaces nor extends a partial download. Resumed responses start mid-file
+/// and are only checked for HTML.
 pub async fn download_with_resume(
     client: &reqwest::Client,
     url: &str,
     dest_path: &Path,
     download_id: String,
     on_event: Channel<DownloadEvent>,
+    cancel: &CancellationToken,
 ) -> Result<(), DownloadError> {
+    if cancel.is_cancelled() {
+        return Err(DownloadError::Cancelled);
+    }
+
     // Check for existing partial file
     let start_pos = if dest_path.exists() {
         tokio::fs::metadata(dest_path)
@@ -132,6 +147,21 @@ pub async fn download_with_resume(
     log::info!("[Resume] Total size: {} bytes", total_size);
     log::info!("[Resume] Actual start position: {}", actual_start);
 
+    // Check what the server sent before touching the file
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let mut stream = response.bytes_stream();
+    let head = read_head(&mut stream).await?;
+    let expected = if actual_start > 0 {
+        ExpectedFormat::Any
+    } else {
+        ExpectedFormat::from_path(dest_path)
+    };
+    check_content(expected, content_type.as_deref(), &head)?;
+
     // Send started event
     on_event
         .send(DownloadEvent::Started {
@@ -167,10 +197,25 @@ pub async fn download_with_resume(
     let mut tracker = ProgressTracker::new(download_id.clone(), total_size);
     tracker.set_downloaded(actual_start);
 
-    // Stream download
-    let mut stream = response.bytes_stream();
+    file.write_all(&head).await.map_err(DownloadError::IoError)?;
+    tracker.update(head.len() as u64);
 
-    while let Some(chunk_result) = stream.next().await {
+    // Stream the rest of the download
+
+    loop {
+        let next = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => {
+                // Keep what was written so the next attempt resumes from here
+                file.flush().await.map_err(DownloadError::IoError)?;
+                log::info!("[Resume] Cancelled at {} bytes", tracker.downloaded_bytes());
+                return Err(DownloadError::Cancelled);
+            }
+            next = stream.next() => next,
+        };
+        let Some(chunk_result) = next else {
+            break;
+        };
         let chunk = chunk_result.map_err(DownloadError::RequestError)?;
         file.write_all(&chunk)
             .await
@@ -304,6 +349,87 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_resume_appends_to_partial_file() {
+        use crate::download::test_server::{serve, Route};
+
+        let partial = Route { status: 206, ..Route::ok("world") };
+        let (base, log) = serve(vec![(
+            "/Patch-A.mpq",
+            partial.header("Content-Range", "bytes 6-10/11"),
+        )])
+        .await;
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("Patch-A.mpq");
+        std::fs::write(&dest, "hello ").unwrap();
+
+        let url = format!("{}/Patch-A.mpq", base);
+        download_with_resume(
+            &reqwest::Client::new(),
+            &url,
+            &dest,
+            "id".to_string(),
+            Channel::new(|_| Ok(())),
+            &CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(std::fs::read_to_string(&dest).unwrap(), "hello world");
+        assert_eq!(log.lock().unwrap()[0].2.get("range").map(String::as_str), Some("bytes=6-"));
+    }
+
+    #[tokio::test]
+    async fn test_html_page_does_not_replace_partial_file() {
+        use crate::download::test_server::{serve, Route};
+
+        // Server ignores the Range header and sends a login page
+        let (base, _) = serve(vec![(
+            "/Patch-A.mpq",
+            Route::ok("<!DOCTYPE html><html><head><title>Sign in</title></head></html>")
+                .header("Content-Type", "text/html"),
+        )])
+        .await;
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("Patch-A.mpq");
+        std::fs::write(&dest, b"MPQ\x1Apartial").unwrap();
+
+        let result = download_with_resume(
+            &reqwest::Client::new(),
+            &format!("{}/Patch-A.mpq", base),
+            &dest,
+            "id".to_string(),
+            Channel::new(|_| Ok(())),
+            &CancellationToken::new(),
+        )
+        .await;
+
+        assert!(matches!(result, Err(DownloadError::UnexpectedContent { ref snippet, .. }) if snippet == "Sign in"));
+        assert_eq!(std::fs::read(&dest).unwrap(), b"MPQ\x1Apartial");
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_download_keeps_partial_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("Patch-A.mpq");
+        std::fs::write(&dest, "partial").unwrap();
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let result = download_with_resume(
+            &reqwest::Client::new(),
+            "http://127.0.0.1:9/Patch-A.mpq",
+            &dest,
+            "id".to_string(),
+            Channel::new(|_| Ok(())),
+            &cancel,
+        )
+        .await;
+
+        assert!(matches!(result, Err(DownloadError::Cancelled)));
+        assert_eq!(std::fs::read_to_string(&dest).unwrap(), "partial");
+    }
+
     /// Helper to test URL-based filename extraction without Response
     fn extract_filename_from_url(url: &str) -> String {
         url.split('/')
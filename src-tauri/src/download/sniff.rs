@@ -0,0 +1,244 @@
+//! Content sniffing for downloads
+//!
+//! Hosts often answer with an HTML page and status 200 instead of the file:
+//! login walls, captcha pages, quota pages. Before anything is written to
+//! disk, the first bytes of the response are checked against the format the
+//! destination file name implies (MPQ magic, archive magics, PE header), and
+//! HTML is rejected outright.
+
+use futures_util::{Stream, StreamExt};
+use std::fmt;
+use std::path::Path;
+
+use crate::download::DownloadError;
+
+/// Bytes read from the start of a response before checking it
+///
+/// Large enough to reach the `ustar` magic of tar archives at offset 257.
+pub const SNIFF_LEN: usize = 512;
+
+/// Longest page snippet included in errors
+const SNIPPET_LEN: usize = 160;
+
+/// What a download is expected to contain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpectedFormat {
+    /// MPQ archive (`MPQ\x1A`, or `MPQ\x1B` with a user data header), or
+    /// an archive it is packaged in
+    Mpq,
+    /// zip, 7z, rar, tar or a compressed tarball
+    Archive,
+    /// Windows PE file (`MZ`)
+    Executable,
+    /// Anything that is not an HTML page
+    Any,
+}
+
+impl ExpectedFormat {
+    /// Work out the expected format from a destination file name
+    pub fn from_path(path: &Path) -> Self {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let ext = name.rsplit('.').next().unwrap_or("");
+
+        match ext {
+            "mpq" => ExpectedFormat::Mpq,
+            "zip" | "7z" | "rar" | "tar" | "gz" | "tgz" | "bz2" | "xz" => ExpectedFormat::Archive,
+            "dll" | "exe" => ExpectedFormat::Executable,
+            _ => ExpectedFormat::Any,
+        }
+    }
+
+    /// Check the leading bytes of a file against this format
+    pub fn matches(self, head: &[u8]) -> bool {
+        match self {
+            ExpectedFormat::Mpq => head.starts_with(b"MPQ\x1A") || head.starts_with(b"MPQ\x1B") || is_archive(head),
+            ExpectedFormat::Archive => is_archive(head),
+            ExpectedFormat::Executable => head.starts_with(b"MZ"),
+            ExpectedFormat::Any => !looks_like_html(head),
+        }
+    }
+}
+
+impl fmt::Display for ExpectedFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ExpectedFormat::Mpq => "an MPQ archive",
+            ExpectedFormat::Archive => "an archive",
+            ExpectedFormat::Executable => "a Windows executable",
+            ExpectedFormat::Any => "a file",
+        })
+    }
+}
+
+/// Check for any supported archive magic
+fn is_archive(head: &[u8]) -> bool {
+    const MAGICS: &[&[u8]] = &[
+        b"PK\x03\x04",                 // zip
+        b"PK\x05\x06",                 // empty zip
+        b"7z\xBC\xAF\x27\x1C",         // 7z
+        b"Rar!\x1A\x07",               // rar 4 and 5
+        b"\x1F\x8B",                   // gzip
+        b"BZh",                        // bzip2
+        b"\xFD7zXZ\x00",               // xz
+    ];
+    MAGICS.iter().any(|m| head.starts_with(m))
+        || head.get(257..262) == Some(b"ustar".as_slice())
+}
+
+/// Whether the bytes look like the start of an HTML or XML page
+pub fn looks_like_html(head: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(&head[..head.len().min(SNIFF_LEN)]);
+    let text = text.trim_start_matches('\u{feff}').trim_start().to_lowercase();
+    ["<!doctype", "<html", "<head", "<body", "<?xml", "<script", "<meta", "<title"]
+        .iter()
+        .any(|tag| text.starts_with(tag))
+}
+
+/// Read up to `SNIFF_LEN` bytes from the start of a response stream
+pub async fn read_head<S, B>(stream: &mut S) -> Result<Vec<u8>, DownloadError>
+where
+    S: Stream<Item = Result<B, reqwest::Error>> + Unpin,
+    B: AsRef<[u8]>,
+{
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    while head.len() < SNIFF_LEN {
+        match stream.next().await {
+            Some(chunk) => head.extend_from_slice(chunk?.as_ref()),
+            None => break,
+        }
+    }
+    Ok(head)
+}
+
+/// Check a response's Content-Type and first bytes against the expected format
+///
+/// A recognised magic number wins over a misleading Content-Type. HTML
+/// (by header or by content) and unrecognised bytes for a specific format
+/// are rejected with a snippet of what was received.
+pub fn check_content(expected: ExpectedFormat, content_type: Option<&str>, head: &[u8]) -> Result<(), DownloadError> {
+    let content_type = content_type.unwrap_or("unknown content type");
+    let html_type = content_type.contains("text/html") || content_type.contains("application/xhtml");
+
+    let accepted = match expected {
+        ExpectedFormat::Any => !html_type && expected.matches(head),
+        _ => expected.matches(head),
+    };
+    if accepted {
+        return Ok(());
+    }
+
+    log::info!("[Sniff] Expected {}, got {} ({} bytes)", expected, content_type, head.len());
+    Err(DownloadError::UnexpectedContent {
+        expected: expected.to_string(),
+        content_type: content_type.to_string(),
+        snippet: snippet(head),
+    })
+}
+
+/// Describe the start of a response for an error message
+///
+/// Uses the page title when there is one, otherwise the first characters
+/// with whitespace collapsed and control characters dropped.
+fn snippet(head: &[u8]) -> String {
+    if head.is_empty() {
+        return "(empty response)".to_string();
+    }
+
+    let text = String::from_utf8_lossy(head);
+    // ASCII-only lowering keeps byte offsets valid for slicing `text`
+    let lower = text.to_ascii_lowercase();
+    if let (Some(start), Some(end)) = (lower.find("<title>"), lower.find("</title>")) {
+        let title = text[start + 7..end.max(start + 7)].trim();
+        if !title.is_empty() {
+            return title.chars().take(SNIPPET_LEN).collect();
+        }
+    }
+
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(SNIPPET_LEN)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expected_format_from_path() {
+        assert_eq!(ExpectedFormat::from_path(Path::new("/d/Patch-A.MPQ")), ExpectedFormat::Mpq);
+        assert_eq!(ExpectedFormat::from_path(Path::new("patch-a.7z")), ExpectedFormat::Archive);
+        assert_eq!(ExpectedFormat::from_path(Path::new("dxvk-2.3.tar.gz")), ExpectedFormat::Archive);
+        assert_eq!(ExpectedFormat::from_path(Path::new("VanillaHelpers.dll")), ExpectedFormat::Executable);
+        assert_eq!(ExpectedFormat::from_path(Path::new("download")), ExpectedFormat::Any);
+    }
+
+    #[test]
+    fn test_accepts_matching_magic() {
+        assert!(check_content(ExpectedFormat::Mpq, Some("application/octet-stream"), b"MPQ\x1A\x20\x00").is_ok());
+        assert!(check_content(ExpectedFormat::Mpq, None, b"MPQ\x1B\x00\x00").is_ok());
+        // Patches packaged in an archive are unpacked at install time
+        assert!(check_content(ExpectedFormat::Mpq, None, b"7z\xBC\xAF\x27\x1C\x00").is_ok());
+        assert!(check_content(ExpectedFormat::Archive, None, b"Rar!\x1A\x07\x01\x00").is_ok());
+        assert!(check_content(ExpectedFormat::Archive, None, b"PK\x03\x04rest").is_ok());
+        assert!(check_content(ExpectedFormat::Executable, None, b"MZ\x90\x00").is_ok());
+
+        let mut tar = vec![0u8; 512];
+        tar[257..262].copy_from_slice(b"ustar");
+        assert!(check_content(ExpectedFormat::Archive, None, &tar).is_ok());
+    }
+
+    #[test]
+    fn test_magic_wins_over_wrong_content_type() {
+        assert!(check_content(ExpectedFormat::Mpq, Some("text/html; charset=utf-8"), b"MPQ\x1A\x20\x00").is_ok());
+    }
+
+    #[test]
+    fn test_rejects_html_page_with_title_snippet() {
+        let page = b"\n<!DOCTYPE html><html><head><title>Dropbox - Sign in</title></head><body>...</body></html>";
+        let err = check_content(ExpectedFormat::Mpq, Some("text/html; charset=utf-8"), page).unwrap_err();
+        match err {
+            DownloadError::UnexpectedContent { expected, content_type, snippet } => {
+                assert_eq!(expected, "an MPQ archive");
+                assert_eq!(content_type, "text/html; charset=utf-8");
+                assert_eq!(snippet, "Dropbox - Sign in");
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_title_after_character_that_grows_when_lowercased() {
+        // `İ` grows by a byte under Unicode lowercasing, which would put
+        // the title's start inside `Ü`
+        let page = "<html><head>İ<TITLE>Überprüfung – Zugriff</TITLE></head>".as_bytes();
+        assert_eq!(snippet(page), "Überprüfung – Zugriff");
+    }
+
+    #[test]
+    fn test_rejects_unknown_bytes_for_specific_format() {
+        let err = check_content(ExpectedFormat::Mpq, Some("text/plain"), b"Rate limit exceeded\r\n  retry later").unwrap_err();
+        assert!(matches!(err, DownloadError::UnexpectedContent { ref snippet, .. } if snippet == "Rate limit exceeded retry later"));
+        assert!(check_content(ExpectedFormat::Mpq, None, b"").is_err());
+    }
+
+    #[test]
+    fn test_any_format_only_rejects_html() {
+        assert!(check_content(ExpectedFormat::Any, Some("application/octet-stream"), b"\x00\x01binary").is_ok());
+        assert!(check_content(ExpectedFormat::Any, None, b"<html><body>captcha</body></html>").is_err());
+        assert!(check_content(ExpectedFormat::Any, Some("text/html"), b"\x00\x01binary").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_head_joins_chunks() {
+        let chunks: Vec<Result<Vec<u8>, reqwest::Error>> = vec![Ok(b"MP".to_vec()), Ok(b"Q\x1A".to_vec())];
+        let mut stream = futures_util::stream::iter(chunks);
+        assert_eq!(read_head(&mut stream).await.unwrap(), b"MPQ\x1A");
+    }
+}
@@ -0,0 +1,92 @@
+//! Minimal HTTP server for provider tests
+//!
+//! Serves canned responses keyed by request path so providers can be
+//! tested against an injectable base URL without network access.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// A canned response
+#[derive(Clone)]
+pub struct Route {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Route {
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self { status: 200, headers: Vec::new(), body: body.into() }
+    }
+
+    pub fn status(status: u16) -> Self {
+        Self { status, headers: Vec::new(), body: Vec::new() }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Requests seen by the server as (method, path, headers)
+pub type RequestLog = Arc<Mutex<Vec<(String, String, HashMap<String, String>)>>>;
+
+/// Start a server on a random local port, returning its base URL
+///
+/// Paths are matched exactly (query string included); unknown paths get 404.
+pub async fn serve(routes: Vec<(&str, Route)>) -> (String, RequestLog) {
+    let routes: Arc<HashMap<String, Route>> = Arc::new(
+        routes.into_iter().map(|(path, route)| (path.to_string(), route)).collect(),
+    );
+    let log: RequestLog = Arc::new(Mutex::new(Vec::new()));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+    let server_log = log.clone();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let routes = routes.clone();
+            let log = server_log.clone();
+            tokio::spawn(async move {
+                let mut buffer = Vec::new();
+                let mut chunk = [0u8; 4096];
+                while !buffer.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut chunk).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+                    }
+                }
+
+                let request = String::from_utf8_lossy(&buffer).to_string();
+                let mut lines = request.lines();
+                let mut parts = lines.next().unwrap_or("").split_whitespace();
+                let method = parts.next().unwrap_or("").to_string();
+                let path = parts.next().unwrap_or("").to_string();
+                let headers: HashMap<String, String> = lines
+                    .take_while(|l| !l.is_empty())
+                    .filter_map(|l| l.split_once(':'))
+                    .map(|(k, v)| (k.trim().to_lowercase(), v.trim().to_string()))
+                    .collect();
+                log.lock().unwrap().push((method.clone(), path.clone(), headers));
+
+                let route = routes.get(&path).cloned().unwrap_or_else(|| Route::status(404));
+                let mut response = format!("HTTP/1.1 {} X\r\nConnection: close\r\n", route.status);
+                for (name, value) in &route.headers {
+                    response.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                response.push_str(&format!("Content-Length: {}\r\n\r\n", route.body.len()));
+
+                let _ = stream.write_all(response.as_bytes()).await;
+                if method != "HEAD" {
+                    let _ = stream.write_all(&route.body).await;
+                }
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+
+    (base_url, log)
+}
@@ -0,0 +1,285 @@
+//! Addon detection and packaging
+//!
+//! Addons live in `Interface/AddOns/<name>/` and are recognised by the
+//! client through `<name>.toc`. Addon downloads are usually zips of one
+//! or more addon folders, often named after a git branch
+//! (`pfUI-master/pfUI.toc`), so each folder is installed under the name
+//! of its `.toc` file. The 1.12 client flags any addon whose
+//! `## Interface:` line is not 11200 as out of date.
+
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use super::casefold::resolve;
+use super::copier::InstallError;
+use super::extract::ExtractedFile;
+use crate::models::PatchId;
+
+/// Folder addons are loaded from, relative to the WoW root
+pub const ADDONS_DIR: &str = "Interface/AddOns";
+
+/// `## Interface:` version of the 1.12 client
+pub const CLIENT_INTERFACE: u32 = 11200;
+
+/// Metadata from an addon's `.toc` file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Toc {
+    pub interface: Option<u32>,
+    pub title: Option<String>,
+    pub version: Option<String>,
+    pub notes: Option<String>,
+}
+
+impl Toc {
+    /// Parse the `## Key: Value` header lines of a `.toc` file
+    pub fn parse(text: &str) -> Self {
+        let mut toc = Self::default();
+        for line in text.lines() {
+            let Some(tag) = line.trim().strip_prefix("##") else {
+                continue;
+            };
+            let Some((key, value)) = tag.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            if value.is_empty() {
+                continue;
+            }
+            match key.trim().to_ascii_lowercase().as_str() {
+                "interface" => toc.interface = value.parse().ok(),
+                "title" => toc.title = Some(value.to_string()),
+                "version" => toc.version = Some(value.to_string()),
+                "notes" => toc.notes = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        toc
+    }
+
+    /// Whether the 1.12 client loads the addon without marking it out of date
+    pub fn matches_client(&self) -> bool {
+        self.interface == Some(CLIENT_INTERFACE)
+    }
+}
+
+/// An addon folder found in `Interface/AddOns`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstalledAddon {
+    /// Folder name, which is also the `.toc` file's name
+    pub name: String,
+    pub title: Option<String>,
+    pub version: Option<String>,
+    pub interface: Option<u32>,
+    /// `## Interface:` is missing or not 11200
+    pub interface_mismatch: bool,
+    /// Catalog patch that installed the addon, if any
+    pub patch_id: Option<PatchId>,
+}
+
+/// Find `<folder>/<folder>.toc`, ignoring case like the client on Windows
+async fn find_toc(folder: &Path, name: &str) -> Option<PathBuf> {
+    let wanted = format!("{}.toc", name.to_lowercase());
+    let mut entries = fs::read_dir(folder).await.ok()?;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if entry.file_name().to_string_lossy().to_lowercase() == wanted {
+            return Some(entry.path());
+        }
+    }
+    None
+}
+
+/// List the addons in a WoW folder, sorted by name
+///
+/// Folders without a matching `.toc` are skipped, as the client skips
+/// them too. The folder is found in any casing (e.g. `interface/addons`
+/// under Wine). `patch_id` is left empty for the caller to fill in.
+pub async fn scan_addons(wow_path: &Path) -> Result<Vec<InstalledAddon>, InstallError> {
+    let addons_dir = resolve(wow_path, ADDONS_DIR);
+    let mut entries = match fs::read_dir(&addons_dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut addons = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        if !entry.file_type().await?.is_dir() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(toc_path) = find_toc(&entry.path(), &name).await else {
+            continue;
+        };
+        let toc = Toc::parse(&String::from_utf8_lossy(&fs::read(&toc_path).await?));
+        addons.push(InstalledAddon {
+            interface_mismatch: !toc.matches_client(),
+            name,
+            title: toc.title,
+            version: toc.version,
+            interface: toc.interface,
+            patch_id: None,
+        });
+    }
+
+    addons.sort_by_key(|a| a.name.to_lowercase());
+    Ok(addons)
+}
+
+/// Get the addon folder name a target path installs into, if any
+pub fn addon_of_target(target: &str) -> Option<&str> {
+    let mut parts = target.split('/');
+    let interface = parts.next()?;
+    let addons = parts.next()?;
+    let name = parts.next()?;
+    (interface.eq_ignore_ascii_case("Interface") && addons.eq_ignore_ascii_case("AddOns") && parts.next().is_some())
+        .then_some(name)
+}
+
+/// Get the addon name of a `.toc` file name
+fn toc_stem(file_name: &str) -> Option<&str> {
+    let split = file_name.len().checked_sub(4)?;
+    file_name
+        .get(split..)?
+        .eq_ignore_ascii_case(".toc")
+        .then(|| &file_name[..split])
+}
+
+/// Work out where the files of an addon archive go
+///
+/// Every folder holding a `.toc` is an addon, installed as
+/// `Interface/AddOns/<toc name>/`. When a folder has several `.toc` files
+/// (e.g. one per client flavour) the one named like the folder wins,
+/// otherwise the shortest name. Addons nested inside another addon's
+/// folder (bundled libraries) stay part of the outer addon.
+pub fn map_addon_folders(extracted: &[ExtractedFile]) -> Result<Vec<(PathBuf, String)>, InstallError> {
+    // (folder inside the archive with a trailing '/', addon name)
+    let mut folders: Vec<(String, String)> = Vec::new();
+    for file in extracted {
+        let Some(stem) = toc_stem(file.name.rsplit('/').next().unwrap_or_default()) else {
+            continue;
+        };
+        let folder = &file.name[..file.name.len() - stem.len() - 4];
+        let folder_name = folder.trim_end_matches('/').rsplit('/').next().unwrap_or_default();
+
+        match folders.iter_mut().find(|(f, _)| f == folder) {
+            Some((_, name)) => {
+                let better = stem.eq_ignore_ascii_case(folder_name)
+                    || (!name.eq_ignore_ascii_case(folder_name) && stem.len() < name.len());
+                if better {
+                    *name = stem.to_string();
+                }
+            }
+            None => folders.push((folder.to_string(), stem.to_string())),
+        }
+    }
+
+    let outer: Vec<&(String, String)> = folders
+        .iter()
+        .filter(|(folder, _)| !folders.iter().any(|(other, _)| other != folder && folder.starts_with(other.as_str())))
+        .collect();
+    if outer.is_empty() {
+        return Err(InstallError::ArchiveSelection {
+            pattern: "*.toc".to_string(),
+            matches: 0,
+            available: extracted.iter().map(|f| f.name.clone()).collect(),
+        });
+    }
+
+    let mut mapped = Vec::new();
+    for (folder, name) in outer {
+        if name.is_empty() || name.contains(['\\', ':']) || name == "." || name == ".." {
+            return Err(InstallError::InvalidTarget(name.clone()));
+        }
+        for file in extracted.iter().filter(|f| f.name.starts_with(folder.as_str())) {
+            let target = format!("{}/{}/{}", ADDONS_DIR, name, &file.name[folder.len()..]);
+            mapped.push((file.path.clone(), target));
+        }
+    }
+    Ok(mapped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn extracted(names: &[&str]) -> Vec<ExtractedFile> {
+        names
+            .iter()
+            .map(|n| ExtractedFile { name: n.to_string(), path: PathBuf::from("/staging").join(n) })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_toc() {
+        let toc = Toc::parse("## Interface: 11200\r\n## Title: |cff33ffccpf|rUI\n## Version: 5.4\n##Notes:\nlibs\\init.lua\n");
+        assert_eq!(toc.interface, Some(11200));
+        assert_eq!(toc.title.as_deref(), Some("|cff33ffccpf|rUI"));
+        assert_eq!(toc.version.as_deref(), Some("5.4"));
+        assert_eq!(toc.notes, None);
+        assert!(toc.matches_client());
+
+        assert!(!Toc::parse("## Interface: 20400").matches_client());
+        assert!(!Toc::parse("## Title: No interface").matches_client());
+    }
+
+    #[test]
+    fn test_map_addon_folders() {
+        let files = extracted(&[
+            "pfUI-master/pfUI.toc",
+            "pfUI-master/pfUI-tbc.toc",
+            "pfUI-master/libs/LibStub/LibStub.toc",
+            "pfUI-master/libs/LibStub/LibStub.lua",
+            "Extra/Extra.toc",
+            "README.md",
+        ]);
+        let mapped = map_addon_folders(&files).unwrap();
+        let targets: Vec<&str> = mapped.iter().map(|(_, t)| t.as_str()).collect();
+        assert_eq!(
+            targets,
+            vec![
+                "Interface/AddOns/pfUI/pfUI.toc",
+                "Interface/AddOns/pfUI/pfUI-tbc.toc",
+                "Interface/AddOns/pfUI/libs/LibStub/LibStub.toc",
+                "Interface/AddOns/pfUI/libs/LibStub/LibStub.lua",
+                "Interface/AddOns/Extra/Extra.toc",
+            ]
+        );
+
+        assert!(matches!(
+            map_addon_folders(&extracted(&["readme.txt"])),
+            Err(InstallError::ArchiveSelection { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_scan_addons() {
+        let wow = tempdir().unwrap();
+        let addons = wow.path().join(ADDONS_DIR);
+        std::fs::create_dir_all(addons.join("pfUI")).unwrap();
+        std::fs::write(addons.join("pfUI/pfui.TOC"), "## Interface: 11200\n## Version: 5.4\n").unwrap();
+        std::fs::create_dir_all(addons.join("Questie")).unwrap();
+        std::fs::write(addons.join("Questie/Questie.toc"), "## Interface: 11302\n").unwrap();
+        std::fs::create_dir_all(addons.join("NoToc")).unwrap();
+
+        let found = scan_addons(wow.path()).await.unwrap();
+        let names: Vec<&str> = found.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["pfUI", "Questie"]);
+        assert!(!found[0].interface_mismatch);
+        assert_eq!(found[0].version.as_deref(), Some("5.4"));
+        assert!(found[1].interface_mismatch);
+
+        // Folders created by hand under Wine are often lowercase
+        let lower = tempdir().unwrap();
+        std::fs::create_dir_all(lower.path().join("interface/addons/pfUI")).unwrap();
+        std::fs::write(lower.path().join("interface/addons/pfUI/pfUI.toc"), "## Interface: 11200\n").unwrap();
+        let found = scan_addons(lower.path()).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "pfUI");
+
+        assert_eq!(addon_of_target("Interface/AddOns/pfUI/pfUI.toc"), Some("pfUI"));
+        assert_eq!(addon_of_target("Data/Patch-A.mpq"), None);
+    }
+}
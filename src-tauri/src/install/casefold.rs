@@ -0,0 +1,119 @@
+//! Case-insensitive path resolution
+//!
+//! The client treats paths case-insensitively, and Wine matches names
+//! regardless of case on Linux, so `Data/patch-a.MPQ` and
+//! `Data/Patch-A.mpq` are the same file to the game but two files to us.
+//! Paths into a WoW folder are resolved against what is on disk so
+//! existing files are found and replaced under their current casing.
+//! On case-insensitive filesystems the exact lookup always succeeds and
+//! paths are returned as given.
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Compare two file names the way Windows does (ignoring case)
+fn same_name(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+/// List the entries of `dir` whose name matches `name` ignoring case, sorted
+fn matching_entries(dir: &Path, name: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut matches: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| same_name(&e.file_name().to_string_lossy(), name))
+        .map(|e| e.path())
+        .collect();
+    matches.sort();
+    matches
+}
+
+/// Resolve `relative` below `base`, reusing the on-disk casing of every
+/// component that exists in some casing
+///
+/// An exact match wins; otherwise the first case variant in sorted order.
+/// Components after the first missing one are kept as given, so the
+/// result is also the right path for creating a new file.
+pub fn resolve(base: &Path, relative: impl AsRef<Path>) -> PathBuf {
+    let mut path = base.to_path_buf();
+    let mut missing = false;
+
+    for component in relative.as_ref().components() {
+        let Component::Normal(name) = component else {
+            path.push(component);
+            continue;
+        };
+        let exact = path.join(name);
+        if missing || fs::symlink_metadata(&exact).is_ok() {
+            path = exact;
+            continue;
+        }
+        match matching_entries(&path, &name.to_string_lossy()).into_iter().next() {
+            Some(existing) => path = existing,
+            None => {
+                missing = true;
+                path = exact;
+            }
+        }
+    }
+    path
+}
+
+/// List every file in `path`'s folder that differs from it only in case,
+/// including `path` itself if it exists
+pub fn case_variants(path: &Path) -> Vec<PathBuf> {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => matching_entries(parent, &name.to_string_lossy()),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// Whether the temp folder's filesystem tells names apart by case
+    fn case_sensitive(dir: &Path) -> bool {
+        fs::write(dir.join("probe"), b"").unwrap();
+        let sensitive = !dir.join("PROBE").exists();
+        fs::remove_file(dir.join("probe")).unwrap();
+        sensitive
+    }
+
+    #[test]
+    fn test_resolve_reuses_existing_casing() {
+        let wow = tempdir().unwrap();
+        if !case_sensitive(wow.path()) {
+            return;
+        }
+        fs::create_dir_all(wow.path().join("data")).unwrap();
+        fs::write(wow.path().join("data/patch-a.MPQ"), b"a").unwrap();
+
+        assert_eq!(resolve(wow.path(), "Data/Patch-A.mpq"), wow.path().join("data/patch-a.MPQ"));
+        assert_eq!(resolve(wow.path(), "Data/Patch-B.mpq"), wow.path().join("data/Patch-B.mpq"));
+        assert_eq!(resolve(wow.path(), "WTF/Config.wtf"), wow.path().join("WTF/Config.wtf"));
+        assert_eq!(resolve(wow.path(), "Data"), wow.path().join("data"));
+    }
+
+    #[test]
+    fn test_case_variants() {
+        let wow = tempdir().unwrap();
+        if !case_sensitive(wow.path()) {
+            return;
+        }
+        fs::write(wow.path().join("Patch-A.mpq"), b"a").unwrap();
+        fs::write(wow.path().join("patch-a.MPQ"), b"a").unwrap();
+        fs::write(wow.path().join("Patch-B.mpq"), b"b").unwrap();
+
+        assert_eq!(
+            case_variants(&wow.path().join("PATCH-A.MPQ")),
+            vec![wow.path().join("Patch-A.mpq"), wow.path().join("patch-a.MPQ")]
+        );
+        // The exact name wins over other variants
+        assert_eq!(resolve(wow.path(), "patch-a.MPQ"), wow.path().join("patch-a.MPQ"));
+        assert_eq!(case_variants(&wow.path().join("Patch-B.mpq")).len(), 1);
+    }
+}
@@ -0,0 +1,422 @@
+//! WTF/Config.wtf editing
+//!
+//! Config.wtf is a list of `SET name "value"` lines. Patches can declare
+//! CVars they need; applying them rewrites only the matching lines and
+//! keeps everything else (comments, unknown lines, line endings) as-is.
+//! The previous values are returned so uninstall can put them back.
+//!
+//! Every write by the patcher first copies the current file to
+//! `Config.wtf.cvars-backup`. Only the latest copy is kept: the game
+//! rewrites Config.wtf on exit, so an older one would throw away settings
+//! changed in game since. The copy is there for recovering by hand; the
+//! patcher undoes its own changes from the recorded previous values.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use super::casefold::resolve;
+use super::copier::InstallError;
+use crate::models::PatchId;
+
+/// Location of Config.wtf relative to the WoW root
+pub const CONFIG_WTF: &str = "WTF/Config.wtf";
+
+/// A CVar change made by installing a patch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigChange {
+    pub key: String,
+    pub value: String,
+    /// Value before the patch set it, `None` if the line did not exist
+    #[serde(default)]
+    pub previous: Option<String>,
+}
+
+/// A patch and the CVars it declares
+pub type PatchCvars = (PatchId, BTreeMap<String, String>);
+
+/// Suffix of the copy made before the patcher writes Config.wtf
+const BACKUP_SUFFIX: &str = ".cvars-backup";
+
+/// A CVar a patch recommends, next to its current value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CvarStatus {
+    pub patch_id: PatchId,
+    pub key: String,
+    /// Value in Config.wtf, `None` when the CVar is not set
+    pub current: Option<String>,
+    pub recommended: String,
+    /// The patch's install recorded a change to this CVar, so
+    /// reverting or uninstalling it puts the earlier value back
+    pub managed: bool,
+}
+
+/// Merge newly applied changes into the ones a patch recorded earlier
+///
+/// A CVar changed before keeps its oldest `previous`, so reverting goes
+/// back to the value from before the patch ever touched it.
+pub fn merge_changes(recorded: &mut Vec<ConfigChange>, changes: Vec<ConfigChange>) {
+    for change in changes {
+        match recorded.iter_mut().find(|c| c.key.eq_ignore_ascii_case(&change.key)) {
+            Some(existing) => existing.value = change.value,
+            None => recorded.push(change),
+        }
+    }
+}
+
+/// Take over a change another patch handed over when it was reverted
+///
+/// The handed-over `previous` is older than anything recorded for the
+/// key, so it replaces the recorded one.
+pub fn take_over_change(recorded: &mut Vec<ConfigChange>, change: ConfigChange) {
+    match recorded.iter_mut().find(|c| c.key.eq_ignore_ascii_case(&change.key)) {
+        Some(existing) => existing.previous = change.previous,
+        None => recorded.push(change),
+    }
+}
+
+/// Compare a patch's CVars with a Config.wtf
+pub fn cvar_statuses(
+    config: &ConfigWtf,
+    patch_id: &str,
+    cvars: &BTreeMap<String, String>,
+    recorded: &[ConfigChange],
+) -> Vec<CvarStatus> {
+    cvars
+        .iter()
+        .map(|(key, value)| CvarStatus {
+            patch_id: patch_id.to_string(),
+            key: key.clone(),
+            current: config.get(key).map(str::to_string),
+            recommended: value.clone(),
+            managed: recorded.iter().any(|c| c.key.eq_ignore_ascii_case(key)),
+        })
+        .collect()
+}
+
+/// Parsed Config.wtf
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigWtf {
+    lines: Vec<String>,
+    crlf: bool,
+}
+
+/// Split a `SET name "value"` line into its name and unquoted value
+fn parse_set(line: &str) -> Option<(&str, &str)> {
+    let trimmed = line.trim();
+    let keyword = trimmed.get(..4)?;
+    if !keyword.eq_ignore_ascii_case("set ") {
+        return None;
+    }
+    let rest = trimmed[4..].trim_start();
+    let (key, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let value = value.trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value);
+    Some((key, value))
+}
+
+impl ConfigWtf {
+    /// Get the path of Config.wtf in a WoW folder, in its on-disk casing
+    pub fn path(wow_path: &Path) -> PathBuf {
+        resolve(wow_path, CONFIG_WTF)
+    }
+
+    pub fn parse(text: &str) -> Self {
+        Self {
+            lines: text.lines().map(str::to_string).collect(),
+            crlf: text.contains("\r\n"),
+        }
+    }
+
+    /// Read Config.wtf, treating a missing file as empty
+    pub async fn load(path: &Path) -> Result<Self, InstallError> {
+        match fs::read(path).await {
+            Ok(bytes) => Ok(Self::parse(&String::from_utf8_lossy(&bytes))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Get the path of the backup made before the patcher's last write
+    pub fn backup_path(path: &Path) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(BACKUP_SUFFIX);
+        path.with_file_name(name)
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<(), InstallError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(path, self.to_string()).await?;
+        Ok(())
+    }
+
+    /// Save over an existing file after copying it to the backup
+    pub async fn save_with_backup(&self, path: &Path) -> Result<(), InstallError> {
+        if path.exists() {
+            fs::copy(path, Self::backup_path(path)).await?;
+        }
+        self.save(path).await
+    }
+
+    /// Get a CVar's value (names are case-insensitive, like the game's)
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| parse_set(line))
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    }
+
+    /// Set a CVar, replacing its line or appending a new one
+    pub fn set(&mut self, key: &str, value: &str) {
+        let line = format!("SET {} \"{}\"", key, value);
+        let existing = self
+            .lines
+            .iter()
+            .position(|l| parse_set(l).is_some_and(|(k, _)| k.eq_ignore_ascii_case(key)));
+        match existing {
+            Some(index) => self.lines[index] = line,
+            None => self.lines.push(line),
+        }
+    }
+
+    /// Remove every line setting a CVar
+    pub fn remove(&mut self, key: &str) {
+        self.lines
+            .retain(|l| !parse_set(l).is_some_and(|(k, _)| k.eq_ignore_ascii_case(key)));
+    }
+}
+
+impl fmt::Display for ConfigWtf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let newline = if self.crlf { "\r\n" } else { "\n" };
+        for line in &self.lines {
+            write!(f, "{}{}", line, newline)?;
+        }
+        Ok(())
+    }
+}
+
+/// Set CVars in a WoW folder's Config.wtf, returning what changed
+///
+/// CVars that already hold the wanted value are not recorded, so
+/// reverting never touches settings the patch did not change.
+pub async fn apply_cvars(
+    wow_path: &Path,
+    cvars: &BTreeMap<String, String>,
+) -> Result<Vec<ConfigChange>, InstallError> {
+    let path = ConfigWtf::path(wow_path);
+    let mut config = ConfigWtf::load(&path).await?;
+
+    let mut changes = Vec::new();
+    for (key, value) in cvars {
+        let previous = config.get(key).map(str::to_string);
+        if previous.as_deref() == Some(value.as_str()) {
+            continue;
+        }
+        config.set(key, value);
+        changes.push(ConfigChange {
+            key: key.clone(),
+            value: value.clone(),
+            previous,
+        });
+    }
+
+    if !changes.is_empty() {
+        config.save_with_backup(&path).await?;
+    }
+    Ok(changes)
+}
+
+/// Undo CVar changes made by `apply_cvars`
+///
+/// A CVar that no longer holds the value the patch set was changed by
+/// the user or the game since, and is left alone. A CVar one of `others`
+/// (the other installed patches and the CVars they declare) still needs
+/// gets that patch's value instead, and the change is returned for the
+/// patch to take over, so its own uninstall goes back to the value from
+/// before either patch.
+pub async fn revert_cvars(
+    wow_path: &Path,
+    changes: &[ConfigChange],
+    others: &[PatchCvars],
+) -> Result<Vec<(PatchId, ConfigChange)>, InstallError> {
+    if changes.is_empty() {
+        return Ok(Vec::new());
+    }
+    let path = ConfigWtf::path(wow_path);
+    let original = ConfigWtf::load(&path).await?;
+    let mut config = original.clone();
+    let mut handed_over = Vec::new();
+
+    for change in changes {
+        let owner = others.iter().find_map(|(patch_id, cvars)| {
+            cvars
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(&change.key))
+                .map(|(_, value)| (patch_id, value))
+        });
+        if let Some((patch_id, value)) = owner {
+            if config.get(&change.key) == Some(change.value.as_str()) {
+                config.set(&change.key, value);
+            }
+            log::info!("[Config] {} is still needed by {}, handing it over", change.key, patch_id);
+            handed_over.push((
+                patch_id.clone(),
+                ConfigChange { key: change.key.clone(), value: value.clone(), previous: change.previous.clone() },
+            ));
+            continue;
+        }
+        if config.get(&change.key) != Some(change.value.as_str()) {
+            log::info!("[Config] {} was changed since install, keeping it", change.key);
+            continue;
+        }
+        match &change.previous {
+            Some(previous) => config.set(&change.key, previous),
+            None => config.remove(&change.key),
+        }
+    }
+
+    if config != original {
+        config.save_with_backup(&path).await?;
+    }
+    Ok(handed_over)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_and_edit_preserves_other_lines() {
+        let text = "SET locale \"enUS\"\r\n# comment\r\nset gxApi \"opengl\"\r\nsomething unknown\r\n";
+        let mut config = ConfigWtf::parse(text);
+        assert_eq!(config.get("GXAPI"), Some("opengl"));
+        assert_eq!(config.get("missing"), None);
+
+        config.set("gxApi", "d3d9");
+        config.set("farclip", "777");
+        assert_eq!(
+            config.to_string(),
+            "SET locale \"enUS\"\r\n# comment\r\nSET gxApi \"d3d9\"\r\nsomething unknown\r\nSET farclip \"777\"\r\n"
+        );
+
+        config.remove("locale");
+        assert_eq!(config.get("locale"), None);
+        assert!(config.to_string().starts_with("# comment"));
+    }
+
+    #[tokio::test]
+    async fn test_apply_and_revert() {
+        let wow = tempdir().unwrap();
+        let path = ConfigWtf::path(wow.path());
+        fs::create_dir_all(path.parent().unwrap()).await.unwrap();
+        fs::write(&path, "SET gxApi \"opengl\"\nSET M2UseShaders \"1\"\n").await.unwrap();
+
+        let cvars: BTreeMap<String, String> = [
+            ("gxApi".to_string(), "d3d9".to_string()),
+            ("M2UseShaders".to_string(), "1".to_string()),
+            ("farclip".to_string(), "777".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        let changes = apply_cvars(wow.path(), &cvars).await.unwrap();
+        assert_eq!(changes.len(), 2);
+
+        let config = ConfigWtf::load(&path).await.unwrap();
+        assert_eq!(config.get("gxApi"), Some("d3d9"));
+        assert_eq!(config.get("farclip"), Some("777"));
+
+        revert_cvars(wow.path(), &changes, &[]).await.unwrap();
+        assert_eq!(fs::read_to_string(&path).await.unwrap(), "SET gxApi \"opengl\"\nSET M2UseShaders \"1\"\n");
+
+        // The backup holds the file from before the revert
+        let backup = ConfigWtf::load(&ConfigWtf::backup_path(&path)).await.unwrap();
+        assert_eq!(backup.get("gxApi"), Some("d3d9"));
+    }
+
+    #[test]
+    fn test_statuses_and_merge() {
+        let config = ConfigWtf::parse("SET farclip \"777\"\n");
+        let cvars: BTreeMap<String, String> = [
+            ("farclip".to_string(), "777".to_string()),
+            ("M2UseShaders".to_string(), "1".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        let mut recorded = vec![ConfigChange { key: "farclip".to_string(), value: "777".to_string(), previous: Some("500".to_string()) }];
+
+        let statuses = cvar_statuses(&config, "A", &cvars, &recorded);
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(statuses[0].key, "M2UseShaders");
+        assert!(statuses[0].current.is_none() && !statuses[0].managed);
+        assert!(statuses[1].managed && statuses[1].current.as_deref() == Some("777"));
+
+        merge_changes(&mut recorded, vec![
+            ConfigChange { key: "FARCLIP".to_string(), value: "800".to_string(), previous: Some("600".to_string()) },
+            ConfigChange { key: "M2UseShaders".to_string(), value: "1".to_string(), previous: None },
+        ]);
+        assert_eq!(recorded.len(), 2);
+        assert_eq!(recorded[0].value, "800");
+        assert_eq!(recorded[0].previous.as_deref(), Some("500"));
+    }
+
+    #[tokio::test]
+    async fn test_revert_keeps_user_changes() {
+        let wow = tempdir().unwrap();
+        let cvars: BTreeMap<String, String> = [("farclip".to_string(), "777".to_string())].into_iter().collect();
+        let changes = apply_cvars(wow.path(), &cvars).await.unwrap();
+
+        let path = ConfigWtf::path(wow.path());
+        fs::write(&path, "SET farclip \"500\"\n").await.unwrap();
+        revert_cvars(wow.path(), &changes, &[]).await.unwrap();
+        assert_eq!(ConfigWtf::load(&path).await.unwrap().get("farclip"), Some("500"));
+    }
+
+    #[tokio::test]
+    async fn test_revert_hands_over_keys_other_patches_need() {
+        let wow = tempdir().unwrap();
+        let path = ConfigWtf::path(wow.path());
+        fs::create_dir_all(path.parent().unwrap()).await.unwrap();
+        fs::write(&path, "SET farclip \"500\"\n").await.unwrap();
+        let farclip = |value: &str| -> BTreeMap<String, String> {
+            [("farclip".to_string(), value.to_string())].into_iter().collect()
+        };
+
+        // A and B want the same value: only A records it
+        let a_changes = apply_cvars(wow.path(), &farclip("777")).await.unwrap();
+        assert!(apply_cvars(wow.path(), &farclip("777")).await.unwrap().is_empty());
+        let handed = revert_cvars(wow.path(), &a_changes, &[("B".to_string(), farclip("777"))]).await.unwrap();
+        assert_eq!(ConfigWtf::load(&path).await.unwrap().get("farclip"), Some("777"));
+        assert_eq!(handed.len(), 1);
+        assert_eq!(handed[0].0, "B");
+        assert_eq!(handed[0].1.previous.as_deref(), Some("500"));
+
+        // Stacked: B changed A's value; removing B puts back A's, and B's
+        // record then goes back to the value from before A
+        let mut b_changes = apply_cvars(wow.path(), &farclip("1000")).await.unwrap();
+        assert_eq!(b_changes[0].previous.as_deref(), Some("777"));
+        let handed = revert_cvars(wow.path(), &b_changes, &[("A".to_string(), farclip("777"))]).await.unwrap();
+        assert_eq!(ConfigWtf::load(&path).await.unwrap().get("farclip"), Some("777"));
+        assert_eq!(handed[0].1.previous.as_deref(), Some("777"));
+
+        // Stacked the other way: removing A while B is installed keeps
+        // B's value and hands B the value from before A
+        fs::write(&path, "SET farclip \"1000\"\n").await.unwrap();
+        let from_a = ConfigChange { key: "FARCLIP".to_string(), value: "1000".to_string(), previous: Some("500".to_string()) };
+        take_over_change(&mut b_changes, from_a);
+        assert_eq!(b_changes.len(), 1);
+        revert_cvars(wow.path(), &b_changes, &[]).await.unwrap();
+        assert_eq!(ConfigWtf::load(&path).await.unwrap().get("farclip"), Some("500"));
+    }
+}
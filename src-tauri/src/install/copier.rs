@@ -1,7 +1,9 @@
 //! MPQ file copy operations with progress reporting
 //!
 //! HD Patch: Reforged distributes raw MPQ files (not archives),
-//! so installation is simply copying files to the WoW Data folder.
+//! so installation is simply copying (or linking) files to the WoW Data folder.
+//! Patches packaged in archives are unpacked by `extract` before they
+//! reach this point.
 
 use std::path::{Path, PathBuf};
 use tokio::fs;
@@ -9,6 +11,11 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader, BufWriter};
 use tauri::ipc::Channel;
 use thiserror::Error;
 
+use crate::disk::SpaceCheck;
+use super::casefold::resolve;
+use super::pe::PeError;
+use super::strategy::{link_file, remove_existing, InstallStrategy};
+
 /// Buffer size for chunked copy (64KB)
 const COPY_BUFFER_SIZE: usize = 64 * 1024;
 
@@ -29,10 +36,89 @@ pub enum InstallError {
     #[error("Invalid source path")]
     InvalidPath,
 
+    #[error("Patches still installed: {0}")]
+    PatchesStillInstalled(String),
+
+    #[error("Profile not found: {0}")]
+    ProfileNotFound(String),
+
+    #[error("Profile name must not be empty")]
+    InvalidProfileName,
+
+    #[error("Cannot delete the last profile")]
+    LastProfile,
+
+    #[error("Not enough disk space on {path:?}: {needed} bytes needed, {available} available")]
+    InsufficientSpace {
+        needed: u64,
+        available: u64,
+        path: PathBuf,
+    },
+
+    #[error("Unsupported archive: {0}")]
+    UnsupportedArchive(String),
+
+    #[error("Failed to extract archive {0}")]
+    ArchiveError(String),
+
+    #[error("Archive entry would be written outside the staging folder: {0}")]
+    UnsafeArchivePath(String),
+
+    #[error("{matches} files in the archive match '{pattern}' (archive contains: {})", available.join(", "))]
+    ArchiveSelection {
+        pattern: String,
+        matches: usize,
+        available: Vec<String>,
+    },
+
+    #[error("Unreadable executable: {0}")]
+    Executable(#[from] PeError),
+
+    #[error("No backup found: {0}")]
+    BackupNotFound(String),
+
+    #[error("Invalid install target: {0}")]
+    InvalidTarget(String),
+
+    #[error("Unknown requirement: {0}")]
+    UnknownRequirement(String),
+
+    #[error("Patch not installed: {0}")]
+    PatchNotInstalled(String),
+
+    #[error("{exe} is running from the WoW folder (process {pid}); close the game and try again")]
+    GameRunning {
+        exe: String,
+        pid: u32,
+    },
+
+    #[error("Requirement not installed through the patcher: {0}")]
+    RequirementNotInstalled(String),
+
+    #[error("No Wine prefix or Lutris config found for {0}")]
+    WinePrefixNotFound(String),
+
+    #[error("Checksum mismatch for {name}: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        name: String,
+        expected: String,
+        actual: String,
+    },
+
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
 }
 
+impl From<SpaceCheck> for InstallError {
+    fn from(check: SpaceCheck) -> Self {
+        InstallError::InsufficientSpace {
+            needed: check.needed,
+            available: check.available,
+            path: check.path,
+        }
+    }
+}
+
 /// Events emitted during installation
 #[derive(Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase", tag = "event", content = "data")]
@@ -72,7 +158,30 @@ pub async fn install_mpq(
         .to_string_lossy()
         .to_string();
 
-    let dest_path = data_folder.join(&file_name);
+    let dest_path = resolve(data_folder, &file_name);
+    install_file(source_path, &dest_path, InstallStrategy::Copy, patch_id, on_event).await?;
+    Ok(dest_path)
+}
+
+/// Install a file to an explicit destination path using `strategy`
+///
+/// Used when the source name differs from the installed name, e.g. when
+/// installing from the content-addressed download store. Falls back to a
+/// copy when the strategy is not possible and returns the strategy that
+/// was actually used.
+pub async fn install_file(
+    source_path: &Path,
+    dest_path: &Path,
+    strategy: InstallStrategy,
+    patch_id: String,
+    on_event: Channel<InstallEvent>,
+) -> Result<InstallStrategy, InstallError> {
+    let file_name = dest_path
+        .file_name()
+        .ok_or(InstallError::InvalidPath)?
+        .to_string_lossy()
+        .to_string();
+    let dest_path = dest_path.to_path_buf();
 
     // Send started event
     let _ = on_event.send(InstallEvent::Started {
@@ -84,22 +193,22 @@ pub async fn install_mpq(
     let metadata = fs::metadata(source_path).await?;
     let total_bytes = metadata.len();
 
-    // Perform chunked copy with progress
-    let result = copy_with_progress(
+    let result = place_file(
         source_path,
         &dest_path,
+        strategy,
         total_bytes,
         patch_id.clone(),
         on_event.clone(),
     ).await;
 
     match result {
-        Ok(_) => {
+        Ok(used) => {
             let _ = on_event.send(InstallEvent::Completed {
                 patch_id,
                 dest_path: dest_path.to_string_lossy().to_string(),
             });
-            Ok(dest_path)
+            Ok(used)
         }
         Err(e) => {
             let _ = on_event.send(InstallEvent::Failed {
@@ -111,6 +220,39 @@ pub async fn install_mpq(
     }
 }
 
+/// Replace `dest` with `source` using `strategy`, falling back to a copy
+async fn place_file(
+    source: &Path,
+    dest: &Path,
+    strategy: InstallStrategy,
+    total_bytes: u64,
+    patch_id: String,
+    on_event: Channel<InstallEvent>,
+) -> Result<InstallStrategy, InstallError> {
+    remove_existing(dest).await?;
+
+    if strategy != InstallStrategy::Copy {
+        match link_file(source, dest, strategy).await {
+            Ok(()) => {
+                // Links complete instantly; report the whole file at once
+                let _ = on_event.send(InstallEvent::Progress {
+                    patch_id,
+                    bytes_copied: total_bytes,
+                    total_bytes,
+                });
+                return Ok(strategy);
+            }
+            Err(e) => {
+                log::warn!("[Install] {:?} failed for {:?}, copying instead: {}", strategy, dest, e);
+                remove_existing(dest).await?;
+            }
+        }
+    }
+
+    copy_with_progress(source, dest, total_bytes, patch_id, on_event).await?;
+    Ok(InstallStrategy::Copy)
+}
+
 /// Copy file with chunked reads and progress events
 async fn copy_with_progress(
     source: &Path,
@@ -166,16 +308,81 @@ pub fn get_mpq_filename(patch_id: &str) -> String {
 }
 
 /// Uninstall (remove) an MPQ file from the WoW Data folder
+///
+/// Only the entry in `Data/` is removed: hardlinks and symlinks are
+/// unlinked without touching the stored download, and broken symlinks
+/// are cleaned up too.
 pub async fn uninstall_mpq(
     data_folder: &Path,
     patch_id: &str,
 ) -> Result<(), InstallError> {
     let file_name = get_mpq_filename(patch_id);
-    let file_path = data_folder.join(&file_name);
+    let file_path = resolve(data_folder, &file_name);
 
-    if file_path.exists() {
-        fs::remove_file(&file_path).await?;
-    }
+    remove_existing(&file_path).await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_copy_over_hardlink_keeps_source_intact() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("object");
+        let dest = dir.path().join("Patch-A.mpq");
+        fs::write(&source, b"stored").await.unwrap();
+
+        let used = install_file(&source, &dest, InstallStrategy::Hardlink, "A".into(), Channel::new(|_| Ok(()))).await.unwrap();
+        assert_eq!(used, InstallStrategy::Hardlink);
+
+        let other = dir.path().join("other");
+        fs::write(&other, b"replacement").await.unwrap();
+        install_file(&other, &dest, InstallStrategy::Copy, "A".into(), Channel::new(|_| Ok(()))).await.unwrap();
+
+        assert_eq!(fs::read(&dest).await.unwrap(), b"replacement");
+        assert_eq!(fs::read(&source).await.unwrap(), b"stored");
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_strategy_falls_back_to_copy() {
+        let dir = tempdir().unwrap();
+        let dest = dir.path().join("Patch-A.mpq");
+
+        // Hardlinking a directory is never allowed
+        let source_dir = dir.path().join("not-a-file");
+        fs::create_dir(&source_dir).await.unwrap();
+        assert!(link_file(&source_dir, &dest, InstallStrategy::Hardlink).await.is_err());
+
+        let source = dir.path().join("object");
+        fs::write(&source, b"stored").await.unwrap();
+        let used = install_file(&source, &dest, InstallStrategy::Reflink, "A".into(), Channel::new(|_| Ok(()))).await.unwrap();
+        assert!(matches!(used, InstallStrategy::Reflink | InstallStrategy::Copy));
+        assert_eq!(fs::read(&dest).await.unwrap(), b"stored");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_uninstall_removes_symlink_only() {
+        let dir = tempdir().unwrap();
+        let data = dir.path().join("Data");
+        fs::create_dir(&data).await.unwrap();
+        let source = dir.path().join("object");
+        fs::write(&source, b"stored").await.unwrap();
+
+        let dest = data.join(get_mpq_filename("A"));
+        install_file(&source, &dest, InstallStrategy::Symlink, "A".into(), Channel::new(|_| Ok(()))).await.unwrap();
+        uninstall_mpq(&data, "A").await.unwrap();
+
+        assert!(fs::symlink_metadata(&dest).await.is_err());
+        assert!(source.exists());
+
+        // A dangling link left behind by a cleaned-up store is removed too
+        std::os::unix::fs::symlink(dir.path().join("gone"), &dest).unwrap();
+        uninstall_mpq(&data, "A").await.unwrap();
+        assert!(fs::symlink_metadata(&dest).await.is_err());
+    }
+}
@@ -1,6 +1,33 @@
 //! WoW installation folder detection and validation
 
 use std::path::{Path, PathBuf};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use super::casefold;
+use super::copier::InstallError;
+use super::pe::{self, FileVersion, PeHeaders};
+use super::prefixes::{self, CandidateSource, Confidence, WowCandidate};
+
+/// Client executable names, in the order they are preferred
+pub const CLIENT_EXES: [&str; 3] = ["WoW.exe", "turtle-wow.exe", "WoWFoV.exe"];
+
+/// The 1.12.1 client's build number
+pub const VANILLA_BUILD: u32 = 5875;
+
+/// Identity of the client executable in a WoW folder
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientInfo {
+    /// Executable file name as found on disk
+    pub exe: String,
+    /// Build number (last part of the file version), if the exe has one
+    pub build: Option<u32>,
+    pub file_version: Option<FileVersion>,
+    /// `CheckSum` field of the PE header (often 0 for game clients)
+    pub checksum: u32,
+    pub sha256: String,
+}
 
 /// Detects and validates WoW installation folders
 pub struct WowDetector;
@@ -8,15 +35,13 @@ pub struct WowDetector;
 impl WowDetector {
     /// Check if a path is a valid WoW installation
     ///
-    /// Valid if it has:
+    /// Valid if it has (in any casing):
     /// - One of: WoW.exe, WoWFoV.exe, turtle-wow.exe
     /// - A Data subdirectory
     pub fn is_valid_wow_folder(path: &Path) -> bool {
-        let has_exe = path.join("WoW.exe").exists()
-            || path.join("WoWFoV.exe").exists()
-            || path.join("turtle-wow.exe").exists();
+        let has_exe = Self::find_exe(path).is_some();
 
-        let has_data = path.join("Data").exists() && path.join("Data").is_dir();
+        let has_data = casefold::resolve(path, "Data").is_dir();
 
         has_exe && has_data
     }
@@ -42,9 +67,93 @@ impl WowDetector {
         None
     }
 
+    /// List every WoW folder found on this machine, most confident first
+    ///
+    /// Checks the same places as `auto_detect`, then (on Linux and other
+    /// Unix systems) the Wine prefixes of the common launchers.
+    pub fn list_candidates() -> Vec<WowCandidate> {
+        let mut candidates: Vec<WowCandidate> = Vec::new();
+        let local = [
+            std::env::current_dir().ok(),
+            std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)),
+        ];
+        for dir in local.into_iter().flatten() {
+            if Self::is_valid_wow_folder(&dir) && !candidates.iter().any(|c| c.path == dir) {
+                candidates.push(WowCandidate {
+                    path: dir,
+                    source: CandidateSource::Local,
+                    confidence: Confidence::High,
+                    prefix: None,
+                });
+            }
+        }
+
+        if cfg!(unix) {
+            if let Some(home) = std::env::var_os("HOME") {
+                let wineprefix = std::env::var_os("WINEPREFIX").map(PathBuf::from);
+                for candidate in prefixes::discover(Path::new(&home), wineprefix.as_deref()) {
+                    if !candidates.iter().any(|c| c.path == candidate.path) {
+                        candidates.push(candidate);
+                    }
+                }
+            }
+        }
+        candidates
+    }
+
+    /// Find the client executable in a WoW folder (in any casing)
+    pub fn find_exe(wow_path: &Path) -> Option<PathBuf> {
+        CLIENT_EXES
+            .iter()
+            .map(|exe| casefold::resolve(wow_path, exe))
+            .find(|path| path.is_file())
+    }
+
+    /// Identify the client in a WoW folder from its executable
+    ///
+    /// Returns `None` when the folder has no client executable.
+    pub fn client_info(wow_path: &Path) -> Result<Option<ClientInfo>, InstallError> {
+        let Some(exe) = Self::find_exe(wow_path) else {
+            return Ok(None);
+        };
+        let data = std::fs::read(&exe)?;
+        let headers = PeHeaders::parse(&data)?;
+        let file_version = pe::file_version(&data)?;
+
+        Ok(Some(ClientInfo {
+            exe: exe.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            build: file_version.map(|v| v.build as u32),
+            file_version,
+            checksum: headers.checksum,
+            sha256: Sha256::digest(&data).iter().map(|b| format!("{:02x}", b)).collect(),
+        }))
+    }
+
     /// Get the Data folder path from a WoW installation
+    ///
+    /// Uses the folder's on-disk casing (e.g. `data` under Wine).
     pub fn get_data_folder(wow_path: &Path) -> PathBuf {
-        wow_path.join("Data")
+        casefold::resolve(wow_path, "Data")
+    }
+}
+
+/// Build fake WoW folders for tests
+#[cfg(test)]
+pub(crate) mod test_wow {
+    use std::path::{Path, PathBuf};
+
+    /// Create a minimal valid WoW folder (and any parents)
+    ///
+    /// Returns the canonical path, the form folder scans report.
+    pub fn create(path: &Path) -> PathBuf {
+        create_with_exe(path, b"MZ")
+    }
+
+    /// Create a WoW folder whose `WoW.exe` holds `exe`
+    pub fn create_with_exe(path: &Path, exe: &[u8]) -> PathBuf {
+        std::fs::create_dir_all(path.join("Data")).unwrap();
+        std::fs::write(path.join("WoW.exe"), exe).unwrap();
+        std::fs::canonicalize(path).unwrap()
     }
 }
 
@@ -66,6 +175,23 @@ mod tests {
         assert_eq!(data_folder, PathBuf::from("/home/user/WoW/Data"));
     }
 
+    #[test]
+    fn test_client_info_reads_build() {
+        let wow = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(wow.path().join("Data")).unwrap();
+        assert_eq!(WowDetector::client_info(wow.path()).unwrap(), None);
+
+        std::fs::write(wow.path().join("WoW.exe"), pe::test_pe::build(0x0102, Some((1, 12, 1, 5875)))).unwrap();
+        let info = WowDetector::client_info(wow.path()).unwrap().unwrap();
+        assert_eq!(info.exe, "WoW.exe");
+        assert_eq!(info.build, Some(VANILLA_BUILD));
+        assert_eq!(info.file_version.unwrap().to_string(), "1.12.1.5875");
+        assert_eq!(info.sha256.len(), 64);
+
+        std::fs::write(wow.path().join("WoW.exe"), b"MZ").unwrap();
+        assert!(WowDetector::client_info(wow.path()).is_err());
+    }
+
     #[test]
     fn test_auto_detect_returns_none_in_dev_environment() {
         // In dev environment, we're not in a WoW folder
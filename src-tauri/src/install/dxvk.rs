@@ -0,0 +1,325 @@
+//! DXVK management
+//!
+//! DXVK replaces the client's Direct3D 9 with a Vulkan implementation
+//! through `d3d9.dll`, and is installed as the `dxvk` catalog requirement.
+//! This module lists its releases, tells a DXVK `d3d9.dll` apart from
+//! other wrappers (ReShade, dgVoodoo and the like) and writes a managed
+//! `dxvk.conf` from presets. A `dxvk.conf` the user wrote is backed up
+//! before the first managed one replaces it.
+
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use super::copier::InstallError;
+use super::manager::target_dest;
+use super::pe::{mentions, product_name, PeHeaders};
+use super::requirements::{back_up, backup_path};
+use crate::download::providers::github::Release;
+
+/// ID of the DXVK entry in the catalog's `requirements`
+pub const DXVK_REQUIREMENT: &str = "dxvk";
+
+/// GitHub repository DXVK is released from
+pub const DXVK_OWNER: &str = "doitsujin";
+pub const DXVK_REPO: &str = "dxvk";
+
+/// The DLL DXVK provides for a Direct3D 9 client
+pub const D3D9_DLL: &str = "d3d9.dll";
+
+/// DXVK's config file, read from the executable's folder
+pub const DXVK_CONF: &str = "dxvk.conf";
+
+/// First line of a `dxvk.conf` written by the patcher
+const MANAGED_HEADER: &str = "# Managed by WoW HD Patcher; changes here are overwritten";
+
+/// Comment recording the preset a managed `dxvk.conf` was written from
+const PRESET_PREFIX: &str = "# preset: ";
+
+/// Versions of the stable DXVK releases, newest first
+///
+/// Drafts, prereleases and releases without a `dxvk-<version>.tar.gz`
+/// asset are skipped.
+pub fn release_versions(releases: &[Release]) -> Vec<String> {
+    releases
+        .iter()
+        .filter(|r| !r.draft && !r.prerelease)
+        .filter_map(|r| {
+            let version = r.tag_name.trim_start_matches('v');
+            let asset = format!("dxvk-{}.tar.gz", version);
+            r.assets.iter().any(|a| a.name == asset).then(|| version.to_string())
+        })
+        .collect()
+}
+
+/// What a `d3d9.dll` in the WoW folder is
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum D3d9Kind {
+    Dxvk,
+    /// Another wrapper, named by its version strings
+    Other { product: String },
+    /// No version strings and no DXVK marker
+    Unknown,
+}
+
+/// Identify a `d3d9.dll` from its PE version info
+///
+/// The product name decides when the file has one. DXVK builds without
+/// version strings are recognised by the `DXVK` marker in their contents.
+pub fn identify_d3d9(data: &[u8]) -> D3d9Kind {
+    if PeHeaders::parse(data).is_err() {
+        return D3d9Kind::Unknown;
+    }
+    match product_name(data) {
+        Some(product) if product.to_lowercase().contains("dxvk") => D3d9Kind::Dxvk,
+        Some(product) => D3d9Kind::Other { product },
+        None if mentions(data, "DXVK") => D3d9Kind::Dxvk,
+        None => D3d9Kind::Unknown,
+    }
+}
+
+/// Starting points for `dxvk.conf`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DxvkPreset {
+    /// 16x anisotropic filtering with vsync
+    Quality,
+    /// 8x anisotropic filtering, driver defaults otherwise
+    Balanced,
+    /// 4x anisotropic filtering, no vsync, one frame of latency
+    Performance,
+}
+
+impl DxvkPreset {
+    fn name(self) -> &'static str {
+        match self {
+            DxvkPreset::Quality => "quality",
+            DxvkPreset::Balanced => "balanced",
+            DxvkPreset::Performance => "performance",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [DxvkPreset::Quality, DxvkPreset::Balanced, DxvkPreset::Performance]
+            .into_iter()
+            .find(|p| p.name() == name.trim())
+    }
+}
+
+/// Settings written to a managed `dxvk.conf`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DxvkConfig {
+    pub preset: DxvkPreset,
+    /// `d3d9.samplerAnisotropy`
+    pub anisotropy: u8,
+    /// `d3d9.maxFrameRate`; 0 for no limit
+    pub max_frame_rate: u32,
+    /// `d3d9.maxFrameLatency`, if not left to DXVK
+    pub max_frame_latency: Option<u8>,
+    /// `d3d9.presentInterval` 1 (on) or 0 (off), if not left to the game
+    pub vsync: Option<bool>,
+}
+
+impl DxvkConfig {
+    /// Settings of a preset, with an optional frame rate limit
+    pub fn from_preset(preset: DxvkPreset, max_frame_rate: Option<u32>) -> Self {
+        let (anisotropy, max_frame_latency, vsync) = match preset {
+            DxvkPreset::Quality => (16, None, Some(true)),
+            DxvkPreset::Balanced => (8, None, None),
+            DxvkPreset::Performance => (4, Some(1), Some(false)),
+        };
+        Self {
+            preset,
+            anisotropy,
+            max_frame_rate: max_frame_rate.unwrap_or(0),
+            max_frame_latency,
+            vsync,
+        }
+    }
+
+    /// Render the config file
+    pub fn render(&self) -> String {
+        let mut lines = vec![
+            MANAGED_HEADER.to_string(),
+            format!("{}{}", PRESET_PREFIX, self.preset.name()),
+            format!("d3d9.samplerAnisotropy = {}", self.anisotropy),
+        ];
+        if self.max_frame_rate > 0 {
+            lines.push(format!("d3d9.maxFrameRate = {}", self.max_frame_rate));
+        }
+        if let Some(latency) = self.max_frame_latency {
+            lines.push(format!("d3d9.maxFrameLatency = {}", latency));
+        }
+        if let Some(vsync) = self.vsync {
+            lines.push(format!("d3d9.presentInterval = {}", vsync as u8));
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+/// DXVK state of a WoW folder
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DxvkStatus {
+    /// What `d3d9.dll` is, if there is one
+    pub d3d9: Option<D3d9Kind>,
+    /// A `d3d9.dll` replaced by the DXVK install is backed up
+    pub has_backup: bool,
+    /// Preset of the managed `dxvk.conf`
+    pub preset: Option<DxvkPreset>,
+    /// Frame rate limit of the managed `dxvk.conf`
+    pub max_frame_rate: Option<u32>,
+    /// A `dxvk.conf` not written by the patcher exists
+    pub custom_conf: bool,
+}
+
+/// Read a file, treating a missing one as `None`
+async fn read_optional(path: &Path) -> Result<Option<Vec<u8>>, InstallError> {
+    match fs::read(path).await {
+        Ok(data) => Ok(Some(data)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Inspect `d3d9.dll` and `dxvk.conf` in a WoW folder
+pub async fn dxvk_status(wow_path: &Path) -> Result<DxvkStatus, InstallError> {
+    let dll = target_dest(wow_path, D3D9_DLL)?;
+    let d3d9 = read_optional(&dll).await?.map(|data| identify_d3d9(&data));
+
+    let conf = read_optional(&target_dest(wow_path, DXVK_CONF)?)
+        .await?
+        .map(|data| String::from_utf8_lossy(&data).to_string());
+    let managed = conf.as_deref().filter(|text| text.starts_with(MANAGED_HEADER));
+    let preset = managed
+        .and_then(|text| text.lines().find_map(|l| l.strip_prefix(PRESET_PREFIX)))
+        .and_then(DxvkPreset::from_name);
+    let max_frame_rate = managed.and_then(|text| {
+        text.lines()
+            .filter_map(|l| l.split_once('='))
+            .find(|(key, _)| key.trim() == "d3d9.maxFrameRate")
+            .and_then(|(_, value)| value.trim().parse().ok())
+    });
+
+    Ok(DxvkStatus {
+        d3d9,
+        has_backup: backup_path(&dll, DXVK_REQUIREMENT).exists(),
+        preset,
+        max_frame_rate,
+        custom_conf: conf.is_some() && managed.is_none(),
+    })
+}
+
+/// Write a managed `dxvk.conf`
+///
+/// A `dxvk.conf` the patcher did not write is backed up first. Returns
+/// whether a backup of it exists.
+pub async fn write_conf(wow_path: &Path, config: &DxvkConfig) -> Result<bool, InstallError> {
+    let dest = target_dest(wow_path, DXVK_CONF)?;
+    let existing = read_optional(&dest).await?;
+    let backed_up = match existing {
+        Some(data) if !data.starts_with(MANAGED_HEADER.as_bytes()) => back_up(wow_path, DXVK_CONF, DXVK_REQUIREMENT).await?,
+        _ => backup_path(&dest, DXVK_REQUIREMENT).exists(),
+    };
+
+    let partial = dest.with_extension("conf.partial");
+    fs::write(&partial, config.render()).await?;
+    fs::rename(&partial, &dest).await?;
+    log::info!("[DXVK] Wrote {} with the {} preset", DXVK_CONF, config.preset.name());
+    Ok(backed_up)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::download::providers::github::ReleaseAsset;
+    use crate::install::pe::test_pe;
+    use tempfile::tempdir;
+
+    fn release(tag: &str, prerelease: bool, asset: &str) -> Release {
+        Release {
+            tag_name: tag.to_string(),
+            prerelease,
+            draft: false,
+            assets: vec![ReleaseAsset {
+                name: asset.to_string(),
+                size: 1,
+                browser_download_url: format!("https://example.com/{}", asset),
+                digest: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_release_versions() {
+        let releases = vec![
+            release("v2.8-rc1", true, "dxvk-2.8-rc1.tar.gz"),
+            release("v2.7.1", false, "dxvk-2.7.1.tar.gz"),
+            release("v2.7", false, "dxvk-native-2.7-steamrt-sniper.tar.gz"),
+            release("v2.5.3", false, "dxvk-2.5.3.tar.gz"),
+        ];
+        assert_eq!(release_versions(&releases), vec!["2.7.1", "2.5.3"]);
+    }
+
+    #[test]
+    fn test_identify_d3d9() {
+        let dxvk = test_pe::build_with_strings(0x2102, None, &[("ProductName", "DXVK")]);
+        assert_eq!(identify_d3d9(&dxvk), D3d9Kind::Dxvk);
+
+        // Builds without version strings still carry DXVK's name in their data
+        let mut marked = test_pe::build(0x2102, None);
+        marked.extend(b"info:  DXVK: v2.7.1");
+        assert_eq!(identify_d3d9(&marked), D3d9Kind::Dxvk);
+
+        let reshade = test_pe::build_with_strings(0x2102, None, &[("ProductName", "ReShade")]);
+        assert_eq!(identify_d3d9(&reshade), D3d9Kind::Other { product: "ReShade".to_string() });
+
+        // A wrapper that names DXVK somewhere in its code is still itself
+        let mut reshade_marked = reshade.clone();
+        reshade_marked.extend(b"forwarding to dxvk d3d9.dll");
+        assert_eq!(identify_d3d9(&reshade_marked), D3d9Kind::Other { product: "ReShade".to_string() });
+        assert!(!mentions(&reshade_marked, "DXVK"));
+        assert_eq!(identify_d3d9(&test_pe::build(0x2102, None)), D3d9Kind::Unknown);
+        assert_eq!(identify_d3d9(b"not a dll"), D3d9Kind::Unknown);
+    }
+
+    #[test]
+    fn test_render_presets() {
+        let quality = DxvkConfig::from_preset(DxvkPreset::Quality, Some(144)).render();
+        assert!(quality.starts_with(MANAGED_HEADER));
+        assert!(quality.contains("d3d9.samplerAnisotropy = 16\n"));
+        assert!(quality.contains("d3d9.maxFrameRate = 144\n"));
+        assert!(quality.contains("d3d9.presentInterval = 1\n"));
+
+        let performance = DxvkConfig::from_preset(DxvkPreset::Performance, None).render();
+        assert!(!performance.contains("maxFrameRate"));
+        assert!(performance.contains("d3d9.maxFrameLatency = 1\n"));
+    }
+
+    #[tokio::test]
+    async fn test_write_conf_backs_up_custom_conf() {
+        let wow = tempdir().unwrap();
+        std::fs::write(wow.path().join("dxvk.conf"), "dxvk.hud = fps\n").unwrap();
+        let status = dxvk_status(wow.path()).await.unwrap();
+        assert!(status.custom_conf && status.preset.is_none() && status.d3d9.is_none());
+
+        assert!(write_conf(wow.path(), &DxvkConfig::from_preset(DxvkPreset::Balanced, Some(60))).await.unwrap());
+        assert_eq!(std::fs::read_to_string(wow.path().join("dxvk.conf.dxvk-backup")).unwrap(), "dxvk.hud = fps\n");
+        let status = dxvk_status(wow.path()).await.unwrap();
+        assert!(!status.custom_conf);
+        assert_eq!(status.preset, Some(DxvkPreset::Balanced));
+        assert_eq!(status.max_frame_rate, Some(60));
+
+        // Rewriting a managed conf keeps the original backup
+        write_conf(wow.path(), &DxvkConfig::from_preset(DxvkPreset::Quality, None)).await.unwrap();
+        assert_eq!(std::fs::read_to_string(wow.path().join("dxvk.conf.dxvk-backup")).unwrap(), "dxvk.hud = fps\n");
+        assert_eq!(dxvk_status(wow.path()).await.unwrap().max_frame_rate, None);
+
+        // A custom conf written over the managed one is not lost
+        std::fs::write(wow.path().join("dxvk.conf"), "dxvk.hud = full\n").unwrap();
+        write_conf(wow.path(), &DxvkConfig::from_preset(DxvkPreset::Quality, None)).await.unwrap();
+        assert_eq!(std::fs::read_to_string(wow.path().join("dxvk.conf.dxvk-backup")).unwrap(), "dxvk.hud = fps\n");
+        assert_eq!(std::fs::read_to_string(wow.path().join("dxvk.conf.dxvk-backup-2")).unwrap(), "dxvk.hud = full\n");
+    }
+}
@@ -0,0 +1,459 @@
+//! Archive extraction for packaged downloads
+//!
+//! HD Patch: Reforged ships raw MPQs, but other community mods arrive as
+//! zip, 7z, rar or tar archives, and DXVK releases are tarballs. Archives
+//! are unpacked into a staging folder first; every entry path is checked
+//! so that nothing can be written outside it (absolute paths, `..`, drive
+//! prefixes), and links are skipped. Callers then pick the extracted files
+//! they need with `select_extracted`.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncReadExt;
+
+use super::copier::InstallError;
+use crate::download::providers::glob_match;
+
+/// Bytes needed to recognise every supported archive format
+const MAGIC_LEN: usize = 262;
+
+/// Supported archive formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    SevenZip,
+    Rar,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    /// Recognise an archive from its leading bytes
+    pub fn from_magic(head: &[u8]) -> Option<Self> {
+        if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") {
+            Some(ArchiveKind::Zip)
+        } else if head.starts_with(b"7z\xBC\xAF\x27\x1C") {
+            Some(ArchiveKind::SevenZip)
+        } else if head.starts_with(b"Rar!\x1A\x07") {
+            Some(ArchiveKind::Rar)
+        } else if head.starts_with(b"\x1F\x8B") {
+            Some(ArchiveKind::TarGz)
+        } else if head.get(257..262) == Some(b"ustar".as_slice()) {
+            Some(ArchiveKind::Tar)
+        } else {
+            None
+        }
+    }
+
+    /// Recognise an archive file, returning None for anything else
+    pub async fn detect(path: &Path) -> Result<Option<Self>, InstallError> {
+        let file = tokio::fs::File::open(path).await?;
+        let mut head = Vec::with_capacity(MAGIC_LEN);
+        file.take(MAGIC_LEN as u64).read_to_end(&mut head).await?;
+        Ok(Self::from_magic(&head))
+    }
+}
+
+/// A file unpacked into the staging folder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractedFile {
+    /// Path inside the archive, `/`-separated
+    pub name: String,
+    /// Location in the staging folder
+    pub path: PathBuf,
+}
+
+/// Join an archive entry name onto the staging folder
+///
+/// Backslashes count as separators. Absolute names, `..` components and
+/// drive or stream prefixes (anything with a `:`) are rejected.
+pub fn safe_join(root: &Path, entry: &str) -> Result<PathBuf, InstallError> {
+    let normalized = entry.replace('\\', "/");
+    let unsafe_path = || InstallError::UnsafeArchivePath(entry.to_string());
+    if normalized.starts_with('/') {
+        return Err(unsafe_path());
+    }
+
+    let mut path = root.to_path_buf();
+    let mut parts = 0;
+    for part in normalized.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => return Err(unsafe_path()),
+            p if p.contains(':') => return Err(unsafe_path()),
+            p => {
+                path.push(p);
+                parts += 1;
+            }
+        }
+    }
+
+    if parts == 0 {
+        return Err(unsafe_path());
+    }
+    Ok(path)
+}
+
+/// Unpack an archive into `staging`, replacing anything already there
+///
+/// Runs on the blocking thread pool. Returns the extracted regular files.
+pub async fn extract_archive(archive: &Path, staging: &Path) -> Result<Vec<ExtractedFile>, InstallError> {
+    let kind = ArchiveKind::detect(archive)
+        .await?
+        .ok_or_else(|| InstallError::UnsupportedArchive(archive.to_string_lossy().to_string()))?;
+
+    if staging.exists() {
+        tokio::fs::remove_dir_all(staging).await?;
+    }
+    tokio::fs::create_dir_all(staging).await?;
+
+    log::info!("[Extract] Unpacking {:?} ({:?}) into {:?}", archive, kind, staging);
+    let archive = archive.to_path_buf();
+    let staging = staging.to_path_buf();
+    tokio::task::spawn_blocking(move || extract_blocking(kind, &archive, &staging))
+        .await
+        .map_err(|e| InstallError::IoError(std::io::Error::other(e)))?
+}
+
+fn extract_blocking(kind: ArchiveKind, archive: &Path, staging: &Path) -> Result<Vec<ExtractedFile>, InstallError> {
+    let files = match kind {
+        ArchiveKind::Zip => extract_zip(archive, staging),
+        ArchiveKind::SevenZip => extract_7z(archive, staging),
+        ArchiveKind::Rar => extract_rar(archive, staging),
+        ArchiveKind::Tar => extract_tar(std::fs::File::open(archive)?, staging),
+        ArchiveKind::TarGz => extract_tar(flate2::read::GzDecoder::new(std::fs::File::open(archive)?), staging),
+    }?;
+    log::info!("[Extract] Extracted {} files", files.len());
+    Ok(files)
+}
+
+/// Write one entry's contents to its checked path in the staging folder
+fn write_entry(staging: &Path, name: &str, reader: &mut dyn Read) -> Result<ExtractedFile, InstallError> {
+    let path = safe_join(staging, name)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::File::create(&path)?;
+    std::io::copy(reader, &mut file)?;
+    Ok(ExtractedFile {
+        name: name.replace('\\', "/").trim_start_matches("./").to_string(),
+        path,
+    })
+}
+
+fn archive_error(archive: &Path, e: impl std::fmt::Display) -> InstallError {
+    InstallError::ArchiveError(format!("{}: {}", archive.to_string_lossy(), e))
+}
+
+fn extract_zip(archive: &Path, staging: &Path) -> Result<Vec<ExtractedFile>, InstallError> {
+    let mut zip = zip::ZipArchive::new(std::fs::File::open(archive)?).map_err(|e| archive_error(archive, e))?;
+    let mut files = Vec::new();
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i).map_err(|e| archive_error(archive, e))?;
+        if entry.is_dir() || entry.is_symlink() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        files.push(write_entry(staging, &name, &mut entry)?);
+    }
+    Ok(files)
+}
+
+fn extract_7z(archive: &Path, staging: &Path) -> Result<Vec<ExtractedFile>, InstallError> {
+    let mut reader = sevenz_rust::SevenZReader::open(archive, sevenz_rust::Password::empty())
+        .map_err(|e| archive_error(archive, e))?;
+    let mut files = Vec::new();
+    let mut failure = None;
+    reader
+        .for_each_entries(|entry, data| {
+            if entry.is_directory() || entry.is_anti_item {
+                return Ok(true);
+            }
+            match write_entry(staging, entry.name(), data) {
+                Ok(file) => {
+                    files.push(file);
+                    Ok(true)
+                }
+                Err(e) => {
+                    failure = Some(e);
+                    Ok(false)
+                }
+            }
+        })
+        .map_err(|e| archive_error(archive, e))?;
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(files),
+    }
+}
+
+/// Whether a RAR entry's attributes mark it as a link
+///
+/// Archives made on Unix store the `st_mode` (file type bits set), those
+/// made on Windows the file attributes, where links are reparse points.
+fn is_rar_link(file_attr: u32) -> bool {
+    const S_IFMT: u32 = 0o170000;
+    const S_IFLNK: u32 = 0o120000;
+    const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+    if file_attr & S_IFMT != 0 {
+        file_attr & S_IFMT == S_IFLNK
+    } else {
+        file_attr & FILE_ATTRIBUTE_REPARSE_POINT != 0
+    }
+}
+
+/// Whether extraction left a symbolic or hard link at `path`
+fn is_link(path: &Path) -> bool {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return false;
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        if metadata.nlink() > 1 {
+            return true;
+        }
+    }
+    metadata.file_type().is_symlink()
+}
+
+fn extract_rar(archive: &Path, staging: &Path) -> Result<Vec<ExtractedFile>, InstallError> {
+    let mut open = unrar::Archive::new(archive)
+        .open_for_processing()
+        .map_err(|e| archive_error(archive, e))?;
+    let mut files = Vec::new();
+    while let Some(header) = open.read_header().map_err(|e| archive_error(archive, e))? {
+        let entry = header.entry();
+        if entry.is_directory() || is_rar_link(entry.file_attr) {
+            open = header.skip().map_err(|e| archive_error(archive, e))?;
+            continue;
+        }
+
+        let name = entry.filename.to_string_lossy().to_string();
+        let path = safe_join(staging, &name)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        open = header.extract_to(&path).map_err(|e| archive_error(archive, e))?;
+        // Links the attributes did not reveal (e.g. RAR5 hard links) are
+        // dropped before a later entry could write through them
+        if is_link(&path) {
+            std::fs::remove_file(&path)?;
+            continue;
+        }
+        files.push(ExtractedFile {
+            name: name.replace('\\', "/"),
+            path,
+        });
+    }
+    Ok(files)
+}
+
+fn extract_tar<R: Read>(reader: R, staging: &Path) -> Result<Vec<ExtractedFile>, InstallError> {
+    let mut tar = tar::Archive::new(reader);
+    let mut files = Vec::new();
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        // Directories are created as needed; links are never followed
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = String::from_utf8_lossy(&entry.path_bytes()).to_string();
+        files.push(write_entry(staging, &name, &mut entry)?);
+    }
+    Ok(files)
+}
+
+/// Match a glob against an archive path, or just its file name when the
+/// pattern has no `/`
+pub fn entry_matches(pattern: &str, name: &str) -> bool {
+    if pattern.contains('/') {
+        glob_match(pattern, name)
+    } else {
+        glob_match(pattern, name.rsplit('/').next().unwrap_or_default())
+    }
+}
+
+/// Pick the file to install from an extracted archive
+///
+/// `patterns` are globs tried in order (see `entry_matches`); the first
+/// pattern with matches must match exactly one file. Without patterns the
+/// file named `default_name` is used, or else the archive's only MPQ.
+pub fn select_extracted<'a>(
+    files: &'a [ExtractedFile],
+    patterns: &[String],
+    default_name: &str,
+) -> Result<&'a ExtractedFile, InstallError> {
+    let matching = |pattern: &str| -> Vec<&'a ExtractedFile> {
+        files.iter().filter(|f| entry_matches(pattern, &f.name)).collect()
+    };
+
+    let (pattern, matches) = if patterns.is_empty() {
+        let exact = matching(default_name);
+        if exact.is_empty() {
+            ("*.mpq".to_string(), matching("*.mpq"))
+        } else {
+            (default_name.to_string(), exact)
+        }
+    } else {
+        patterns
+            .iter()
+            .map(|p| (p.clone(), matching(p)))
+            .find(|(_, m)| !m.is_empty())
+            .unwrap_or_else(|| (patterns.join(", "), Vec::new()))
+    };
+
+    match matches.as_slice() {
+        [file] => Ok(file),
+        _ => Err(InstallError::ArchiveSelection {
+            pattern,
+            matches: matches.len(),
+            available: files.iter().map(|f| f.name.clone()).collect(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use tempfile::tempdir;
+
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, data) in entries {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        std::fs::write(path, zip.finish().unwrap().into_inner()).unwrap();
+    }
+
+    fn write_tar_gz(path: &Path, entries: &[(&str, &[u8])]) {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, data) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            // Bypass the builder's own path checks to get hostile names in
+            header.as_gnu_mut().unwrap().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_cksum();
+            builder.append(&header, *data).unwrap();
+        }
+        std::fs::write(path, builder.into_inner().unwrap().finish().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_archive_kind_from_magic() {
+        assert_eq!(ArchiveKind::from_magic(b"PK\x03\x04"), Some(ArchiveKind::Zip));
+        assert_eq!(ArchiveKind::from_magic(b"7z\xBC\xAF\x27\x1C\x00"), Some(ArchiveKind::SevenZip));
+        assert_eq!(ArchiveKind::from_magic(b"Rar!\x1A\x07\x00"), Some(ArchiveKind::Rar));
+        assert_eq!(ArchiveKind::from_magic(b"\x1F\x8B\x08"), Some(ArchiveKind::TarGz));
+        assert_eq!(ArchiveKind::from_magic(b"MPQ\x1A"), None);
+    }
+
+    #[test]
+    fn test_safe_join() {
+        let root = Path::new("/staging");
+        assert_eq!(safe_join(root, "dir/Patch-A.mpq").unwrap(), root.join("dir").join("Patch-A.mpq"));
+        assert_eq!(safe_join(root, "./dir\\file.txt").unwrap(), root.join("dir").join("file.txt"));
+        for hostile in ["../evil.dll", "dir/../../evil.dll", "/etc/passwd", "\\Windows\\evil.dll", "C:evil.dll", "./"] {
+            assert!(
+                matches!(safe_join(root, hostile), Err(InstallError::UnsafeArchivePath(_))),
+                "{} was accepted",
+                hostile
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_extract_zip() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("patch-a.zip");
+        write_zip(&archive, &[("Patch-A/readme.txt", b"hi"), ("Patch-A/Patch-A.mpq", b"MPQ\x1Adata")]);
+
+        let staging = dir.path().join("staging");
+        let files = extract_archive(&archive, &staging).await.unwrap();
+        assert_eq!(files.len(), 2);
+
+        let mpq = select_extracted(&files, &[], "Patch-A.mpq").unwrap();
+        assert_eq!(mpq.name, "Patch-A/Patch-A.mpq");
+        assert_eq!(std::fs::read(&mpq.path).unwrap(), b"MPQ\x1Adata");
+    }
+
+    #[tokio::test]
+    async fn test_extract_tar_gz() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("dxvk.tar.gz");
+        write_tar_gz(&archive, &[("dxvk-2.3/x32/d3d9.dll", b"MZ32"), ("dxvk-2.3/x64/d3d9.dll", b"MZ64")]);
+
+        let files = extract_archive(&archive, &dir.path().join("staging")).await.unwrap();
+        let dll = select_extracted(&files, &["x32/d3d9.dll".into(), "*/x32/d3d9.dll".into()], "").unwrap();
+        assert_eq!(std::fs::read(&dll.path).unwrap(), b"MZ32");
+    }
+
+    #[tokio::test]
+    async fn test_extract_7z() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("source");
+        std::fs::create_dir_all(source.join("HD")).unwrap();
+        std::fs::write(source.join("HD/Patch-B.mpq"), b"MPQ\x1Ab").unwrap();
+        let archive = dir.path().join("patch-b.7z");
+        sevenz_rust::compress_to_path(&source, &archive).unwrap();
+
+        let files = extract_archive(&archive, &dir.path().join("staging")).await.unwrap();
+        let mpq = select_extracted(&files, &[], "Patch-B.mpq").unwrap();
+        assert_eq!(std::fs::read(&mpq.path).unwrap(), b"MPQ\x1Ab");
+    }
+
+    #[test]
+    fn test_rar_link_attributes() {
+        // Unix st_mode: regular file, symlink
+        assert!(!is_rar_link(0o100644));
+        assert!(is_rar_link(0o120777));
+        // A setgid file is not a reparse point
+        assert!(!is_rar_link(0o102755));
+        // Windows attributes: archive, archive + reparse point
+        assert!(!is_rar_link(0x20));
+        assert!(is_rar_link(0x420));
+    }
+
+    #[tokio::test]
+    async fn test_traversal_entries_are_rejected() {
+        let dir = tempdir().unwrap();
+        let staging = dir.path().join("staging");
+
+        let zip = dir.path().join("evil.zip");
+        write_zip(&zip, &[("../evil.dll", b"MZ")]);
+        let result = extract_archive(&zip, &staging).await;
+        assert!(matches!(result, Err(InstallError::UnsafeArchivePath(_))));
+
+        let tar = dir.path().join("evil.tar.gz");
+        write_tar_gz(&tar, &[("../../evil.dll", b"MZ")]);
+        let result = extract_archive(&tar, &staging).await;
+        assert!(matches!(result, Err(InstallError::UnsafeArchivePath(_))));
+
+        assert!(!dir.path().join("evil.dll").exists());
+    }
+
+    #[test]
+    fn test_select_extracted() {
+        let file = |name: &str| ExtractedFile { name: name.to_string(), path: PathBuf::from(name) };
+        let files = vec![file("HD/Patch-A.mpq"), file("Alt/Patch-A.mpq"), file("readme.txt")];
+
+        // The catalog's first pattern with matches wins
+        let patterns = vec!["Missing/*.mpq".to_string(), "Alt/*.mpq".to_string()];
+        assert_eq!(select_extracted(&files, &patterns, "Patch-A.mpq").unwrap().name, "Alt/Patch-A.mpq");
+
+        // Ambiguous defaults are an error listing the contents
+        match select_extracted(&files, &[], "Patch-A.mpq") {
+            Err(InstallError::ArchiveSelection { matches, available, .. }) => {
+                assert_eq!(matches, 2);
+                assert_eq!(available.len(), 3);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(select_extracted(&files, &["*.dll".to_string()], "Patch-A.mpq").is_err());
+    }
+}
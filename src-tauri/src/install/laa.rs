@@ -0,0 +1,152 @@
+//! Large Address Aware patching of the client executable
+//!
+//! The 1.12 client is a 32-bit program limited to 2 GB of address space,
+//! which HD texture packs exceed. Setting `IMAGE_FILE_LARGE_ADDRESS_AWARE`
+//! in the PE header lets it use up to 4 GB on a 64-bit system. Patching is
+//! opt-in: the original executable is kept as `<exe>.laa-backup` and can
+//! be put back with `restore_exe`.
+
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use tokio::fs;
+
+use super::copier::InstallError;
+use super::detector::WowDetector;
+use super::pe::{set_large_address_aware, PeHeaders};
+use super::process::ensure_not_running;
+
+/// Suffix of the untouched executable's backup
+const BACKUP_SUFFIX: &str = ".laa-backup";
+
+/// Large Address Aware state of a WoW folder's executable
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LaaStatus {
+    /// Executable file name as found on disk
+    pub exe: String,
+    pub enabled: bool,
+    /// A backup of the executable from before patching exists
+    pub has_backup: bool,
+}
+
+/// Get the backup path for an executable
+fn backup_path(exe: &Path) -> PathBuf {
+    let mut name = exe.file_name().unwrap_or_default().to_os_string();
+    name.push(BACKUP_SUFFIX);
+    exe.with_file_name(name)
+}
+
+fn find_exe(wow_path: &Path) -> Result<PathBuf, InstallError> {
+    WowDetector::find_exe(wow_path).ok_or(InstallError::InvalidWowFolder)
+}
+
+/// Inspect the executable in a WoW folder
+pub async fn laa_status(wow_path: &Path) -> Result<LaaStatus, InstallError> {
+    let exe = find_exe(wow_path)?;
+    let data = fs::read(&exe).await?;
+    Ok(LaaStatus {
+        exe: exe.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        enabled: PeHeaders::parse(&data)?.is_large_address_aware(),
+        has_backup: backup_path(&exe).exists(),
+    })
+}
+
+/// Set the Large Address Aware flag on the executable in a WoW folder
+///
+/// Backs up the executable first whenever it lacks the flag, replacing
+/// any older backup: a client update brings a new unpatched executable,
+/// and restoring must not roll it back to the previous build. The patched
+/// file is written next to the executable and renamed over it.
+pub async fn enable_laa(wow_path: &Path) -> Result<LaaStatus, InstallError> {
+    ensure_not_running(wow_path)?;
+    let exe = find_exe(wow_path)?;
+    let mut data = fs::read(&exe).await?;
+    if set_large_address_aware(&mut data)? {
+        fs::copy(&exe, backup_path(&exe)).await?;
+        let partial = exe.with_extension("exe.partial");
+        fs::write(&partial, &data).await?;
+        fs::rename(&partial, &exe).await?;
+        log::info!("[LAA] Set Large Address Aware on {:?}", exe);
+    }
+    laa_status(wow_path).await
+}
+
+/// Put back the executable saved before patching
+pub async fn restore_exe(wow_path: &Path) -> Result<LaaStatus, InstallError> {
+    ensure_not_running(wow_path)?;
+    let exe = find_exe(wow_path)?;
+    let backup = backup_path(&exe);
+    if !backup.exists() {
+        return Err(InstallError::BackupNotFound(backup.to_string_lossy().to_string()));
+    }
+    fs::rename(&backup, &exe).await?;
+    log::info!("[LAA] Restored {:?} from backup", exe);
+    laa_status(wow_path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::install::detector::test_wow;
+    use crate::install::pe::{file_version, test_pe};
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_enable_and_restore() {
+        let wow = tempdir().unwrap();
+        let original = test_pe::build(0x0102, Some((1, 12, 1, 5875)));
+        test_wow::create_with_exe(wow.path(), &original);
+
+        let status = laa_status(wow.path()).await.unwrap();
+        assert_eq!(status, LaaStatus { exe: "WoW.exe".into(), enabled: false, has_backup: false });
+
+        let status = enable_laa(wow.path()).await.unwrap();
+        assert!(status.enabled && status.has_backup);
+        let patched = std::fs::read(wow.path().join("WoW.exe")).unwrap();
+        assert_eq!(file_version(&patched).unwrap().unwrap().build, 5875);
+        assert_eq!(std::fs::read(wow.path().join("WoW.exe.laa-backup")).unwrap(), original);
+
+        // Enabling again changes nothing
+        enable_laa(wow.path()).await.unwrap();
+        assert_eq!(std::fs::read(wow.path().join("WoW.exe")).unwrap(), patched);
+
+        let status = restore_exe(wow.path()).await.unwrap();
+        assert!(!status.enabled && !status.has_backup);
+        assert_eq!(std::fs::read(wow.path().join("WoW.exe")).unwrap(), original);
+        assert!(matches!(restore_exe(wow.path()).await, Err(InstallError::BackupNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_client_update_refreshes_backup() {
+        let wow = tempdir().unwrap();
+        test_wow::create_with_exe(wow.path(), &test_pe::build(0x0102, Some((1, 12, 1, 5875))));
+        enable_laa(wow.path()).await.unwrap();
+
+        // An update replaces the patched executable with a new unpatched one
+        let updated = test_pe::build(0x0102, Some((1, 12, 2, 6005)));
+        std::fs::write(wow.path().join("WoW.exe"), &updated).unwrap();
+        enable_laa(wow.path()).await.unwrap();
+        assert_eq!(std::fs::read(wow.path().join("WoW.exe.laa-backup")).unwrap(), updated);
+
+        restore_exe(wow.path()).await.unwrap();
+        assert_eq!(std::fs::read(wow.path().join("WoW.exe")).unwrap(), updated);
+    }
+
+    #[tokio::test]
+    async fn test_already_aware_exe_is_not_backed_up() {
+        let wow = tempdir().unwrap();
+        test_wow::create_with_exe(wow.path(), &test_pe::build(0x0122, None));
+
+        let status = enable_laa(wow.path()).await.unwrap();
+        assert!(status.enabled);
+        assert!(!status.has_backup);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_exe_is_an_error() {
+        let wow = tempdir().unwrap();
+        test_wow::create_with_exe(wow.path(), b"MZ");
+        assert!(matches!(enable_laa(wow.path()).await, Err(InstallError::Executable(_))));
+        assert!(!wow.path().join("WoW.exe.laa-backup").exists());
+    }
+}
@@ -1,60 +1,181 @@
 //! Installation manager coordinating all install operations
 //!
 //! Provides a centralized manager for:
-//! - WoW folder path management
-//! - Downloads folder path management
-//! - Install, verify, and repair operations
+//! - Installation profiles (WoW folder, selection and manifest per profile)
+//! - Downloads folder and content-addressed download store
+//! - Unpacking patches that were downloaded as archives
+//! - Catalog-declared install targets and Config.wtf CVars
+//! - Catalog requirements recorded per profile, and the managed `dxvk.conf`
+//! - Install, verify, repair and uninstall operations
+//! - Disk space preflight before installing
+//! - WDB cache policy and install strategy
+//!
+//! Every operation takes an optional profile ID and falls back to the
+//! active profile when none is given.
 
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::RwLock;
 use tauri::ipc::Channel;
 
+use crate::disk::{same_filesystem, SpaceCheck};
+use super::addons::{addon_of_target, map_addon_folders, scan_addons, InstalledAddon};
 use super::detector::WowDetector;
-use super::copier::{install_mpq, get_mpq_filename, InstallEvent, InstallError};
-use super::verifier::{verify_patch, verify_all, VerifyResult};
-use super::repair::{repair_patch, repair_all, RepairResult};
+use super::dxvk::{self, DxvkConfig, DXVK_CONF, DXVK_REQUIREMENT};
+use super::casefold::{case_variants, resolve};
+use super::config_wtf::{
+    apply_cvars, cvar_statuses, merge_changes, revert_cvars, take_over_change, ConfigChange, ConfigWtf, CvarStatus,
+    PatchCvars,
+};
+use super::extract::{extract_archive, safe_join, select_extracted, ArchiveKind};
+use super::copier::{install_file, uninstall_mpq, get_mpq_filename, InstallEvent, InstallError};
+use super::manifest::{InstalledFile, ManifestEntry};
+use super::process::ensure_not_running;
+use super::profiles::{Profile, Profiles};
+use super::strategy::{remove_existing, InstallStrategy};
+use super::store::{ContentStore, GcResult, StoreEntry, StoreStats};
+use super::targets::{default_target, download_filename, map_extracted, map_single, store_name, InstallSpec};
+use super::verifier::{combine_results, verify_file, verify_patch, VerifyResult, is_patch_installed};
+use super::repair::RepairResult;
+use super::requirements::{
+    check_requirement, place_requirement, remove_previous, remove_requirement_files, restore_backups, verify_download,
+    RequirementRecord, RequirementStatus,
+};
+use super::wdb::{self, WdbPolicy};
+use crate::models::{PatchId, PatchModule, Requirement};
+
+/// Name of the staging folder inside the downloads folder
+const STAGING_DIR: &str = "staging";
 
 /// Centralized installation manager
 ///
 /// Manages paths and coordinates install/verify/repair operations.
-/// Thread-safe via RwLock for path storage.
+/// Thread-safe via RwLock for profile storage.
 pub struct InstallManager {
-    wow_path: RwLock<Option<PathBuf>>,
+    profiles: RwLock<Profiles>,
     downloads_path: PathBuf,
+    store: ContentStore,
+    wdb_policy: RwLock<WdbPolicy>,
+    install_strategy: RwLock<InstallStrategy>,
+    /// How each catalog patch installs
+    catalog: RwLock<HashMap<PatchId, InstallSpec>>,
+    /// Requirements declared in the catalog
+    requirements: RwLock<Vec<Requirement>>,
+    /// Newest release of each requirement looked up this session
+    latest_versions: RwLock<HashMap<String, String>>,
 }
 
 impl InstallManager {
     /// Create a new InstallManager with the downloads directory
     pub fn new(downloads_path: PathBuf) -> Self {
         Self {
-            wow_path: RwLock::new(None),
+            profiles: RwLock::new(Profiles::default()),
+            store: ContentStore::new(&downloads_path),
             downloads_path,
+            wdb_policy: RwLock::new(WdbPolicy::default()),
+            install_strategy: RwLock::new(InstallStrategy::default()),
+            catalog: RwLock::new(HashMap::new()),
+            requirements: RwLock::new(Vec::new()),
+            latest_versions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Replace all profiles (e.g. with the ones loaded from settings)
+    pub fn load_profiles(&self, profiles: Profiles) {
+        *self.profiles.write().unwrap() = profiles;
+    }
+
+    /// Get a snapshot of all profiles for persisting or listing
+    pub fn profiles(&self) -> Profiles {
+        self.profiles.read().unwrap().clone()
+    }
+
+    /// Get a single profile (or the active one)
+    pub fn get_profile(&self, profile_id: Option<&str>) -> Result<Profile, InstallError> {
+        self.profiles.read().unwrap().resolve(profile_id).cloned()
+    }
+
+    /// Create a new profile, validating its WoW path if one is given
+    pub fn create_profile(&self, name: &str, wow_path: Option<PathBuf>) -> Result<Profile, InstallError> {
+        if let Some(path) = &wow_path {
+            if !WowDetector::is_valid_wow_folder(path) {
+                return Err(InstallError::InvalidWowFolder);
+            }
         }
+        self.profiles.write().unwrap().create(name, wow_path).cloned()
+    }
+
+    /// Make a profile the active one
+    pub fn switch_profile(&self, profile_id: &str) -> Result<Profile, InstallError> {
+        self.profiles.write().unwrap().switch(profile_id).cloned()
     }
 
-    /// Set the WoW installation path
+    /// Delete a profile (files installed through it are left untouched)
+    pub fn delete_profile(&self, profile_id: &str) -> Result<Profile, InstallError> {
+        self.profiles.write().unwrap().delete(profile_id)
+    }
+
+    /// Save the module selection and chosen variants for a profile
+    pub fn set_profile_selection(
+        &self,
+        profile_id: Option<&str>,
+        selected_modules: Vec<String>,
+        variants: std::collections::HashMap<String, String>,
+    ) -> Result<(), InstallError> {
+        let mut profiles = self.profiles.write().unwrap();
+        let profile = profiles.resolve_mut(profile_id)?;
+        profile.selected_modules = selected_modules;
+        profile.variants = variants;
+        Ok(())
+    }
+
+    /// Set the WoW installation path of the active profile
     ///
     /// Validates the path is a valid WoW installation before setting.
     pub fn set_wow_path(&self, path: PathBuf) -> Result<(), InstallError> {
+        self.set_profile_wow_path(None, path)
+    }
+
+    /// Set the WoW installation path of a profile
+    pub fn set_profile_wow_path(&self, profile_id: Option<&str>, path: PathBuf) -> Result<(), InstallError> {
         if !WowDetector::is_valid_wow_folder(&path) {
             return Err(InstallError::InvalidWowFolder);
         }
 
-        let mut wow_path = self.wow_path.write().unwrap();
-        *wow_path = Some(path);
+        let mut profiles = self.profiles.write().unwrap();
+        profiles.resolve_mut(profile_id)?.wow_path = Some(path);
         Ok(())
     }
 
-    /// Get the current WoW path (if set)
+    /// Get the WoW path of the active profile (if set)
     pub fn get_wow_path(&self) -> Option<PathBuf> {
-        self.wow_path.read().unwrap().clone()
+        self.profiles.read().unwrap().active().wow_path.clone()
     }
 
-    /// Get the Data folder path
-    pub fn get_data_folder(&self) -> Result<PathBuf, InstallError> {
-        let wow_path = self.wow_path.read().unwrap();
-        let path = wow_path.as_ref().ok_or(InstallError::WowPathNotSet)?;
-        Ok(WowDetector::get_data_folder(path))
+    /// Get the WoW path of a profile
+    pub fn get_profile_wow_path(&self, profile_id: Option<&str>) -> Result<PathBuf, InstallError> {
+        self.profiles
+            .read()
+            .unwrap()
+            .resolve(profile_id)?
+            .wow_path
+            .clone()
+            .ok_or(InstallError::WowPathNotSet)
+    }
+
+    /// Get a profile's WoW path for changing files in it
+    ///
+    /// Fails with `GameRunning` while a client runs from the folder.
+    fn writable_wow_path(&self, profile_id: Option<&str>) -> Result<PathBuf, InstallError> {
+        let wow_path = self.get_profile_wow_path(profile_id)?;
+        ensure_not_running(&wow_path)?;
+        Ok(wow_path)
+    }
+
+    /// Get the Data folder path of a profile
+    pub fn get_data_folder(&self, profile_id: Option<&str>) -> Result<PathBuf, InstallError> {
+        let wow_path = self.get_profile_wow_path(profile_id)?;
+        Ok(WowDetector::get_data_folder(&wow_path))
     }
 
     /// Get the downloads folder path
@@ -62,98 +183,913 @@ impl InstallManager {
         &self.downloads_path
     }
 
+    /// Set how the WDB cache is handled on install, repair and uninstall
+    pub fn set_wdb_policy(&self, policy: WdbPolicy) {
+        *self.wdb_policy.write().unwrap() = policy;
+    }
+
+    /// Get the current WDB cache policy
+    pub fn get_wdb_policy(&self) -> WdbPolicy {
+        *self.wdb_policy.read().unwrap()
+    }
+
+    /// Set how files are placed into the Data folder
+    pub fn set_install_strategy(&self, strategy: InstallStrategy) {
+        *self.install_strategy.write().unwrap() = strategy;
+    }
+
+    /// Get the current install strategy
+    pub fn get_install_strategy(&self) -> InstallStrategy {
+        *self.install_strategy.read().unwrap()
+    }
+
+    /// Replace the install specs taken from the catalog
+    pub fn set_catalog(&self, catalog: HashMap<PatchId, InstallSpec>) {
+        *self.catalog.write().unwrap() = catalog;
+    }
+
+    /// Replace the requirements taken from the catalog
+    pub fn set_requirements(&self, requirements: Vec<Requirement>) {
+        *self.requirements.write().unwrap() = requirements;
+    }
+
+    /// Get the catalog requirements
+    pub fn requirements(&self) -> Vec<Requirement> {
+        self.requirements.read().unwrap().clone()
+    }
+
+    /// Remember the newest release of a requirement for update checks
+    pub fn set_latest_version(&self, id: &str, version: String) {
+        self.latest_versions.write().unwrap().insert(id.to_string(), version);
+    }
+
+    /// Newest known version of a requirement: the last release looked up,
+    /// else the catalog's default
+    fn latest_version(&self, requirement: &Requirement) -> Option<String> {
+        self.latest_versions
+            .read()
+            .unwrap()
+            .get(&requirement.id)
+            .cloned()
+            .or_else(|| requirement.versions.first().cloned())
+    }
+
+    /// Get a catalog requirement by ID
+    pub fn requirement(&self, id: &str) -> Result<Requirement, InstallError> {
+        self.requirements
+            .read()
+            .unwrap()
+            .iter()
+            .find(|r| r.id == id)
+            .cloned()
+            .ok_or_else(|| InstallError::UnknownRequirement(id.to_string()))
+    }
+
+    /// Get how a patch installs; a single Data MPQ unless the catalog says otherwise
+    fn install_spec(&self, patch_id: &str) -> InstallSpec {
+        self.catalog.read().unwrap().get(patch_id).cloned().unwrap_or_default()
+    }
+
+    /// Get the CVars declared by every patch installed in a profile except one
+    fn other_cvars(&self, profile_id: Option<&str>, patch_id: &str) -> Result<Vec<PatchCvars>, InstallError> {
+        let installed: Vec<PatchId> = self
+            .profiles
+            .read()
+            .unwrap()
+            .resolve(profile_id)?
+            .manifest
+            .patches
+            .keys()
+            .filter(|id| id.as_str() != patch_id)
+            .cloned()
+            .collect();
+        Ok(installed
+            .into_iter()
+            .map(|id| {
+                let cvars = self.install_spec(&id).cvars;
+                (id, cvars)
+            })
+            .filter(|(_, cvars)| !cvars.is_empty())
+            .collect())
+    }
+
+    /// Revert a patch's CVar changes, handing keys other installed patches
+    /// still declare over to their manifest entries
+    async fn revert_patch_config(
+        &self,
+        wow_path: &Path,
+        profile_id: Option<&str>,
+        patch_id: &str,
+        changes: &[ConfigChange],
+    ) -> Result<(), InstallError> {
+        let handed_over = revert_cvars(wow_path, changes, &self.other_cvars(profile_id, patch_id)?).await?;
+        if handed_over.is_empty() {
+            return Ok(());
+        }
+        let mut profiles = self.profiles.write().unwrap();
+        let profile = profiles.resolve_mut(profile_id)?;
+        for (owner, change) in handed_over {
+            if let Some(entry) = profile.manifest.patches.get_mut(&owner) {
+                take_over_change(&mut entry.config, change);
+            }
+        }
+        Ok(())
+    }
+
+    /// Get a patch's entry in a profile's manifest
+    fn manifest_entry(&self, profile_id: Option<&str>, patch_id: &str) -> Result<Option<ManifestEntry>, InstallError> {
+        Ok(self.profiles.read().unwrap().resolve(profile_id)?.manifest.get(patch_id).cloned())
+    }
+
+    /// Get a scratch folder for unpacking archives, e.g. `staging/dxvk`
+    ///
+    /// Lives in the downloads folder so extracted files can be moved into
+    /// the store with a rename.
+    pub fn staging_dir(&self, name: &str) -> PathBuf {
+        self.downloads_path.join(STAGING_DIR).join(name)
+    }
+
+    /// Find a patch's download and its size without modifying anything
+    ///
+    /// Prefers a fresh loose download in the downloads folder, then the
+    /// store objects currently named after the patch (all of its targets
+    /// for a declared patch).
+    async fn find_download(&self, patch_id: &str) -> Result<Option<(PathBuf, u64)>, InstallError> {
+        let spec = self.install_spec(patch_id);
+        let name = download_filename(patch_id, &spec);
+        let loose_path = self.downloads_path.join(&name);
+        if loose_path.exists() {
+            let size = tokio::fs::metadata(&loose_path).await?.len();
+            return Ok(Some((loose_path, size)));
+        }
+
+        let entries: Vec<StoreEntry> = if spec.is_declared() {
+            self.store
+                .get_prefixed(&store_name(patch_id, ""))
+                .await?
+                .into_iter()
+                .map(|(_, entry)| entry)
+                .collect()
+        } else {
+            self.store.get(&name).await?.into_iter().collect()
+        };
+        Ok(entries
+            .first()
+            .map(|first| (first.path.clone(), entries.iter().map(|e| e.size).sum())))
+    }
+
+    /// Get the store entry for a patch, importing a fresh loose download first
+    ///
+    /// A loose download that turns out to be an archive is unpacked and
+    /// replaced by the MPQ it contains before the import.
+    async fn resolve_source(&self, patch_id: &str, spec: &InstallSpec) -> Result<Option<StoreEntry>, InstallError> {
+        let mpq_name = get_mpq_filename(patch_id);
+        let loose_path = self.downloads_path.join(&mpq_name);
+        if loose_path.exists() {
+            if ArchiveKind::detect(&loose_path).await?.is_some() {
+                self.unpack_download(patch_id, spec, &loose_path).await?;
+            }
+            return self.store.import(&loose_path, &mpq_name).await.map(Some);
+        }
+        self.store.get(&mpq_name).await
+    }
+
+    /// Get the files to install for a patch as (target, store entry) pairs
+    ///
+    /// Targets are paths relative to the WoW root. A fresh loose download
+    /// is imported first. Returns nothing when the patch has not been
+    /// downloaded.
+    async fn resolve_targets(&self, patch_id: &str, spec: &InstallSpec) -> Result<Vec<(String, StoreEntry)>, InstallError> {
+        if !spec.is_declared() {
+            let source = self.resolve_source(patch_id, spec).await?;
+            return Ok(source.map(|entry| (default_target(patch_id), entry)).into_iter().collect());
+        }
+
+        let prefix = store_name(patch_id, "");
+        let loose_path = self.downloads_path.join(download_filename(patch_id, spec));
+        if loose_path.exists() {
+            self.import_declared(patch_id, spec, &loose_path).await?;
+        }
+        Ok(self
+            .store
+            .get_prefixed(&prefix)
+            .await?
+            .into_iter()
+            .map(|(name, entry)| (name[prefix.len()..].to_string(), entry))
+            .collect())
+    }
+
+    /// Replace an archive download with the patch MPQ picked from it
+    ///
+    /// Uses the patch's catalog extract rules, or the archive's only MPQ.
+    /// The staging folder is removed afterwards, whether or not a file
+    /// could be picked.
+    async fn unpack_download(&self, patch_id: &str, spec: &InstallSpec, archive: &Path) -> Result<(), InstallError> {
+        let staging = self.staging_dir(patch_id);
+        let result = async {
+            let files = extract_archive(archive, &staging).await?;
+            let selected = select_extracted(&files, &spec.extract, &get_mpq_filename(patch_id))?;
+            log::info!("[Install] Using {} from the {} archive", selected.name, patch_id);
+            tokio::fs::rename(&selected.path, archive).await?;
+            Ok(())
+        }
+        .await;
+
+        remove_staging(&staging).await;
+        result
+    }
+
+    /// Import a declared patch's download into the store, one name per target
+    ///
+    /// Archives are unpacked and mapped with the catalog's file rules, or
+    /// by their `.toc` files for an addon; any other download is the
+    /// single declared file. Targets of an older
+    /// download of the patch are forgotten first.
+    async fn import_declared(&self, patch_id: &str, spec: &InstallSpec, download: &Path) -> Result<(), InstallError> {
+        let staging = self.staging_dir(patch_id);
+        let result = async {
+            let mapped = if ArchiveKind::detect(download).await?.is_some() {
+                let files = extract_archive(download, &staging).await?;
+                if spec.files.is_empty() {
+                    map_addon_folders(&files)?
+                } else {
+                    map_extracted(&files, &spec.files)?
+                }
+            } else {
+                vec![(download.to_path_buf(), map_single(&spec.files)?)]
+            };
+
+            self.store.forget_prefixed(&store_name(patch_id, "")).await?;
+            for (path, target) in mapped {
+                self.store.import(&path, &store_name(patch_id, &target)).await?;
+            }
+            if download.exists() {
+                tokio::fs::remove_file(download).await?;
+            }
+            Ok(())
+        }
+        .await;
+
+        remove_staging(&staging).await;
+        result
+    }
+
+    /// Record a successfully installed patch in a profile's manifest
+    fn record_installed(
+        &self,
+        profile_id: Option<&str>,
+        patch_id: &str,
+        files: Vec<InstalledFile>,
+        config: Vec<ConfigChange>,
+    ) {
+        let Some(first) = files.first().cloned() else {
+            return;
+        };
+        let file_name = first
+            .path
+            .rsplit('/')
+            .next()
+            .map(str::to_string)
+            .unwrap_or_else(|| get_mpq_filename(patch_id));
+
+        let mut profiles = self.profiles.write().unwrap();
+        if let Ok(profile) = profiles.resolve_mut(profile_id) {
+            let variant = profile.variants.get(patch_id).cloned();
+            profile.manifest.record(ManifestEntry {
+                patch_id: patch_id.to_string(),
+                file_name,
+                size: first.size,
+                variant,
+                sha256: first.sha256,
+                mode: first.mode,
+                installed_at: chrono::Utc::now().to_rfc3339(),
+                files,
+                config,
+            });
+        }
+    }
+
     /// Install a single patch
+    ///
+    /// Returns the path of its first installed file.
     pub async fn install_patch(
         &self,
+        profile_id: Option<&str>,
         patch_id: &str,
         on_event: Channel<InstallEvent>,
     ) -> Result<PathBuf, InstallError> {
-        let data_folder = self.get_data_folder()?;
-        let mpq_name = get_mpq_filename(patch_id);
-        let source_path = self.downloads_path.join(&mpq_name);
+        let wow_path = self.writable_wow_path(profile_id)?;
+        let spec = self.install_spec(patch_id);
+        let targets = self.resolve_targets(patch_id, &spec).await?;
+        if targets.is_empty() {
+            return Err(InstallError::DownloadNotFound(download_filename(patch_id, &spec)));
+        }
+
+        let files = self.install_targets(profile_id, patch_id, &spec, targets, on_event).await?;
+        target_dest(&wow_path, &files[0].path)
+    }
+
+    /// Place a patch's files and CVars and record them in the manifest
+    ///
+    /// Files a previous install of the patch placed that are no longer
+    /// part of it are removed, and its earlier Config.wtf changes are
+    /// reverted before the current CVars are applied.
+    async fn install_targets(
+        &self,
+        profile_id: Option<&str>,
+        patch_id: &str,
+        spec: &InstallSpec,
+        targets: Vec<(String, StoreEntry)>,
+        on_event: Channel<InstallEvent>,
+    ) -> Result<Vec<InstalledFile>, InstallError> {
+        let wow_path = self.get_profile_wow_path(profile_id)?;
+        let strategy = self.get_install_strategy();
+        let previous = self.manifest_entry(profile_id, patch_id)?;
+
+        let mut files = Vec::with_capacity(targets.len());
+        for (target, source) in targets {
+            let dest_path = target_dest(&wow_path, &target)?;
+            if let Some(parent) = dest_path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            let mode = install_file(&source.path, &dest_path, strategy, patch_id.to_string(), on_event.clone()).await?;
+            // Other casings of the file would shadow or duplicate it in game
+            for duplicate in case_variants(&dest_path).into_iter().filter(|p| *p != dest_path) {
+                log::info!("[Install] Removing case-variant duplicate {:?}", duplicate);
+                remove_existing(&duplicate).await?;
+            }
+            files.push(InstalledFile {
+                path: target,
+                size: source.size,
+                sha256: Some(source.sha256),
+                mode,
+            });
+        }
+
+        if let Some(previous) = &previous {
+            for old in previous.installed_files() {
+                if !files.iter().any(|f| f.path == old.path) {
+                    remove_existing(&target_dest(&wow_path, &old.path)?).await?;
+                    remove_empty_addon_dirs(&wow_path, &old.path).await;
+                }
+            }
+            self.revert_patch_config(&wow_path, profile_id, patch_id, &previous.config).await?;
+        }
+        let config = apply_cvars(&wow_path, &spec.cvars).await?;
+
+        self.record_installed(profile_id, patch_id, files.clone(), config);
+        Ok(files)
+    }
+
+    /// Targets a patch currently has in a profile's WoW folder
+    ///
+    /// Taken from the manifest; a patch without a manifest entry may still
+    /// have its default Data MPQ from before manifests existed.
+    fn installed_targets(&self, profile_id: Option<&str>, patch_id: &str, spec: &InstallSpec) -> Result<Vec<String>, InstallError> {
+        Ok(match self.manifest_entry(profile_id, patch_id)? {
+            Some(entry) => entry.installed_files().into_iter().map(|f| f.path).collect(),
+            None if !spec.is_declared() => vec![default_target(patch_id)],
+            None => Vec::new(),
+        })
+    }
+
+    /// IDs of the given patches whose files are all present in a profile's WoW folder
+    pub async fn detect_installed(&self, profile_id: Option<&str>, patch_ids: &[&str]) -> Result<Vec<String>, InstallError> {
+        let wow_path = self.get_profile_wow_path(profile_id)?;
+        let mut installed = Vec::new();
+        for id in patch_ids {
+            let targets = self.installed_targets(profile_id, id, &self.install_spec(id))?;
+            let mut present = !targets.is_empty();
+            for target in &targets {
+                present &= target_dest(&wow_path, target)?.exists();
+            }
+            if present {
+                installed.push(id.to_string());
+            }
+        }
+        Ok(installed)
+    }
+
+    /// List the addons in a profile's WoW folder
+    ///
+    /// Addons placed by an installed catalog entry carry its patch ID.
+    pub async fn list_addons(&self, profile_id: Option<&str>) -> Result<Vec<InstalledAddon>, InstallError> {
+        let wow_path = self.get_profile_wow_path(profile_id)?;
+        let mut addons = scan_addons(&wow_path).await?;
+
+        let profile = self.get_profile(profile_id)?;
+        for addon in &mut addons {
+            addon.patch_id = profile
+                .manifest
+                .patches
+                .values()
+                .find(|entry| {
+                    entry
+                        .installed_files()
+                        .iter()
+                        .any(|f| addon_of_target(&f.path).is_some_and(|n| n.eq_ignore_ascii_case(&addon.name)))
+                })
+                .map(|entry| entry.patch_id.clone());
+        }
+        Ok(addons)
+    }
+
+    /// Compare the CVars recommended by a profile's installed and selected
+    /// patches with its Config.wtf
+    pub async fn cvar_status(&self, profile_id: Option<&str>) -> Result<Vec<CvarStatus>, InstallError> {
+        let wow_path = self.get_profile_wow_path(profile_id)?;
+        let profile = self.get_profile(profile_id)?;
+        let config = ConfigWtf::load(&ConfigWtf::path(&wow_path)).await?;
+
+        let mut patch_ids = profile.manifest.patch_ids();
+        for id in &profile.selected_modules {
+            if !patch_ids.contains(id) {
+                patch_ids.push(id.clone());
+            }
+        }
+
+        let mut statuses = Vec::new();
+        for patch_id in patch_ids {
+            let spec = self.install_spec(&patch_id);
+            let recorded = profile.manifest.get(&patch_id).map(|e| e.config.as_slice()).unwrap_or_default();
+            statuses.extend(cvar_statuses(&config, &patch_id, &spec.cvars, recorded));
+        }
+        Ok(statuses)
+    }
+
+    /// Set an installed patch's recommended CVars again
+    ///
+    /// The changes join the ones its install recorded, so uninstalling the
+    /// patch reverts them too.
+    pub async fn apply_patch_cvars(&self, profile_id: Option<&str>, patch_id: &str) -> Result<(), InstallError> {
+        let wow_path = self.writable_wow_path(profile_id)?;
+        if self.manifest_entry(profile_id, patch_id)?.is_none() {
+            return Err(InstallError::PatchNotInstalled(patch_id.to_string()));
+        }
+        let changes = apply_cvars(&wow_path, &self.install_spec(patch_id).cvars).await?;
+
+        let mut profiles = self.profiles.write().unwrap();
+        if let Some(entry) = profiles.resolve_mut(profile_id)?.manifest.patches.get_mut(patch_id) {
+            merge_changes(&mut entry.config, changes);
+        }
+        Ok(())
+    }
+
+    /// Put back the CVars an installed patch changed, keeping the patch
+    pub async fn revert_patch_cvars(&self, profile_id: Option<&str>, patch_id: &str) -> Result<(), InstallError> {
+        let wow_path = self.writable_wow_path(profile_id)?;
+        let entry = self
+            .manifest_entry(profile_id, patch_id)?
+            .ok_or_else(|| InstallError::PatchNotInstalled(patch_id.to_string()))?;
+        self.revert_patch_config(&wow_path, profile_id, patch_id, &entry.config).await?;
+
+        let mut profiles = self.profiles.write().unwrap();
+        if let Some(entry) = profiles.resolve_mut(profile_id)?.manifest.patches.get_mut(patch_id) {
+            entry.config.clear();
+        }
+        Ok(())
+    }
+
+    /// Check every catalog requirement in a profile's WoW folder
+    ///
+    /// `required_by` lists the patches among the profile's selected and
+    /// installed ones whose catalog entry declares the requirement.
+    pub async fn check_requirements(
+        &self,
+        profile_id: Option<&str>,
+        modules: &[PatchModule],
+    ) -> Result<Vec<RequirementStatus>, InstallError> {
+        let wow_path = self.get_profile_wow_path(profile_id)?;
+        let profile = self.get_profile(profile_id)?;
+        let requirements = self.requirements.read().unwrap().clone();
+
+        let mut statuses = Vec::with_capacity(requirements.len());
+        for requirement in &requirements {
+            let required_by = modules
+                .iter()
+                .filter(|m| m.requires.contains(&requirement.id))
+                .filter(|m| profile.selected_modules.contains(&m.id) || profile.manifest.get(&m.id).is_some())
+                .map(|m| m.id.clone())
+                .collect();
+            let record = profile.requirements.get(&requirement.id);
+            let status = check_requirement(&wow_path, requirement, record, required_by).await?;
+            statuses.push(status.with_latest(self.latest_version(requirement)));
+        }
+        Ok(statuses)
+    }
+
+    /// Version a requirement is pinned to in a profile, if any
+    pub fn pinned_version(&self, profile_id: Option<&str>, id: &str) -> Result<Option<String>, InstallError> {
+        let profile = self.get_profile(profile_id)?;
+        Ok(profile.requirements.get(id).filter(|r| r.pinned).and_then(|r| r.version.clone()))
+    }
+
+    /// Pin a requirement installed through the patcher to its version, or unpin it
+    pub fn set_requirement_pinned(&self, profile_id: Option<&str>, id: &str, pinned: bool) -> Result<(), InstallError> {
+        let mut profiles = self.profiles.write().unwrap();
+        let record = profiles
+            .resolve_mut(profile_id)?
+            .requirements
+            .get_mut(id)
+            .ok_or_else(|| InstallError::RequirementNotInstalled(id.to_string()))?;
+        record.pinned = pinned;
+        Ok(())
+    }
+
+    /// Install or update a requirement from its download
+    ///
+    /// The download is checked against `published_sha256` when its source
+    /// published one. Files an earlier install placed that the new one
+    /// does not are removed, while files it generated (e.g. `dxvk.conf`),
+    /// backups and the pin are kept. The download and its staging folder
+    /// are deleted afterwards, whether or not the install succeeded.
+    pub async fn install_requirement(
+        &self,
+        profile_id: Option<&str>,
+        id: &str,
+        version: Option<String>,
+        download: &Path,
+        published_sha256: Option<&str>,
+    ) -> Result<(), InstallError> {
+        let staging = self.staging_dir(&format!("requirement-{}", id));
+        let result = async {
+            let wow_path = self.writable_wow_path(profile_id)?;
+            let requirement = self.requirement(id)?;
+            let previous = self.get_profile(profile_id)?.requirements.get(id).cloned();
+            let managed = previous.as_ref().map(RequirementRecord::managed_paths).unwrap_or_default();
+            let sha256 = verify_download(download, &requirement.name, published_sha256).await?;
+            let placed = place_requirement(&wow_path, &requirement, download, &staging, &managed).await?;
+            Ok::<_, InstallError>((wow_path, previous, placed, sha256))
+        }
+        .await;
+        remove_staging(&staging).await;
+        if download.exists() {
+            tokio::fs::remove_file(download).await?;
+        }
+        let (wow_path, previous, (files, mut backups), sha256) = result?;
+
+        let mut generated = Vec::new();
+        let mut pinned = false;
+        let mut previous_version = None;
+        if let Some(previous) = previous {
+            pinned = previous.pinned;
+            previous_version = previous.version;
+            let stale: Vec<String> = previous
+                .files
+                .into_iter()
+                .filter(|old| !files.iter().any(|f| f.path.eq_ignore_ascii_case(&old.path)))
+                .map(|f| f.path)
+                .collect();
+            remove_requirement_files(&wow_path, &stale).await?;
+            generated = previous.generated;
+            for backup in previous.backups {
+                if !backups.contains(&backup) {
+                    backups.push(backup);
+                }
+            }
+        }
+
+        let mut profiles = self.profiles.write().unwrap();
+        profiles.resolve_mut(profile_id)?.requirements.insert(
+            id.to_string(),
+            RequirementRecord {
+                version,
+                files,
+                generated,
+                backups,
+                sha256: Some(sha256),
+                verified: published_sha256.is_some(),
+                pinned,
+                previous_version,
+                installed_at: chrono::Utc::now().to_rfc3339(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Remove a requirement's files and put back what it replaced
+    ///
+    /// Uses the files recorded at install time, or just the detected file
+    /// for a requirement that was put there by hand.
+    pub async fn uninstall_requirement(&self, profile_id: Option<&str>, id: &str) -> Result<(), InstallError> {
+        let wow_path = self.writable_wow_path(profile_id)?;
+        let requirement = self.requirement(id)?;
+        match self.get_profile(profile_id)?.requirements.get(id) {
+            Some(record) => {
+                remove_requirement_files(&wow_path, &record.managed_paths()).await?;
+                let files: Vec<String> = record.files.iter().map(|f| f.path.clone()).collect();
+                remove_previous(&wow_path, &files, id).await?;
+                restore_backups(&wow_path, &record.backups, id).await?;
+            }
+            None => remove_requirement_files(&wow_path, std::slice::from_ref(&requirement.detect.file)).await?,
+        }
+
+        let mut profiles = self.profiles.write().unwrap();
+        profiles.resolve_mut(profile_id)?.requirements.remove(id);
+        Ok(())
+    }
+
+    /// Write a managed `dxvk.conf` for a DXVK installed through the patcher
+    ///
+    /// The file is recorded with the DXVK install, so uninstalling DXVK
+    /// removes it and puts back a `dxvk.conf` it replaced.
+    pub async fn write_dxvk_conf(&self, profile_id: Option<&str>, config: &DxvkConfig) -> Result<(), InstallError> {
+        let wow_path = self.writable_wow_path(profile_id)?;
+        if !self.get_profile(profile_id)?.requirements.contains_key(DXVK_REQUIREMENT) {
+            return Err(InstallError::RequirementNotInstalled(DXVK_REQUIREMENT.to_string()));
+        }
+        let backed_up = dxvk::write_conf(&wow_path, config).await?;
+
+        let mut profiles = self.profiles.write().unwrap();
+        if let Some(record) = profiles.resolve_mut(profile_id)?.requirements.get_mut(DXVK_REQUIREMENT) {
+            if !record.generated.iter().any(|g| g == DXVK_CONF) {
+                record.generated.push(DXVK_CONF.to_string());
+            }
+            if backed_up && !record.backups.iter().any(|b| b == DXVK_CONF) {
+                record.backups.push(DXVK_CONF.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Work out the disk space needed to download and install patches
+    ///
+    /// Downloaded patches count with their stored size, missing ones with
+    /// their size from `catalog_sizes` (unknown sizes count as zero).
+    /// Files that will be replaced count against the Data folder's need,
+    /// and link strategies only need space when they would fall back to a
+    /// copy. When downloads and Data share a filesystem a single combined
+    /// check is returned.
+    pub async fn check_install_space(
+        &self,
+        profile_id: Option<&str>,
+        patch_ids: &[&str],
+        catalog_sizes: &HashMap<String, u64>,
+    ) -> Result<Vec<SpaceCheck>, InstallError> {
+        let data_folder = self.get_data_folder(profile_id)?;
+        let strategy = self.get_install_strategy();
+
+        let mut download_needed: u64 = 0;
+        let mut data_needed: u64 = 0;
+
+        let wow_path = self.get_profile_wow_path(profile_id)?;
+        for id in patch_ids {
+            let (source_path, size) = match self.find_download(id).await? {
+                Some(found) => found,
+                None => {
+                    let size = catalog_sizes.get(*id).copied().unwrap_or(0);
+                    download_needed += size;
+                    (self.downloads_path.clone(), size)
+                }
+            };
+            let copies = match strategy {
+                InstallStrategy::Copy | InstallStrategy::Reflink => true,
+                InstallStrategy::Hardlink => !same_filesystem(&source_path, &data_folder),
+                InstallStrategy::Symlink => false,
+            };
+            let size = if copies { size } else { 0 };
 
-        if !source_path.exists() {
-            return Err(InstallError::DownloadNotFound(mpq_name));
+            let mut replaced = 0;
+            for target in self.installed_targets(profile_id, id, &self.install_spec(id))? {
+                if let Ok(meta) = tokio::fs::symlink_metadata(target_dest(&wow_path, &target)?).await {
+                    if meta.is_file() {
+                        replaced += meta.len();
+                    }
+                }
+            }
+            data_needed += size.saturating_sub(replaced);
         }
 
-        install_mpq(&source_path, &data_folder, patch_id.to_string(), on_event).await
+        if same_filesystem(&self.downloads_path, &data_folder) {
+            return Ok(vec![SpaceCheck::new(&data_folder, download_needed + data_needed)?]);
+        }
+        Ok(vec![
+            SpaceCheck::new(&self.downloads_path, download_needed)?,
+            SpaceCheck::new(&data_folder, data_needed)?,
+        ])
     }
 
     /// Install multiple patches
     ///
-    /// Automatically clears the WDB cache folder before installing.
+    /// Checks free space in the Data folder first and fails with
+    /// `InsufficientSpace` before anything is written. Then clears the
+    /// WDB cache folder and installs each patch.
     pub async fn install_patches(
         &self,
+        profile_id: Option<&str>,
         patch_ids: &[&str],
         on_event: Channel<InstallEvent>,
-    ) -> Vec<Result<PathBuf, InstallError>> {
+    ) -> Result<Vec<Result<PathBuf, InstallError>>, InstallError> {
+        self.writable_wow_path(profile_id)?;
+        let checks = self.check_install_space(profile_id, patch_ids, &HashMap::new()).await?;
+        if let Some(check) = checks.into_iter().find(|c| !c.is_sufficient()) {
+            return Err(check.into());
+        }
+
         // Clear WDB folder before installing mods (required for mods to work properly)
-        if let Err(e) = self.clear_wdb_folder().await {
+        if let Err(e) = self.clear_wdb_folder(profile_id).await {
             log::warn!("[Install] Failed to clear WDB folder: {:?}", e);
         }
 
         let mut results = Vec::with_capacity(patch_ids.len());
 
         for id in patch_ids {
-            let result = self.install_patch(id, on_event.clone()).await;
+            let result = self.install_patch(profile_id, id, on_event.clone()).await;
             results.push(result);
         }
 
-        results
+        Ok(results)
     }
 
-    /// Clear the WDB cache folder and replace with empty file
+    /// Uninstall patches from a profile's Data folder
+    ///
+    /// Removes the MPQ files and their manifest entries, then clears the
+    /// WDB cache. Returns the IDs that were removed.
+    pub async fn uninstall_patches(
+        &self,
+        profile_id: Option<&str>,
+        patch_ids: &[&str],
+    ) -> Result<Vec<String>, InstallError> {
+        let wow_path = self.writable_wow_path(profile_id)?;
+        let data_folder = WowDetector::get_data_folder(&wow_path);
+        if !data_folder.exists() {
+            return Err(InstallError::InvalidWowFolder);
+        }
+
+        let mut uninstalled = Vec::new();
+        for patch_id in patch_ids {
+            let result = match self.manifest_entry(profile_id, patch_id)? {
+                Some(entry) => async {
+                    remove_installed(&wow_path, &entry).await?;
+                    self.revert_patch_config(&wow_path, profile_id, patch_id, &entry.config).await
+                }
+                .await,
+                None => uninstall_mpq(&data_folder, patch_id).await,
+            };
+            match result {
+                Ok(_) => {
+                    if let Ok(profile) = self.profiles.write().unwrap().resolve_mut(profile_id) {
+                        profile.manifest.remove(patch_id);
+                    }
+                    uninstalled.push(patch_id.to_string());
+                }
+                Err(e) => log::error!("[Uninstall] Failed to uninstall {}: {:?}", patch_id, e),
+            }
+        }
+
+        // Clear WDB cache after uninstalling
+        if let Err(e) = self.clear_wdb_folder(profile_id).await {
+            log::warn!("[Uninstall] Failed to clear WDB: {:?}", e);
+        }
+
+        Ok(uninstalled)
+    }
+
+    /// Clear the WDB cache folder according to the configured policy
     ///
     /// The WDB folder contains cached data that must be cleared when mods are
     /// installed or updated. Per the official guide:
     /// 1. Delete the existing WDB folder
     /// 2. Create an empty file named "WDB" (prevents WoW from recreating cache)
-    pub async fn clear_wdb_folder(&self) -> Result<(), InstallError> {
+    ///
+    /// The folder is moved to `WDB.bak` instead of being deleted, and step 2
+    /// only happens with `WdbPolicy::ClearAndBlock`.
+    pub async fn clear_wdb_folder(&self, profile_id: Option<&str>) -> Result<(), InstallError> {
         // Get path without holding lock across await
-        let wdb_path = {
-            let wow_path = self.wow_path.read().unwrap();
-            let path = wow_path.as_ref().ok_or(InstallError::WowPathNotSet)?;
-            path.join("WDB")
-        };
+        let wow_path = self.writable_wow_path(profile_id)?;
+        let policy = self.get_wdb_policy();
 
-        // Remove WDB whether it's a folder or file
-        if wdb_path.exists() {
-            log::info!("[Install] Removing WDB: {:?}", wdb_path);
-            if wdb_path.is_dir() {
-                tokio::fs::remove_dir_all(&wdb_path).await?;
-            } else {
-                tokio::fs::remove_file(&wdb_path).await?;
+        wdb::clear_wdb(&wow_path, policy).await
+    }
+
+    /// Restore the WDB cache once no managed patches remain installed
+    ///
+    /// Removes the blocking `WDB` file and puts the backed-up cache back.
+    /// Fails with `PatchesStillInstalled` if the profile's manifest still
+    /// lists patches, or any of `patch_ids` is present in the Data folder.
+    /// Returns true if anything was changed.
+    pub async fn restore_wdb(
+        &self,
+        profile_id: Option<&str>,
+        patch_ids: &[&str],
+    ) -> Result<bool, InstallError> {
+        let profile = self.get_profile(profile_id)?;
+        let wow_path = profile.wow_path.ok_or(InstallError::WowPathNotSet)?;
+        ensure_not_running(&wow_path)?;
+        let data_folder = WowDetector::get_data_folder(&wow_path);
+
+        let mut remaining = profile.manifest.patch_ids();
+        for id in patch_ids {
+            if !remaining.iter().any(|r| r == id) && is_patch_installed(id, &data_folder).await {
+                remaining.push(id.to_string());
             }
         }
+        if !remaining.is_empty() {
+            return Err(InstallError::PatchesStillInstalled(remaining.join(", ")));
+        }
 
-        // Create empty file named WDB (prevents WoW from recreating cache folder)
-        log::info!("[Install] Creating empty WDB file to prevent cache recreation");
-        tokio::fs::File::create(&wdb_path).await?;
-        log::info!("[Install] WDB cleared and replaced with empty file");
-
-        Ok(())
+        wdb::restore_wdb(&wow_path).await
     }
 
     /// Verify a single patch
-    pub async fn verify_patch(&self, patch_id: &str) -> Result<VerifyResult, InstallError> {
-        let data_folder = self.get_data_folder()?;
-        Ok(verify_patch(patch_id, &data_folder, &self.downloads_path).await)
+    pub async fn verify_patch(&self, profile_id: Option<&str>, patch_id: &str) -> Result<VerifyResult, InstallError> {
+        let wow_path = self.get_profile_wow_path(profile_id)?;
+        let spec = self.install_spec(patch_id);
+
+        let Some(entry) = self.manifest_entry(profile_id, patch_id)? else {
+            if spec.is_declared() {
+                return Ok(VerifyResult::NotInstalled);
+            }
+            // Installed before manifests existed, or by hand
+            let download_path = self
+                .find_download(patch_id)
+                .await?
+                .map(|(path, _)| path)
+                .unwrap_or_else(|| self.downloads_path.join(get_mpq_filename(patch_id)));
+            return Ok(verify_patch(patch_id, &WowDetector::get_data_folder(&wow_path), &download_path).await);
+        };
+
+        let mut results = Vec::new();
+        for file in entry.installed_files() {
+            let expected = self.expected_source(patch_id, &spec, &file).await?;
+            results.push(verify_file(&target_dest(&wow_path, &file.path)?, &expected).await);
+        }
+        let result = combine_results(results);
+        if !matches!(result, VerifyResult::Installed { .. }) || spec.cvars.is_empty() {
+            return Ok(result);
+        }
+
+        let config = ConfigWtf::load(&ConfigWtf::path(&wow_path)).await?;
+        for (key, value) in &spec.cvars {
+            let actual = config.get(key);
+            if actual != Some(value.as_str()) {
+                return Ok(VerifyResult::ConfigMismatch {
+                    key: key.clone(),
+                    expected: value.clone(),
+                    actual: actual.map(str::to_string),
+                });
+            }
+        }
+        Ok(result)
+    }
+
+    /// Get the download an installed file should match
+    ///
+    /// This is the current download for its target, so a newer download
+    /// shows up as a mismatch that repair fixes; otherwise the stored
+    /// object it was installed from.
+    async fn expected_source(&self, patch_id: &str, spec: &InstallSpec, file: &InstalledFile) -> Result<PathBuf, InstallError> {
+        if spec.is_declared() {
+            if let Some(entry) = self.store.get(&store_name(patch_id, &file.path)).await? {
+                return Ok(entry.path);
+            }
+        } else if let Some((path, _)) = self.find_download(patch_id).await? {
+            return Ok(path);
+        }
+        Ok(match &file.sha256 {
+            Some(sha256) => self.store.object_path(sha256),
+            None => self.downloads_path.join(download_filename(patch_id, spec)),
+        })
     }
 
     /// Verify multiple patches
-    pub async fn verify_patches(&self, patch_ids: &[&str]) -> Result<Vec<(String, VerifyResult)>, InstallError> {
-        let data_folder = self.get_data_folder()?;
-        Ok(verify_all(patch_ids, &data_folder, &self.downloads_path).await)
+    pub async fn verify_patches(
+        &self,
+        profile_id: Option<&str>,
+        patch_ids: &[&str],
+    ) -> Result<Vec<(String, VerifyResult)>, InstallError> {
+        let mut results = Vec::with_capacity(patch_ids.len());
+        for id in patch_ids {
+            results.push((id.to_string(), self.verify_patch(profile_id, id).await?));
+        }
+        Ok(results)
     }
 
     /// Repair a single patch
     pub async fn repair_patch(
         &self,
+        profile_id: Option<&str>,
         patch_id: &str,
         on_event: Channel<InstallEvent>,
     ) -> Result<RepairResult, InstallError> {
-        let data_folder = self.get_data_folder()?;
-        Ok(repair_patch(patch_id, &data_folder, &self.downloads_path, on_event).await)
+        self.writable_wow_path(profile_id)?;
+        let spec = self.install_spec(patch_id);
+        let targets = self.resolve_targets(patch_id, &spec).await?;
+        if targets.is_empty() {
+            return Ok(RepairResult::DownloadMissing { patch_id: patch_id.to_string() });
+        }
+
+        // Re-install (install_file replaces existing files or links)
+        Ok(match self.install_targets(profile_id, patch_id, &spec, targets, on_event).await {
+            Ok(files) => RepairResult::Repaired {
+                patch_id: patch_id.to_string(),
+                mode: files[0].mode,
+            },
+            Err(e) => RepairResult::Failed {
+                patch_id: patch_id.to_string(),
+                error: e.to_string(),
+            },
+        })
     }
 
     /// Repair multiple patches
@@ -161,19 +1097,46 @@ impl InstallManager {
     /// Automatically clears the WDB cache folder before repairing/updating.
     pub async fn repair_patches(
         &self,
+        profile_id: Option<&str>,
         patch_ids: &[&str],
         on_event: Channel<InstallEvent>,
     ) -> Result<Vec<RepairResult>, InstallError> {
+        self.writable_wow_path(profile_id)?;
+
         // Clear WDB folder before updating mods (required for mods to work properly)
-        if let Err(e) = self.clear_wdb_folder().await {
+        if let Err(e) = self.clear_wdb_folder(profile_id).await {
             log::warn!("[Install] Failed to clear WDB folder: {:?}", e);
         }
 
-        let data_folder = self.get_data_folder()?;
-        Ok(repair_all(patch_ids, &data_folder, &self.downloads_path, on_event).await)
+        let mut results = Vec::with_capacity(patch_ids.len());
+        for id in patch_ids {
+            results.push(self.repair_patch(profile_id, id, on_event.clone()).await?);
+        }
+        Ok(results)
+    }
+
+    /// Summarize the disk usage of the shared download store
+    pub async fn download_cache_stats(&self) -> Result<StoreStats, InstallError> {
+        self.store.stats().await
     }
 
-    /// Try to auto-detect WoW folder and set it
+    /// Remove stored downloads that no profile's manifest references
+    ///
+    /// The current download for each name is always kept.
+    pub async fn collect_download_garbage(&self) -> Result<GcResult, InstallError> {
+        let referenced: HashSet<String> = {
+            let profiles = self.profiles.read().unwrap();
+            profiles
+                .profiles
+                .iter()
+                .flat_map(|p| p.manifest.referenced_hashes())
+                .map(str::to_string)
+                .collect()
+        };
+        self.store.collect_garbage(&referenced).await
+    }
+
+    /// Try to auto-detect WoW folder and set it on the active profile
     pub fn try_auto_detect(&self) -> bool {
         if let Some(path) = WowDetector::auto_detect() {
             self.set_wow_path(path).is_ok()
@@ -182,3 +1145,416 @@ impl InstallManager {
         }
     }
 }
+
+/// Resolve a target path relative to the WoW root
+///
+/// Existing files and folders are found in any casing, so a
+/// `data/patch-a.MPQ` is replaced rather than joined by a second copy.
+pub(super) fn target_dest(wow_path: &Path, target: &str) -> Result<PathBuf, InstallError> {
+    safe_join(wow_path, target).map_err(|_| InstallError::InvalidTarget(target.to_string()))?;
+    Ok(resolve(wow_path, target))
+}
+
+/// Remove a staging folder, logging failures
+async fn remove_staging(staging: &Path) {
+    match tokio::fs::remove_dir_all(staging).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            log::warn!("[Install] Failed to remove staging folder {:?}: {}", staging, e);
+        }
+        _ => {}
+    }
+}
+
+/// Remove every file a manifest entry lists
+async fn remove_installed(wow_path: &Path, entry: &ManifestEntry) -> Result<(), InstallError> {
+    for file in entry.installed_files() {
+        remove_existing(&target_dest(wow_path, &file.path)?).await?;
+        remove_empty_addon_dirs(wow_path, &file.path).await;
+    }
+    Ok(())
+}
+
+/// Remove folders below `Interface/AddOns/` that a removed file left empty
+async fn remove_empty_addon_dirs(wow_path: &Path, target: &str) {
+    let parts: Vec<&str> = target.split('/').collect();
+    if parts.len() < 4
+        || !parts[0].eq_ignore_ascii_case("Interface")
+        || !parts[1].eq_ignore_ascii_case("AddOns")
+    {
+        return;
+    }
+    // remove_dir fails on the first folder that still has files
+    for depth in (3..parts.len()).rev() {
+        if tokio::fs::remove_dir(resolve(wow_path, parts[..depth].join("/"))).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::install::detector::test_wow;
+    use crate::models::PatchFile;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_install_records_manifest_per_profile() {
+        let downloads = tempdir().unwrap();
+        let main_wow = tempdir().unwrap();
+        let ptr_wow = tempdir().unwrap();
+        test_wow::create(main_wow.path());
+        test_wow::create(ptr_wow.path());
+        std::fs::write(downloads.path().join("Patch-A.mpq"), b"patch data").unwrap();
+
+        let manager = InstallManager::new(downloads.path().to_path_buf());
+        manager.set_wow_path(main_wow.path().to_path_buf()).unwrap();
+        let test_id = manager
+            .create_profile("Test", Some(ptr_wow.path().to_path_buf()))
+            .unwrap()
+            .id;
+
+        let results = manager
+            .install_patches(Some(&test_id), &["A"], Channel::new(|_| Ok(())))
+            .await
+            .unwrap();
+        assert!(results[0].is_ok());
+
+        assert!(ptr_wow.path().join("Data/Patch-A.mpq").exists());
+        assert!(!main_wow.path().join("Data/Patch-A.mpq").exists());
+        // The download moved into the shared store
+        assert!(!downloads.path().join("Patch-A.mpq").exists());
+
+        // The main profile installs the same stored copy without re-downloading
+        let results = manager.install_patches(None, &["A"], Channel::new(|_| Ok(()))).await.unwrap();
+        assert!(results[0].is_ok());
+        assert!(main_wow.path().join("Data/Patch-A.mpq").exists());
+
+        let test_profile = manager.get_profile(Some(&test_id)).unwrap();
+        assert_eq!(test_profile.manifest.get("A").unwrap().size, 10);
+        assert!(test_profile.manifest.get("A").unwrap().sha256.is_some());
+        assert!(manager.get_profile(None).unwrap().manifest.contains("A"));
+
+        let removed = manager.uninstall_patches(Some(&test_id), &["A"]).await.unwrap();
+        assert_eq!(removed, vec!["A".to_string()]);
+        assert!(manager.get_profile(Some(&test_id)).unwrap().manifest.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_install_unpacks_archive_download() {
+        use std::io::Write;
+
+        let downloads = tempdir().unwrap();
+        let wow = tempdir().unwrap();
+        test_wow::create(wow.path());
+
+        // patch-a.zip saved under the patch's MPQ name by the downloader
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, data) in [("Alt/Patch-A.mpq", b"MPQ\x1Aalt"), ("HD/Patch-A.mpq", b"MPQ\x1Ahd!")] {
+            zip.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        std::fs::write(downloads.path().join("Patch-A.mpq"), zip.finish().unwrap().into_inner()).unwrap();
+
+        let manager = InstallManager::new(downloads.path().to_path_buf());
+        manager.set_wow_path(wow.path().to_path_buf()).unwrap();
+        let spec = InstallSpec { extract: vec!["HD/*.mpq".to_string()], ..Default::default() };
+        manager.set_catalog([("A".to_string(), spec)].into_iter().collect());
+
+        manager.install_patch(None, "A", Channel::new(|_| Ok(()))).await.unwrap();
+
+        assert_eq!(std::fs::read(wow.path().join("Data/Patch-A.mpq")).unwrap(), b"MPQ\x1Ahd!");
+        assert!(!manager.staging_dir("A").exists());
+        assert_eq!(manager.get_profile(None).unwrap().manifest.get("A").unwrap().size, 7);
+    }
+
+    #[tokio::test]
+    async fn test_declared_files_and_cvars_round_trip() {
+        use std::io::Write;
+
+        let downloads = tempdir().unwrap();
+        let wow = tempdir().unwrap();
+        test_wow::create(wow.path());
+        std::fs::create_dir_all(wow.path().join("WTF")).unwrap();
+        std::fs::write(wow.path().join("WTF/Config.wtf"), "SET gxApi \"opengl\"\n").unwrap();
+
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, data) in [
+            ("Helper-main/Helper.toc", &b"## Interface: 11200"[..]),
+            ("Helper-main/img/icon.tga", b"tga"),
+            ("bin/Helper.dll", b"MZdll"),
+        ] {
+            zip.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        std::fs::write(downloads.path().join("Patch-H.download"), zip.finish().unwrap().into_inner()).unwrap();
+
+        let manager = InstallManager::new(downloads.path().to_path_buf());
+        manager.set_wow_path(wow.path().to_path_buf()).unwrap();
+        let spec = InstallSpec {
+            files: vec![
+                PatchFile {
+                    source: Some("Helper-main/*".to_string()),
+                    destination: "Interface/AddOns/Helper/".to_string(),
+                    rename: None,
+                },
+                PatchFile { source: Some("*.dll".to_string()), destination: String::new(), rename: None },
+            ],
+            cvars: [("gxApi".to_string(), "d3d9".to_string())].into_iter().collect(),
+            ..Default::default()
+        };
+        manager.set_catalog([("H".to_string(), spec)].into_iter().collect());
+
+        manager.install_patch(None, "H", Channel::new(|_| Ok(()))).await.unwrap();
+        assert!(wow.path().join("Interface/AddOns/Helper/img/icon.tga").exists());
+        assert!(wow.path().join("Helper.dll").exists());
+        assert!(std::fs::read_to_string(wow.path().join("WTF/Config.wtf")).unwrap().contains("d3d9"));
+        assert!(matches!(manager.verify_patch(None, "H").await.unwrap(), VerifyResult::Installed { .. }));
+        assert_eq!(manager.detect_installed(None, &["H"]).await.unwrap(), vec!["H".to_string()]);
+
+        // Reverting keeps the patch, and applying again is undone by uninstall
+        let status = manager.cvar_status(None).await.unwrap();
+        assert_eq!(status.len(), 1);
+        assert!(status[0].managed && status[0].current.as_deref() == Some("d3d9"));
+        manager.revert_patch_cvars(None, "H").await.unwrap();
+        let status = manager.cvar_status(None).await.unwrap();
+        assert!(!status[0].managed && status[0].current.as_deref() == Some("opengl"));
+        assert!(wow.path().join("WTF/Config.wtf.cvars-backup").exists());
+        manager.apply_patch_cvars(None, "H").await.unwrap();
+        assert!(manager.cvar_status(None).await.unwrap()[0].managed);
+        assert!(matches!(manager.apply_patch_cvars(None, "X").await, Err(InstallError::PatchNotInstalled(_))));
+
+        manager.uninstall_patches(None, &["H"]).await.unwrap();
+        assert!(!wow.path().join("Interface/AddOns/Helper").exists());
+        assert!(!wow.path().join("Helper.dll").exists());
+        assert_eq!(
+            std::fs::read_to_string(wow.path().join("WTF/Config.wtf")).unwrap(),
+            "SET gxApi \"opengl\"\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shared_cvars_survive_uninstalling_one_patch() {
+        let downloads = tempdir().unwrap();
+        let wow = tempdir().unwrap();
+        test_wow::create(wow.path());
+        std::fs::create_dir_all(wow.path().join("WTF")).unwrap();
+        std::fs::write(wow.path().join("WTF/Config.wtf"), "SET farclip \"500\"\n").unwrap();
+        std::fs::write(downloads.path().join("Patch-A.mpq"), b"a").unwrap();
+        std::fs::write(downloads.path().join("Patch-B.mpq"), b"b").unwrap();
+
+        let manager = InstallManager::new(downloads.path().to_path_buf());
+        manager.set_wow_path(wow.path().to_path_buf()).unwrap();
+        let spec = InstallSpec {
+            cvars: [("farclip".to_string(), "777".to_string())].into_iter().collect(),
+            ..Default::default()
+        };
+        manager.set_catalog([("A".to_string(), spec.clone()), ("B".to_string(), spec)].into_iter().collect());
+        let farclip = || {
+            let text = std::fs::read_to_string(wow.path().join("WTF/Config.wtf")).unwrap();
+            ConfigWtf::parse(&text).get("farclip").map(str::to_string)
+        };
+
+        // Only A changed the value, but B still needs it once A is gone
+        manager.install_patches(None, &["A", "B"], Channel::new(|_| Ok(()))).await.unwrap();
+        manager.uninstall_patches(None, &["A"]).await.unwrap();
+        assert_eq!(farclip().as_deref(), Some("777"));
+        assert!(matches!(manager.verify_patch(None, "B").await.unwrap(), VerifyResult::Installed { .. }));
+
+        manager.uninstall_patches(None, &["B"]).await.unwrap();
+        assert_eq!(farclip().as_deref(), Some("500"));
+    }
+
+    #[tokio::test]
+    async fn test_addon_archive_installs_by_toc_name() {
+        use std::io::Write;
+
+        let downloads = tempdir().unwrap();
+        let wow = tempdir().unwrap();
+        test_wow::create(wow.path());
+
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, data) in [("pfUI-master/pfUI.toc", "## Interface: 11200\n"), ("pfUI-master/init.lua", "")] {
+            zip.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(data.as_bytes()).unwrap();
+        }
+        std::fs::write(downloads.path().join("Patch-P.download"), zip.finish().unwrap().into_inner()).unwrap();
+
+        let manager = InstallManager::new(downloads.path().to_path_buf());
+        manager.set_wow_path(wow.path().to_path_buf()).unwrap();
+        let spec = InstallSpec { addon: true, ..Default::default() };
+        manager.set_catalog([("P".to_string(), spec)].into_iter().collect());
+
+        manager.install_patch(None, "P", Channel::new(|_| Ok(()))).await.unwrap();
+        assert!(wow.path().join("Interface/AddOns/pfUI/init.lua").exists());
+
+        let addons = manager.list_addons(None).await.unwrap();
+        assert_eq!(addons.len(), 1);
+        assert_eq!(addons[0].name, "pfUI");
+        assert_eq!(addons[0].patch_id.as_deref(), Some("P"));
+        assert!(!addons[0].interface_mismatch);
+    }
+
+    #[tokio::test]
+    async fn test_requirement_install_update_and_uninstall() {
+        use crate::models::{Requirement, RequirementDetect, RequirementLevel};
+        use std::io::Write;
+
+        let downloads = tempdir().unwrap();
+        let wow = tempdir().unwrap();
+        test_wow::create(wow.path());
+
+        let manager = InstallManager::new(downloads.path().to_path_buf());
+        manager.set_wow_path(wow.path().to_path_buf()).unwrap();
+        manager.set_requirements(vec![Requirement {
+            id: "dxvk".to_string(),
+            name: "DXVK".to_string(),
+            description: String::new(),
+            level: RequirementLevel::Recommended,
+            source: "https://example.com/dxvk-{version}.tar.gz".to_string(),
+            versions: vec!["2.0".to_string()],
+            detect: RequirementDetect {
+                file: "d3d9.dll".to_string(),
+                min_version: None,
+                sha256: Vec::new(),
+                product: None,
+            },
+            install: vec![PatchFile { source: Some("dxvk/x32/*.dll".to_string()), destination: String::new(), rename: None }],
+        }]);
+
+        let write_zip = |files: &[&str]| {
+            let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+            for name in files {
+                zip.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+                zip.write_all(b"dll").unwrap();
+            }
+            let path = downloads.path().join("dxvk.download");
+            std::fs::write(&path, zip.finish().unwrap().into_inner()).unwrap();
+            path
+        };
+
+        let download = write_zip(&["dxvk/x32/d3d9.dll", "dxvk/x32/dxgi.dll", "dxvk/x64/d3d9.dll"]);
+        manager.install_requirement(None, "dxvk", Some("2.0".to_string()), &download, None).await.unwrap();
+        assert!(!download.exists());
+        assert!(wow.path().join("dxgi.dll").exists());
+        let status = &manager.check_requirements(None, &[]).await.unwrap()[0];
+        assert!(status.satisfied && status.managed && !status.verified);
+        assert_eq!(status.installed_version.as_deref(), Some("2.0"));
+        assert!(status.sha256.is_some() && !status.update_available);
+
+        // A newer release is offered unless the install is pinned
+        manager.set_latest_version("dxvk", "2.1".to_string());
+        assert!(manager.check_requirements(None, &[]).await.unwrap()[0].update_available);
+        manager.set_requirement_pinned(None, "dxvk", true).unwrap();
+        assert_eq!(manager.pinned_version(None, "dxvk").unwrap().as_deref(), Some("2.0"));
+        assert!(!manager.check_requirements(None, &[]).await.unwrap()[0].update_available);
+
+        // A download not matching the published checksum is refused
+        let download = write_zip(&["dxvk/x32/d3d9.dll"]);
+        let result = manager.install_requirement(None, "dxvk", Some("2.1".to_string()), &download, Some("00")).await;
+        assert!(matches!(result, Err(InstallError::ChecksumMismatch { .. })));
+        assert!(!download.exists());
+
+        // Failing before the checks still deletes the download
+        let download = write_zip(&["dxvk/x32/d3d9.dll"]);
+        let result = manager.install_requirement(None, "x", None, &download, None).await;
+        assert!(matches!(result, Err(InstallError::UnknownRequirement(_))));
+        assert!(!download.exists());
+
+        // Updating drops files the new version no longer ships and keeps the replaced one
+        let download = write_zip(&["dxvk/x32/d3d9.dll"]);
+        manager.install_requirement(None, "dxvk", Some("2.1".to_string()), &download, None).await.unwrap();
+        assert!(!wow.path().join("dxgi.dll").exists());
+        assert!(wow.path().join("d3d9.dll.dxvk-previous").exists());
+        let status = &manager.check_requirements(None, &[]).await.unwrap()[0];
+        assert!(status.pinned && !status.update_available);
+
+        // A managed dxvk.conf survives updates and goes with the uninstall
+        std::fs::write(wow.path().join("dxvk.conf"), "custom\n").unwrap();
+        let config = DxvkConfig::from_preset(crate::install::DxvkPreset::Balanced, None);
+        manager.write_dxvk_conf(None, &config).await.unwrap();
+        let download = write_zip(&["dxvk/x32/d3d9.dll"]);
+        manager.install_requirement(None, "dxvk", Some("2.1".to_string()), &download, None).await.unwrap();
+        assert!(wow.path().join("dxvk.conf.dxvk-backup").exists());
+
+        manager.uninstall_requirement(None, "dxvk").await.unwrap();
+        assert!(!wow.path().join("d3d9.dll").exists());
+        assert!(!wow.path().join("d3d9.dll.dxvk-previous").exists());
+        assert_eq!(std::fs::read_to_string(wow.path().join("dxvk.conf")).unwrap(), "custom\n");
+        assert!(matches!(
+            manager.write_dxvk_conf(None, &config).await,
+            Err(InstallError::RequirementNotInstalled(_))
+        ));
+        let status = &manager.check_requirements(None, &[]).await.unwrap()[0];
+        assert!(!status.installed && !status.managed);
+        assert!(matches!(manager.uninstall_requirement(None, "x").await, Err(InstallError::UnknownRequirement(_))));
+    }
+
+    #[tokio::test]
+    async fn test_install_reuses_existing_casing() {
+        let downloads = tempdir().unwrap();
+        let wow = tempdir().unwrap();
+        std::fs::create_dir_all(wow.path().join("data")).unwrap();
+        std::fs::write(wow.path().join("wow.exe"), b"MZ").unwrap();
+        if wow.path().join("Data").exists() {
+            return; // case-insensitive filesystem
+        }
+        std::fs::write(wow.path().join("data/patch-a.MPQ"), b"old").unwrap();
+        std::fs::write(wow.path().join("data/Patch-A.mpq"), b"older").unwrap();
+        std::fs::write(downloads.path().join("Patch-A.mpq"), b"new").unwrap();
+
+        let manager = InstallManager::new(downloads.path().to_path_buf());
+        manager.set_wow_path(wow.path().to_path_buf()).unwrap();
+        assert!(matches!(
+            manager.verify_patch(None, "A").await.unwrap(),
+            VerifyResult::CaseDuplicate { .. }
+        ));
+
+        let dest = manager.install_patch(None, "A", Channel::new(|_| Ok(()))).await.unwrap();
+        assert_eq!(dest, wow.path().join("data/Patch-A.mpq"));
+        let names: Vec<String> = std::fs::read_dir(wow.path().join("data"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["Patch-A.mpq".to_string()]);
+        assert!(matches!(
+            manager.verify_patch(None, "A").await.unwrap(),
+            VerifyResult::Installed { verified: true }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_check_install_space() {
+        let downloads = tempdir().unwrap();
+        let wow = tempdir().unwrap();
+        test_wow::create(wow.path());
+        std::fs::write(downloads.path().join("Patch-A.mpq"), vec![0u8; 100]).unwrap();
+        std::fs::write(wow.path().join("Data/Patch-A.mpq"), vec![0u8; 40]).unwrap();
+
+        let manager = InstallManager::new(downloads.path().to_path_buf());
+        manager.set_wow_path(wow.path().to_path_buf()).unwrap();
+        let catalog: HashMap<String, u64> = [("B".to_string(), 1000)].into_iter().collect();
+
+        // A replaces 40 of its 100 bytes; B is downloaded and installed
+        let checks = manager.check_install_space(None, &["A", "B"], &catalog).await.unwrap();
+        let needed: u64 = checks.iter().map(|c| c.needed).sum();
+        assert_eq!(needed, 60 + 1000 + 1000);
+        assert!(checks.iter().all(|c| c.is_sufficient()));
+
+        // Symlinks only need space for the download
+        manager.set_install_strategy(InstallStrategy::Symlink);
+        let checks = manager.check_install_space(None, &["A", "B"], &catalog).await.unwrap();
+        let needed: u64 = checks.iter().map(|c| c.needed).sum();
+        assert_eq!(needed, 1000);
+    }
+
+    #[test]
+    fn test_unknown_profile_is_an_error() {
+        let manager = InstallManager::new(PathBuf::from("/tmp/downloads"));
+        assert!(matches!(
+            manager.get_data_folder(Some("missing")),
+            Err(InstallError::ProfileNotFound(_))
+        ));
+        assert!(matches!(manager.get_data_folder(None), Err(InstallError::WowPathNotSet)));
+    }
+}
@@ -0,0 +1,189 @@
+//! Install manifest
+//!
+//! Records which patches the patcher installed into a WoW folder, so that
+//! uninstall, repair and cache cleanup know what they manage without
+//! guessing from file names in `Data/`.
+
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+
+use super::config_wtf::ConfigChange;
+use super::strategy::InstallStrategy;
+use crate::models::PatchId;
+
+/// A file a patch placed in the WoW folder
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstalledFile {
+    /// Path relative to the WoW root, `/`-separated (e.g. `Data/Patch-A.mpq`)
+    pub path: String,
+    pub size: u64,
+    /// sha256 of the content in the download store
+    #[serde(default)]
+    pub sha256: Option<String>,
+    #[serde(default)]
+    pub mode: InstallStrategy,
+}
+
+/// A single installed patch
+///
+/// `file_name`, `size`, `sha256` and `mode` describe the first installed
+/// file; `files` lists all of them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    pub patch_id: PatchId,
+    /// File name inside the Data folder
+    pub file_name: String,
+    /// Installed size in bytes
+    pub size: u64,
+    /// Variant chosen at install time, if the patch has variants
+    #[serde(default)]
+    pub variant: Option<String>,
+    /// sha256 of the installed content in the download store
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// How the file was placed into the Data folder
+    #[serde(default)]
+    pub mode: InstallStrategy,
+    /// RFC 3339 timestamp of the install
+    pub installed_at: String,
+    /// Every installed file; empty in manifests written before patches
+    /// could install more than one file
+    #[serde(default)]
+    pub files: Vec<InstalledFile>,
+    /// Config.wtf changes to revert on uninstall
+    #[serde(default)]
+    pub config: Vec<ConfigChange>,
+}
+
+impl ManifestEntry {
+    /// All installed files, including the single `Data/` file of older entries
+    pub fn installed_files(&self) -> Vec<InstalledFile> {
+        if !self.files.is_empty() {
+            return self.files.clone();
+        }
+        vec![InstalledFile {
+            path: format!("Data/{}", self.file_name),
+            size: self.size,
+            sha256: self.sha256.clone(),
+            mode: self.mode,
+        }]
+    }
+}
+
+/// All patches installed into one WoW folder
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallManifest {
+    #[serde(default)]
+    pub patches: BTreeMap<PatchId, ManifestEntry>,
+}
+
+impl InstallManifest {
+    /// Record (or replace) an installed patch
+    pub fn record(&mut self, entry: ManifestEntry) {
+        self.patches.insert(entry.patch_id.clone(), entry);
+    }
+
+    /// Forget an installed patch, returning its entry if it was recorded
+    pub fn remove(&mut self, patch_id: &str) -> Option<ManifestEntry> {
+        self.patches.remove(patch_id)
+    }
+
+    /// Check whether a patch is recorded as installed
+    pub fn contains(&self, patch_id: &str) -> bool {
+        self.patches.contains_key(patch_id)
+    }
+
+    /// Get the entry for an installed patch
+    pub fn get(&self, patch_id: &str) -> Option<&ManifestEntry> {
+        self.patches.get(patch_id)
+    }
+
+    /// Store hashes referenced by this manifest
+    pub fn referenced_hashes(&self) -> impl Iterator<Item = &str> {
+        self.patches.values().flat_map(|e| {
+            e.sha256
+                .as_deref()
+                .into_iter()
+                .chain(e.files.iter().filter_map(|f| f.sha256.as_deref()))
+        })
+    }
+
+    /// IDs of all recorded patches
+    pub fn patch_ids(&self) -> Vec<PatchId> {
+        self.patches.keys().cloned().collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patches.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str) -> ManifestEntry {
+        ManifestEntry {
+            patch_id: id.to_string(),
+            file_name: format!("Patch-{}.mpq", id),
+            size: 42,
+            variant: None,
+            sha256: None,
+            mode: InstallStrategy::Copy,
+            installed_at: "2024-01-01T00:00:00+00:00".to_string(),
+            files: Vec::new(),
+            config: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_record_and_remove() {
+        let mut manifest = InstallManifest::default();
+        assert!(manifest.is_empty());
+
+        manifest.record(entry("A"));
+        manifest.record(entry("B"));
+        assert!(manifest.contains("A"));
+        assert_eq!(manifest.patch_ids(), vec!["A".to_string(), "B".to_string()]);
+
+        assert!(manifest.remove("A").is_some());
+        assert!(!manifest.contains("A"));
+        assert!(manifest.remove("A").is_none());
+    }
+
+    #[test]
+    fn test_record_replaces_existing() {
+        let mut manifest = InstallManifest::default();
+        manifest.record(entry("A"));
+
+        let mut updated = entry("A");
+        updated.size = 100;
+        manifest.record(updated);
+
+        assert_eq!(manifest.patches.len(), 1);
+        assert_eq!(manifest.get("A").unwrap().size, 100);
+    }
+
+    #[test]
+    fn test_manifest_serde_roundtrip() {
+        let mut manifest = InstallManifest::default();
+        manifest.record(entry("A"));
+
+        let json = serde_json::to_value(&manifest).unwrap();
+        assert_eq!(json["patches"]["A"]["fileName"], "Patch-A.mpq");
+
+        let parsed: InstallManifest = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, manifest);
+    }
+
+    #[test]
+    fn test_older_entries_list_their_data_file() {
+        let files = entry("A").installed_files();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "Data/Patch-A.mpq");
+        assert_eq!(files[0].size, 42);
+    }
+}
@@ -2,23 +2,72 @@
 //!
 //! This module provides:
 //! - WoW folder detection and validation
+//! - Client build detection from the executable's PE version resource
+//! - Opt-in Large Address Aware patching of the executable, with backup
+//! - Running client detection, so files in use are never modified
+//! - Case-insensitive path resolution for Wine-hosted installs
+//! - Wine/Proton prefix discovery for Linux launchers
 //! - Settings persistence for WoW path
+//! - Named installation profiles with per-profile install manifests
+//! - Content-addressed download store shared by all profiles
 //! - MPQ file copy operations with progress events
+//! - Archive extraction for patches packaged as zip, 7z, rar or tar
+//! - Catalog-declared install targets beyond `Data/*.mpq`
+//! - Config.wtf CVar editing with per-patch status, apply, revert and backup
+//! - Addon detection and addon archive installs
+//! - Catalog-declared requirements (VanillaHelpers, DXVK) with check,
+//!   install, update and uninstall
+//! - DXVK release listing, `d3d9.dll` identification and managed `dxvk.conf`
+//! - Wine d3d9 DLL overrides in `user.reg` or Lutris configs for DXVK
+//! - Hardlink, reflink and symlink install strategies with copy fallback
 //! - Installation verification
 //! - Repair functionality
+//! - Reversible WDB cache clearing
 //! - Centralized InstallManager for coordinating all operations
 
+pub mod addons;
+pub mod casefold;
+pub mod config_wtf;
 pub mod copier;
 pub mod detector;
+pub mod dxvk;
+pub mod extract;
+pub mod laa;
 pub mod manager;
+pub mod manifest;
+pub mod pe;
+pub mod prefixes;
+pub mod process;
+pub mod profiles;
 pub mod repair;
+pub mod requirements;
 pub mod settings;
+pub mod store;
+pub mod strategy;
+pub mod targets;
 pub mod verifier;
+pub mod wdb;
+pub mod wine;
 
 // Re-exports
-pub use copier::{install_mpq, uninstall_mpq, InstallEvent, InstallError, get_mpq_filename};
-pub use detector::WowDetector;
+pub use addons::{InstalledAddon, Toc, CLIENT_INTERFACE};
+pub use config_wtf::{ConfigChange, ConfigWtf, CvarStatus};
+pub use copier::{install_mpq, install_file, uninstall_mpq, InstallEvent, InstallError, get_mpq_filename};
+pub use detector::{ClientInfo, WowDetector, VANILLA_BUILD};
+pub use dxvk::{D3d9Kind, DxvkConfig, DxvkPreset, DxvkStatus};
+pub use extract::{extract_archive, select_extracted, ArchiveKind, ExtractedFile};
+pub use laa::LaaStatus;
 pub use manager::InstallManager;
+pub use manifest::{InstallManifest, InstalledFile, ManifestEntry};
+pub use prefixes::{CandidateSource, Confidence, WowCandidate};
+pub use process::RunningClient;
+pub use profiles::{Profile, Profiles, DEFAULT_PROFILE_ID};
 pub use repair::{repair_patch, repair_all, RepairResult, patches_needing_repair};
+pub use requirements::{RequirementRecord, RequirementStatus};
 pub use settings::{Settings, SettingsError};
+pub use store::{ContentStore, GcResult, StoreStats};
+pub use strategy::InstallStrategy;
+pub use targets::InstallSpec;
 pub use verifier::{verify_patch, verify_all, VerifyResult, is_patch_installed, get_installed_patches};
+pub use wdb::{WdbPolicy, WdbState};
+pub use wine::WineOverrideStatus;
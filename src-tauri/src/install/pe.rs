@@ -0,0 +1,614 @@
+//! Minimal PE (Portable Executable) reader
+//!
+//! Reads just enough of a Windows executable to identify the client:
+//! the COFF and optional headers, the section table, and the
+//! `VS_VERSIONINFO` resource holding the file version. The 1.12 client
+//! stores its build number in the last field of the file version
+//! (`1.12.1.5875`). The `StringFileInfo` strings (`ProductName` and the
+//! like) tell DLLs such as `d3d9.dll` wrappers apart. It can also set the
+//! Large Address Aware flag and recompute the header checksum afterwards.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use serde::Serialize;
+use thiserror::Error;
+
+/// `RT_VERSION` resource type
+const RT_VERSION: u32 = 16;
+
+/// Signature of `VS_FIXEDFILEINFO`
+const FIXED_FILE_INFO_SIGNATURE: u32 = 0xFEEF_04BD;
+
+/// Index of the resource table in the optional header's data directories
+const RESOURCE_DIRECTORY: usize = 2;
+
+/// COFF characteristic letting a 32-bit process use more than 2 GB
+pub const IMAGE_FILE_LARGE_ADDRESS_AWARE: u16 = 0x0020;
+
+#[derive(Error, Debug)]
+pub enum PeError {
+    #[error("Not a Windows executable")]
+    NotPe,
+
+    #[error("Executable is truncated or malformed at offset {0:#x}")]
+    Malformed(usize),
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Result<u16, PeError> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or(PeError::Malformed(offset))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32, PeError> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or(PeError::Malformed(offset))
+}
+
+/// A section table entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub virtual_address: u32,
+    pub virtual_size: u32,
+    pub raw_offset: u32,
+    pub raw_size: u32,
+}
+
+/// Parsed PE headers, with file offsets of the fields that matter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeHeaders {
+    /// Offset of the COFF `Characteristics` field
+    pub characteristics_offset: usize,
+    pub characteristics: u16,
+    /// Offset of the optional header's `CheckSum` field
+    pub checksum_offset: usize,
+    pub checksum: u32,
+    /// Offset of the first data directory entry
+    data_directories_offset: usize,
+    data_directory_count: u32,
+    pub sections: Vec<Section>,
+}
+
+impl PeHeaders {
+    /// Parse the headers of a PE file
+    pub fn parse(data: &[u8]) -> Result<Self, PeError> {
+        if !data.starts_with(b"MZ") {
+            return Err(PeError::NotPe);
+        }
+        let pe_offset = u32_at(data, 0x3C)? as usize;
+        if data.get(pe_offset..pe_offset + 4) != Some(b"PE\0\0".as_slice()) {
+            return Err(PeError::NotPe);
+        }
+
+        let coff = pe_offset + 4;
+        let section_count = u16_at(data, coff + 2)? as usize;
+        let optional_size = u16_at(data, coff + 16)? as usize;
+        let characteristics_offset = coff + 18;
+        let characteristics = u16_at(data, characteristics_offset)?;
+
+        let optional = coff + 20;
+        // PE32 and PE32+ differ in where the data directories start
+        let (count_offset, data_directories_offset) = match u16_at(data, optional)? {
+            0x10B => (optional + 92, optional + 96),
+            0x20B => (optional + 108, optional + 112),
+            _ => return Err(PeError::Malformed(optional)),
+        };
+        let checksum_offset = optional + 64;
+        let checksum = u32_at(data, checksum_offset)?;
+        let data_directory_count = u32_at(data, count_offset)?;
+
+        let sections_offset = optional + optional_size;
+        let sections = (0..section_count)
+            .map(|i| {
+                let header = sections_offset + i * 40;
+                Ok(Section {
+                    virtual_size: u32_at(data, header + 8)?,
+                    virtual_address: u32_at(data, header + 12)?,
+                    raw_size: u32_at(data, header + 16)?,
+                    raw_offset: u32_at(data, header + 20)?,
+                })
+            })
+            .collect::<Result<Vec<_>, PeError>>()?;
+
+        Ok(Self {
+            characteristics_offset,
+            characteristics,
+            checksum_offset,
+            checksum,
+            data_directories_offset,
+            data_directory_count,
+            sections,
+        })
+    }
+
+    /// Whether a 32-bit process may use more than 2 GB of address space
+    pub fn is_large_address_aware(&self) -> bool {
+        self.characteristics & IMAGE_FILE_LARGE_ADDRESS_AWARE != 0
+    }
+
+    /// Get a data directory's (RVA, size), if present
+    fn data_directory(&self, data: &[u8], index: usize) -> Result<Option<(u32, u32)>, PeError> {
+        if index as u32 >= self.data_directory_count {
+            return Ok(None);
+        }
+        let entry = self.data_directories_offset + index * 8;
+        let rva = u32_at(data, entry)?;
+        let size = u32_at(data, entry + 4)?;
+        Ok((rva != 0 && size != 0).then_some((rva, size)))
+    }
+
+    /// Map a relative virtual address to a file offset
+    fn rva_to_offset(&self, rva: u32) -> Result<usize, PeError> {
+        let section = self
+            .sections
+            .iter()
+            .find(|s| rva >= s.virtual_address && rva < s.virtual_address.saturating_add(s.virtual_size.max(s.raw_size)))
+            .ok_or(PeError::Malformed(rva as usize))?;
+        // Computed in usize: a raw offset near 4 GiB would wrap in u32
+        (section.raw_offset as usize)
+            .checked_add((rva - section.virtual_address) as usize)
+            .ok_or(PeError::Malformed(rva as usize))
+    }
+}
+
+/// Compute the PE image checksum the way `CheckSumMappedFile` does
+///
+/// Sums the file as 16-bit words with the `CheckSum` field itself
+/// treated as zero, folds the carries and adds the file length.
+pub fn compute_checksum(data: &[u8], checksum_offset: usize) -> u32 {
+    let mut sum: u64 = 0;
+    for (i, chunk) in data.chunks(2).enumerate() {
+        let offset = i * 2;
+        if offset >= checksum_offset && offset < checksum_offset + 4 {
+            continue;
+        }
+        let word = match chunk {
+            [lo, hi] => u16::from_le_bytes([*lo, *hi]),
+            [lo] => *lo as u16,
+            _ => unreachable!(),
+        };
+        sum += word as u64;
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    sum = (sum & 0xFFFF) + (sum >> 16);
+    (sum as u32).wrapping_add(data.len() as u32)
+}
+
+/// Set the Large Address Aware flag and fix up the checksum
+///
+/// Returns `false` (leaving the data untouched) if the flag was already set.
+pub fn set_large_address_aware(data: &mut [u8]) -> Result<bool, PeError> {
+    let headers = PeHeaders::parse(data)?;
+    if headers.is_large_address_aware() {
+        return Ok(false);
+    }
+    let characteristics = headers.characteristics | IMAGE_FILE_LARGE_ADDRESS_AWARE;
+    let offset = headers.characteristics_offset;
+    data[offset..offset + 2].copy_from_slice(&characteristics.to_le_bytes());
+
+    let checksum = compute_checksum(data, headers.checksum_offset);
+    let offset = headers.checksum_offset;
+    data[offset..offset + 4].copy_from_slice(&checksum.to_le_bytes());
+    Ok(true)
+}
+
+/// A four-part file version, e.g. `1.12.1.5875`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FileVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+    pub build: u16,
+}
+
+impl fmt::Display for FileVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}.{}", self.major, self.minor, self.patch, self.build)
+    }
+}
+
+impl FileVersion {
+    /// Parse a dotted version like `1.12.1.5875`; missing parts are 0
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = [0u16; 4];
+        for (i, part) in text.trim().trim_start_matches('v').split('.').enumerate() {
+            *parts.get_mut(i)? = part.parse().ok()?;
+        }
+        Some(Self { major: parts[0], minor: parts[1], patch: parts[2], build: parts[3] })
+    }
+}
+
+impl Serialize for FileVersion {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Find the data of the first `RT_VERSION` resource
+fn version_resource<'a>(data: &'a [u8], headers: &PeHeaders) -> Result<Option<&'a [u8]>, PeError> {
+    let Some((rva, _)) = headers.data_directory(data, RESOURCE_DIRECTORY)? else {
+        return Ok(None);
+    };
+    let root = headers.rva_to_offset(rva)?;
+
+    // Type -> name -> language; the first name and language are used
+    let mut directory = root;
+    for level in 0..3 {
+        let named = u16_at(data, directory + 12)? as usize;
+        let ids = u16_at(data, directory + 14)? as usize;
+        let entries = directory + 16;
+        let entry = if level == 0 {
+            let Some(index) = (named..named + ids)
+                .find(|i| u32_at(data, entries + i * 8).is_ok_and(|id| id == RT_VERSION))
+            else {
+                return Ok(None);
+            };
+            entries + index * 8
+        } else if named + ids > 0 {
+            entries
+        } else {
+            return Ok(None);
+        };
+
+        let target = u32_at(data, entry + 4)?;
+        let offset = root + (target & 0x7FFF_FFFF) as usize;
+        if target & 0x8000_0000 != 0 {
+            directory = offset;
+            continue;
+        }
+        // A data entry: RVA and size of the resource
+        let start = headers.rva_to_offset(u32_at(data, offset)?)?;
+        let size = u32_at(data, offset + 4)? as usize;
+        return data.get(start..start + size).map(Some).ok_or(PeError::Malformed(start));
+    }
+    Ok(None)
+}
+
+/// Read the file version from an executable's `VS_VERSIONINFO`
+///
+/// Returns `None` for executables without version information.
+pub fn file_version(data: &[u8]) -> Result<Option<FileVersion>, PeError> {
+    let headers = PeHeaders::parse(data)?;
+    let Some(resource) = version_resource(data, &headers)? else {
+        return Ok(None);
+    };
+
+    // VS_FIXEDFILEINFO follows the UTF-16 "VS_VERSION_INFO" key, aligned to 4 bytes
+    if resource.len() < 52 {
+        return Ok(None);
+    }
+    let Some(fixed) = (0..=resource.len() - 52)
+        .step_by(4)
+        .find(|&i| u32_at(resource, i).is_ok_and(|v| v == FIXED_FILE_INFO_SIGNATURE))
+    else {
+        return Ok(None);
+    };
+    let ms = u32_at(resource, fixed + 8)?;
+    let ls = u32_at(resource, fixed + 12)?;
+    Ok(Some(FileVersion {
+        major: (ms >> 16) as u16,
+        minor: ms as u16,
+        patch: (ls >> 16) as u16,
+        build: ls as u16,
+    }))
+}
+
+/// Round a resource offset up to the next 4-byte boundary
+fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+/// One node of a `VS_VERSIONINFO` tree: its key, value and children
+struct VersionBlock<'a> {
+    key: String,
+    /// Raw value; UTF-16 text for string values
+    value: &'a [u8],
+    children: &'a [u8],
+}
+
+impl<'a> VersionBlock<'a> {
+    /// Parse the block at the start of `data`, returning it and the
+    /// offset of the next sibling
+    fn parse(data: &'a [u8]) -> Result<(Self, usize), PeError> {
+        let length = u16_at(data, 0)? as usize;
+        let value_length = u16_at(data, 2)? as usize;
+        let text = u16_at(data, 4)? == 1;
+        if length < 6 || length > data.len() {
+            return Err(PeError::Malformed(0));
+        }
+        let block = &data[..length];
+
+        let mut key = Vec::new();
+        let mut pos = 6;
+        loop {
+            let c = u16_at(block, pos)?;
+            pos += 2;
+            if c == 0 {
+                break;
+            }
+            key.push(c);
+        }
+
+        // String values are counted in UTF-16 units, binary ones in bytes
+        let value_start = align4(pos).min(length);
+        let value_end = (value_start + if text { value_length * 2 } else { value_length }).min(length);
+        let children_start = align4(value_end).min(length);
+        Ok((
+            Self {
+                key: String::from_utf16_lossy(&key),
+                value: &block[value_start..value_end],
+                children: &block[children_start..],
+            },
+            align4(length),
+        ))
+    }
+
+    /// Parse the child blocks
+    fn children(&self) -> Result<Vec<VersionBlock<'a>>, PeError> {
+        let mut children = Vec::new();
+        let mut rest = self.children;
+        while rest.len() >= 6 {
+            let (child, next) = VersionBlock::parse(rest)?;
+            children.push(child);
+            rest = rest.get(next..).unwrap_or_default();
+        }
+        Ok(children)
+    }
+
+    /// The value as text, up to its terminating NUL
+    fn text(&self) -> String {
+        let units: Vec<u16> = self
+            .value
+            .chunks_exact(2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .take_while(|&c| c != 0)
+            .collect();
+        String::from_utf16_lossy(&units)
+    }
+}
+
+/// Read the `StringFileInfo` strings of an executable or DLL
+///
+/// Keys are e.g. `ProductName`, `FileDescription` and `CompanyName`.
+/// When several languages are present the first one wins.
+pub fn version_strings(data: &[u8]) -> Result<BTreeMap<String, String>, PeError> {
+    let headers = PeHeaders::parse(data)?;
+    let mut strings = BTreeMap::new();
+    let Some(resource) = version_resource(data, &headers)? else {
+        return Ok(strings);
+    };
+
+    let (root, _) = VersionBlock::parse(resource)?;
+    for info in root.children()?.iter().filter(|b| b.key == "StringFileInfo") {
+        for table in info.children()? {
+            for string in table.children()? {
+                strings.entry(string.key.clone()).or_insert_with(|| string.text());
+            }
+        }
+    }
+    Ok(strings)
+}
+
+/// Get the product name a file declares, falling back to its description
+pub fn product_name(data: &[u8]) -> Option<String> {
+    let mut strings = version_strings(data).ok()?;
+    strings
+        .remove("ProductName")
+        .or_else(|| strings.remove("FileDescription"))
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// Whether a file names `needle` (ignoring case) in its version strings
+/// or, for files without them, anywhere in its bytes as ASCII
+pub fn mentions(data: &[u8], needle: &str) -> bool {
+    let needle = needle.to_lowercase();
+    let strings = version_strings(data).unwrap_or_default();
+    if !strings.is_empty() {
+        return strings.values().any(|value| value.to_lowercase().contains(&needle));
+    }
+    !needle.is_empty() && data.windows(needle.len()).any(|w| w.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+/// Build small PE32 files for tests
+#[cfg(test)]
+pub(crate) mod test_pe {
+    /// File offset of the resource section
+    const RSRC_OFFSET: usize = 0x200;
+    const RSRC_RVA: u32 = 0x1000;
+
+    fn put_u16(data: &mut [u8], offset: usize, value: u16) {
+        data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_u32(data: &mut [u8], offset: usize, value: u32) {
+        data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn pad4(data: &mut Vec<u8>) {
+        while !data.len().is_multiple_of(4) {
+            data.push(0);
+        }
+    }
+
+    /// Serialize a `VS_VERSIONINFO` block
+    fn block(key: &str, text: bool, value: &[u8], value_length: usize, children: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = vec![0u8; 6];
+        for c in key.encode_utf16().chain([0]) {
+            data.extend(c.to_le_bytes());
+        }
+        pad4(&mut data);
+        data.extend(value);
+        for child in children {
+            pad4(&mut data);
+            data.extend(child);
+        }
+        let length = data.len() as u16;
+        put_u16(&mut data, 0, length);
+        put_u16(&mut data, 2, value_length as u16);
+        put_u16(&mut data, 4, text as u16);
+        data
+    }
+
+    /// A PE32 executable with the given COFF characteristics and, if set,
+    /// a version resource for `version` (major, minor, patch, build)
+    pub fn build(characteristics: u16, version: Option<(u16, u16, u16, u16)>) -> Vec<u8> {
+        build_with_strings(characteristics, version, &[])
+    }
+
+    /// Like `build`, with `StringFileInfo` strings such as `ProductName`;
+    /// any string adds a version resource (version 0.0.0.0 if unset)
+    pub fn build_with_strings(
+        characteristics: u16,
+        version: Option<(u16, u16, u16, u16)>,
+        strings: &[(&str, &str)],
+    ) -> Vec<u8> {
+        let resource = (version.is_some() || !strings.is_empty()).then(|| {
+            let (major, minor, patch, build) = version.unwrap_or_default();
+            let mut fixed = vec![0u8; 52];
+            put_u32(&mut fixed, 0, super::FIXED_FILE_INFO_SIGNATURE);
+            put_u32(&mut fixed, 8, (major as u32) << 16 | minor as u32);
+            put_u32(&mut fixed, 12, (patch as u32) << 16 | build as u32);
+
+            let mut children = Vec::new();
+            if !strings.is_empty() {
+                let entries: Vec<Vec<u8>> = strings
+                    .iter()
+                    .map(|(key, value)| {
+                        let text: Vec<u8> = value.encode_utf16().chain([0]).flat_map(u16::to_le_bytes).collect();
+                        block(key, true, &text, value.encode_utf16().count() + 1, &[])
+                    })
+                    .collect();
+                let table = block("040904b0", true, &[], 0, &entries);
+                children.push(block("StringFileInfo", true, &[], 0, &[table]));
+            }
+            block("VS_VERSION_INFO", false, &fixed, 52, &children)
+        });
+
+        let rsrc_size = resource.as_ref().map_or(0x200, |r| (0x58 + r.len() + 0x1FF) & !0x1FF);
+        let mut data = vec![0u8; RSRC_OFFSET + rsrc_size];
+        data[..2].copy_from_slice(b"MZ");
+        put_u32(&mut data, 0x3C, 0x40);
+        data[0x40..0x44].copy_from_slice(b"PE\0\0");
+
+        let coff = 0x44;
+        put_u16(&mut data, coff, 0x14C); // i386
+        put_u16(&mut data, coff + 2, 1); // one section
+        put_u16(&mut data, coff + 16, 224); // PE32 optional header size
+        put_u16(&mut data, coff + 18, characteristics);
+
+        let optional = coff + 20;
+        put_u16(&mut data, optional, 0x10B);
+        put_u32(&mut data, optional + 92, 16);
+
+        let section = optional + 224;
+        data[section..section + 5].copy_from_slice(b".rsrc");
+        put_u32(&mut data, section + 8, rsrc_size as u32);
+        put_u32(&mut data, section + 12, RSRC_RVA);
+        put_u32(&mut data, section + 16, rsrc_size as u32);
+        put_u32(&mut data, section + 20, RSRC_OFFSET as u32);
+
+        let Some(resource) = resource else {
+            return data;
+        };
+        put_u32(&mut data, optional + 96 + 2 * 8, RSRC_RVA);
+        put_u32(&mut data, optional + 96 + 2 * 8 + 4, rsrc_size as u32);
+
+        // Type (RT_VERSION) -> name 1 -> language 0x409 -> data entry
+        let r = RSRC_OFFSET;
+        for (dir, id, next) in [(0x00, 16, 0x8000_0018), (0x18, 1, 0x8000_0030), (0x30, 0x409, 0x48)] {
+            put_u16(&mut data, r + dir + 14, 1);
+            put_u32(&mut data, r + dir + 16, id);
+            put_u32(&mut data, r + dir + 20, next);
+        }
+        put_u32(&mut data, r + 0x48, RSRC_RVA + 0x58);
+        put_u32(&mut data, r + 0x48 + 4, resource.len() as u32);
+        data[r + 0x58..r + 0x58 + resource.len()].copy_from_slice(&resource);
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_headers() {
+        let data = test_pe::build(0x0102, None);
+        let headers = PeHeaders::parse(&data).unwrap();
+        assert_eq!(headers.characteristics, 0x0102);
+        assert_eq!(headers.checksum, 0);
+        assert_eq!(headers.sections.len(), 1);
+        assert_eq!(headers.rva_to_offset(0x1010).unwrap(), 0x210);
+
+        // A section placed near 4 GiB must not wrap back into the file
+        let mut far = headers.clone();
+        far.sections[0].raw_offset = u32::MAX;
+        assert!(far.rva_to_offset(0x1010).ok().is_none_or(|offset| offset > u32::MAX as usize));
+
+        assert!(matches!(PeHeaders::parse(b"not an exe"), Err(PeError::NotPe)));
+        assert!(matches!(PeHeaders::parse(&data[..0x50]), Err(PeError::Malformed(_))));
+    }
+
+    #[test]
+    fn test_set_large_address_aware() {
+        let mut data = test_pe::build(0x0102, Some((1, 12, 1, 5875)));
+        assert!(!PeHeaders::parse(&data).unwrap().is_large_address_aware());
+
+        assert!(set_large_address_aware(&mut data).unwrap());
+        let headers = PeHeaders::parse(&data).unwrap();
+        assert!(headers.is_large_address_aware());
+        assert_eq!(headers.characteristics, 0x0122);
+        assert_ne!(headers.checksum, 0);
+        assert_eq!(headers.checksum, compute_checksum(&data, headers.checksum_offset));
+        // The version resource is untouched
+        assert_eq!(file_version(&data).unwrap().unwrap().build, 5875);
+
+        let before = data.clone();
+        assert!(!set_large_address_aware(&mut data).unwrap());
+        assert_eq!(data, before);
+    }
+
+    #[test]
+    fn test_compute_checksum() {
+        // Words 0x0201 + 0x0403 (+ odd trailing byte 0x05), plus the length
+        assert_eq!(compute_checksum(&[1, 2, 3, 4, 5], 100), 0x0201 + 0x0403 + 5 + 5);
+        // The checksum field itself is skipped
+        assert_eq!(compute_checksum(&[1, 2, 0xFF, 0xFF, 0xFF, 0xFF], 2), 0x0201 + 6);
+        // Carries fold back into the low 16 bits
+        assert_eq!(compute_checksum(&[0xFF, 0xFF, 0x02, 0x00], 100), 0x0002 + 4);
+    }
+
+    #[test]
+    fn test_file_version() {
+        let data = test_pe::build(0x0102, Some((1, 12, 1, 5875)));
+        let version = file_version(&data).unwrap().unwrap();
+        assert_eq!(version.build, 5875);
+        assert_eq!(version.to_string(), "1.12.1.5875");
+
+        assert_eq!(file_version(&test_pe::build(0x0102, None)).unwrap(), None);
+        assert_eq!(FileVersion::parse("1.12.1.5875"), Some(version));
+        assert!(FileVersion::parse("v1.12").unwrap() < version);
+        assert_eq!(FileVersion::parse("1.2.3.4.5"), None);
+    }
+
+    #[test]
+    fn test_version_strings() {
+        let data = test_pe::build_with_strings(
+            0x2102,
+            Some((2, 7, 1, 0)),
+            &[("FileDescription", "Direct3D 9 to Vulkan"), ("ProductName", "DXVK")],
+        );
+        let strings = version_strings(&data).unwrap();
+        assert_eq!(strings.get("ProductName").map(String::as_str), Some("DXVK"));
+        assert_eq!(product_name(&data).as_deref(), Some("DXVK"));
+        assert_eq!(file_version(&data).unwrap().unwrap().to_string(), "2.7.1.0");
+        assert!(mentions(&data, "vulkan"));
+        assert!(!mentions(&data, "ReShade"));
+
+        let plain = test_pe::build(0x2102, Some((1, 0, 0, 0)));
+        assert!(version_strings(&plain).unwrap().is_empty());
+        assert_eq!(product_name(&plain), None);
+    }
+}
@@ -0,0 +1,367 @@
+//! Wine/Proton prefix discovery
+//!
+//! Linux players run the Windows client inside a Wine prefix managed by
+//! a launcher. Each launcher keeps its prefixes (and sometimes the game's
+//! exe path) in its own place:
+//! - Lutris: `games/*.yml` with `game.exe` and `game.prefix`
+//! - Bottles: `bottles/<name>/bottle.yml`, the bottle folder is the prefix
+//! - Heroic: `GamesConfig/*.json` with `winePrefix`, and `~/Games/Heroic/Prefixes`
+//! - Steam/Proton: `steamapps/compatdata/<appid>/pfx`
+//! - Plain Wine: `$WINEPREFIX` and `~/.wine`
+//!
+//! Flatpak installs keep the same layout below `~/.var/app/<app id>/`.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use super::detector::WowDetector;
+
+/// How deep below `drive_c` a WoW folder is looked for
+const MAX_SCAN_DEPTH: usize = 4;
+
+/// Where a candidate WoW folder was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CandidateSource {
+    /// Current directory or the patcher's own folder
+    Local,
+    Lutris,
+    Bottles,
+    Heroic,
+    Steam,
+    Wine,
+}
+
+/// How sure the detector is that a candidate is the player's game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Confidence {
+    /// Found by scanning a prefix nothing points at
+    Low,
+    /// Found by scanning a prefix a launcher manages
+    Medium,
+    /// A launcher is configured to run this folder's exe
+    High,
+}
+
+/// A WoW folder found on this machine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WowCandidate {
+    pub path: PathBuf,
+    pub source: CandidateSource,
+    pub confidence: Confidence,
+    /// Wine prefix the folder lives in, if any
+    pub prefix: Option<PathBuf>,
+}
+
+/// Get every value of `key:` in a YAML file
+///
+/// Launcher configs only need a few scalar fields, so this reads
+/// `key: value` lines (and `- key: value` list items) at any depth
+/// rather than parsing the whole document.
+pub(super) fn yaml_values(text: &str, key: &str) -> Vec<String> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim_start();
+            let line = line.strip_prefix("- ").unwrap_or(line);
+            let (k, value) = line.split_once(':')?;
+            if k.trim() != key {
+                return None;
+            }
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value);
+            (!value.is_empty()).then(|| value.to_string())
+        })
+        .collect()
+}
+
+/// List the entries of a folder, ignoring errors
+fn read_dir_paths(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+        .unwrap_or_default()
+}
+
+/// List the files in a folder with an extension
+fn files_with_extension(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    read_dir_paths(dir)
+        .into_iter()
+        .filter(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case(extension)))
+        .collect()
+}
+
+/// Find WoW folders inside a Wine prefix's `drive_c`
+///
+/// Walks a few levels deep without following symlinks (the user folders
+/// in a prefix link back into the home folder) and skips the `windows`
+/// and `AppData` trees.
+pub fn scan_prefix(prefix: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let mut queue = VecDeque::from([(prefix.join("drive_c"), 0)]);
+
+    while let Some((dir, depth)) = queue.pop_front() {
+        if WowDetector::is_valid_wow_folder(&dir) {
+            found.push(dir);
+            continue;
+        }
+        if depth == MAX_SCAN_DEPTH {
+            continue;
+        }
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut children: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+            .map(|e| e.path())
+            .filter(|p| {
+                let name = p.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+                name != "windows" && name != "appdata"
+            })
+            .collect();
+        children.sort();
+        queue.extend(children.into_iter().map(|c| (c, depth + 1)));
+    }
+    found
+}
+
+/// Collects candidates, keeping the most confident entry per folder
+#[derive(Default)]
+struct Candidates(Vec<WowCandidate>);
+
+impl Candidates {
+    fn add(&mut self, path: PathBuf, source: CandidateSource, confidence: Confidence, prefix: Option<&Path>) {
+        let path = fs::canonicalize(&path).unwrap_or(path);
+        if let Some(existing) = self.0.iter_mut().find(|c| c.path == path) {
+            if confidence > existing.confidence {
+                existing.source = source;
+                existing.confidence = confidence;
+            }
+            return;
+        }
+        self.0.push(WowCandidate {
+            path,
+            source,
+            confidence,
+            prefix: prefix.map(Path::to_path_buf),
+        });
+    }
+
+    /// Add the folder of an exe a launcher runs, if it is a WoW folder
+    fn add_exe(&mut self, exe: &Path, source: CandidateSource, prefix: Option<&Path>) {
+        if let Some(dir) = exe.parent().filter(|d| WowDetector::is_valid_wow_folder(d)) {
+            self.add(dir.to_path_buf(), source, Confidence::High, prefix);
+        }
+    }
+
+    /// Add every WoW folder inside a prefix
+    fn add_prefix(&mut self, prefix: &Path, source: CandidateSource, confidence: Confidence) {
+        for dir in scan_prefix(prefix) {
+            self.add(dir, source, confidence, Some(prefix));
+        }
+    }
+}
+
+/// Config roots of a launcher, native and Flatpak
+fn launcher_dirs(home: &Path, native: &str, flatpak_id: &str, flatpak: &str) -> [PathBuf; 2] {
+    [home.join(native), home.join(".var/app").join(flatpak_id).join(flatpak)]
+}
+
+/// Lutris game configs (`*.yml`) of native and flatpak installs
+pub fn lutris_configs(home: &Path) -> Vec<PathBuf> {
+    let roots = [
+        launcher_dirs(home, ".config/lutris/games", "net.lutris.Lutris", "config/lutris/games"),
+        launcher_dirs(home, ".local/share/lutris/games", "net.lutris.Lutris", "data/lutris/games"),
+    ];
+    roots.iter().flatten().flat_map(|dir| files_with_extension(dir, "yml")).collect()
+}
+
+fn discover_lutris(home: &Path, candidates: &mut Candidates) {
+    for config in lutris_configs(home) {
+        let Ok(text) = fs::read_to_string(&config) else {
+            continue;
+        };
+        let prefix = yaml_values(&text, "prefix").into_iter().next().map(PathBuf::from);
+        for exe in yaml_values(&text, "exe") {
+            candidates.add_exe(Path::new(&exe), CandidateSource::Lutris, prefix.as_deref());
+        }
+        if let Some(prefix) = &prefix {
+            candidates.add_prefix(prefix, CandidateSource::Lutris, Confidence::Medium);
+        }
+    }
+}
+
+fn discover_bottles(home: &Path, candidates: &mut Candidates) {
+    let roots = launcher_dirs(home, ".local/share/bottles/bottles", "com.usebottles.bottles", "data/bottles/bottles");
+    for bottle in roots.iter().flat_map(|dir| read_dir_paths(dir)) {
+        let Ok(text) = fs::read_to_string(bottle.join("bottle.yml")) else {
+            continue;
+        };
+        // Bottles with a custom location store it as an absolute Path
+        let prefix = yaml_values(&text, "Path")
+            .into_iter()
+            .map(PathBuf::from)
+            .find(|p| p.is_absolute())
+            .unwrap_or_else(|| bottle.clone());
+        // External_Programs entries hold the exe's Unix path
+        for exe in yaml_values(&text, "path").into_iter().filter(|p| p.to_lowercase().ends_with(".exe")) {
+            candidates.add_exe(Path::new(&exe), CandidateSource::Bottles, Some(&prefix));
+        }
+        candidates.add_prefix(&prefix, CandidateSource::Bottles, Confidence::Medium);
+    }
+}
+
+fn discover_heroic(home: &Path, candidates: &mut Candidates) {
+    let roots = launcher_dirs(home, ".config/heroic/GamesConfig", "com.heroicgameslauncher.hgl", "config/heroic/GamesConfig");
+    for config in roots.iter().flat_map(|dir| files_with_extension(dir, "json")) {
+        let Some(json) = fs::read_to_string(&config)
+            .ok()
+            .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+        else {
+            continue;
+        };
+        // Keyed by the game's app name
+        for game in json.as_object().into_iter().flat_map(|o| o.values()) {
+            if let Some(prefix) = game["winePrefix"].as_str() {
+                candidates.add_prefix(Path::new(prefix), CandidateSource::Heroic, Confidence::Medium);
+            }
+        }
+    }
+    for prefix in read_dir_paths(&home.join("Games/Heroic/Prefixes")) {
+        candidates.add_prefix(&prefix, CandidateSource::Heroic, Confidence::Medium);
+    }
+}
+
+fn discover_steam(home: &Path, candidates: &mut Candidates) {
+    let roots = [
+        home.join(".steam/steam/steamapps/compatdata"),
+        home.join(".local/share/Steam/steamapps/compatdata"),
+        home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam/steamapps/compatdata"),
+    ];
+    for app in roots.iter().flat_map(|dir| read_dir_paths(dir)) {
+        candidates.add_prefix(&app.join("pfx"), CandidateSource::Steam, Confidence::Medium);
+    }
+}
+
+fn discover_wine(home: &Path, wineprefix: Option<&Path>, candidates: &mut Candidates) {
+    if let Some(prefix) = wineprefix {
+        candidates.add_prefix(prefix, CandidateSource::Wine, Confidence::Medium);
+    }
+    candidates.add_prefix(&home.join(".wine"), CandidateSource::Wine, Confidence::Low);
+}
+
+/// Find WoW folders in every known Wine prefix below a home folder
+///
+/// Sorted from most to least confident.
+pub fn discover(home: &Path, wineprefix: Option<&Path>) -> Vec<WowCandidate> {
+    let mut candidates = Candidates::default();
+    discover_lutris(home, &mut candidates);
+    discover_bottles(home, &mut candidates);
+    discover_heroic(home, &mut candidates);
+    discover_steam(home, &mut candidates);
+    discover_wine(home, wineprefix, &mut candidates);
+
+    let mut found = candidates.0;
+    found.sort_by_key(|c| std::cmp::Reverse(c.confidence));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::install::detector::test_wow;
+    use tempfile::tempdir;
+
+    fn find<'a>(found: &'a [WowCandidate], path: &Path) -> &'a WowCandidate {
+        found.iter().find(|c| c.path == path).unwrap_or_else(|| panic!("{:?} not found in {:?}", path, found))
+    }
+
+    #[test]
+    fn test_yaml_values() {
+        let text = "game:\n  exe: /games/tw/WoW.exe\n  prefix: '/games/tw'\nwine:\n  version: lutris-7\nprograms:\n  - path: \"/x/y.exe\"\n";
+        assert_eq!(yaml_values(text, "exe"), vec!["/games/tw/WoW.exe"]);
+        assert_eq!(yaml_values(text, "prefix"), vec!["/games/tw"]);
+        assert_eq!(yaml_values(text, "path"), vec!["/x/y.exe"]);
+        assert!(yaml_values(text, "missing").is_empty());
+    }
+
+    #[test]
+    fn test_scan_prefix_skips_windows_and_deep_folders() {
+        let prefix = tempdir().unwrap();
+        let drive_c = prefix.path().join("drive_c");
+        let game = test_wow::create(&drive_c.join("Program Files/TurtleWoW"));
+        test_wow::create(&drive_c.join("windows/WoW"));
+        test_wow::create(&drive_c.join("a/b/c/d/e/WoW"));
+
+        assert_eq!(scan_prefix(prefix.path()), vec![drive_c.join("Program Files/TurtleWoW")]);
+        assert_eq!(fs::canonicalize(&scan_prefix(prefix.path())[0]).unwrap(), game);
+    }
+
+    #[test]
+    fn test_discover_launchers() {
+        let home = tempdir().unwrap();
+        let home = home.path();
+
+        // Lutris points straight at the exe
+        let lutris_prefix = home.join("Games/turtle-wow");
+        let lutris_game = test_wow::create(&lutris_prefix.join("drive_c/TurtleWoW"));
+        let lutris_games = home.join(".config/lutris/games");
+        fs::create_dir_all(&lutris_games).unwrap();
+        fs::write(
+            lutris_games.join("turtle-wow-1700000000.yml"),
+            format!(
+                "game:\n  exe: {}/WoW.exe\n  prefix: {}\nsystem: {{}}\n",
+                lutris_game.display(),
+                lutris_prefix.display()
+            ),
+        )
+        .unwrap();
+
+        // A Flatpak Bottles bottle without an external program entry
+        let bottle = home.join(".var/app/com.usebottles.bottles/data/bottles/bottles/Turtle");
+        let bottles_game = test_wow::create(&bottle.join("drive_c/Games/Turtle WoW"));
+        fs::write(bottle.join("bottle.yml"), "Name: Turtle\nPath: Turtle\nExternal_Programs: {}\n").unwrap();
+
+        // Proton prefix of a non-Steam shortcut
+        let steam_game = test_wow::create(&home.join(".local/share/Steam/steamapps/compatdata/3012345678/pfx/drive_c/WoW"));
+
+        // Heroic sideloaded game
+        let heroic_prefix = home.join("Games/Heroic/Prefixes/default/Turtle");
+        let heroic_config = home.join(".config/heroic/GamesConfig");
+        fs::create_dir_all(&heroic_config).unwrap();
+        fs::write(
+            heroic_config.join("abc.json"),
+            format!("{{\"abc\": {{\"winePrefix\": \"{}\"}}}}", heroic_prefix.display()),
+        )
+        .unwrap();
+        let heroic_game = test_wow::create(&heroic_prefix.join("drive_c/TurtleWoW"));
+
+        let wine_game = test_wow::create(&home.join(".wine/drive_c/Program Files (x86)/TurtleWoW"));
+
+        let found = discover(home, None);
+        assert_eq!(found.len(), 5);
+        assert_eq!(found[0].path, lutris_game);
+        assert_eq!(found[0].confidence, Confidence::High);
+        assert_eq!(found[0].source, CandidateSource::Lutris);
+        assert_eq!(found[0].prefix.as_deref(), Some(lutris_prefix.as_path()));
+
+        assert_eq!(find(&found, &bottles_game).source, CandidateSource::Bottles);
+        assert_eq!(find(&found, &steam_game).source, CandidateSource::Steam);
+        assert_eq!(find(&found, &heroic_game).source, CandidateSource::Heroic);
+        let wine = find(&found, &wine_game);
+        assert_eq!((wine.source, wine.confidence), (CandidateSource::Wine, Confidence::Low));
+    }
+
+    #[test]
+    fn test_discover_empty_home() {
+        let home = tempdir().unwrap();
+        assert!(discover(home.path(), None).is_empty());
+    }
+}
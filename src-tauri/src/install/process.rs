@@ -0,0 +1,210 @@
+//! Running client detection
+//!
+//! Files must not be replaced while the client has them open: on Windows
+//! its MPQs are locked and a copy fails halfway, under Wine it reads a
+//! half-written archive. A process counts as a running client when its
+//! executable is one of `CLIENT_EXES` and lives in the managed WoW folder.
+//! - Linux: `/proc/<pid>/cmdline`. Wine shows the Windows path of the
+//!   executable (`C:\Games\WoW\WoW.exe`, `Z:\home\...`), which is mapped
+//!   back through the prefix; relative paths resolve against the cwd.
+//! - Windows: the Toolhelp process list with each image's full path.
+
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+
+use super::copier::InstallError;
+use super::detector::CLIENT_EXES;
+
+/// A client process running from a WoW folder
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunningClient {
+    pub pid: u32,
+    /// Executable name, e.g. `WoW.exe`
+    pub exe: String,
+}
+
+/// Get the client executable named by a path, in either slash style
+fn client_exe(path: &str) -> Option<&'static str> {
+    let name = path.rsplit(['/', '\\']).next()?;
+    CLIENT_EXES.iter().copied().find(|exe| exe.eq_ignore_ascii_case(name))
+}
+
+/// Get the Unix folder of an executable path seen in a process
+///
+/// Windows drive paths go through the prefix: `Z:` is the Unix root,
+/// `C:` is `drive_c` and other drives are the prefix's `dosdevices` links.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn unix_dir(exe: &str, cwd: Option<&Path>, prefix: Option<&Path>) -> Option<PathBuf> {
+    let normalized = exe.replace('\\', "/");
+    let dir = normalized.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+    let bytes = dir.as_bytes();
+    if bytes.len() >= 2 && bytes[1] == b':' && bytes[0].is_ascii_alphabetic() {
+        let drive = bytes[0].to_ascii_lowercase() as char;
+        let rest = dir[2..].trim_start_matches('/');
+        return match drive {
+            'z' => Some(Path::new("/").join(rest)),
+            'c' => prefix.map(|p| p.join("drive_c").join(rest)),
+            _ => prefix.map(|p| p.join("dosdevices").join(format!("{}:", drive)).join(rest)),
+        };
+    }
+    if normalized.starts_with('/') {
+        return Some(PathBuf::from(if dir.is_empty() { "/" } else { dir }));
+    }
+    cwd.map(|cwd| cwd.join(dir))
+}
+
+/// Compare folders the way Wine does, ignoring case
+fn same_dir(a: &Path, b: &Path) -> bool {
+    let normalize = |p: &Path| {
+        p.canonicalize()
+            .unwrap_or_else(|_| p.to_path_buf())
+            .to_string_lossy()
+            .trim_end_matches(['/', '\\'])
+            .to_lowercase()
+    };
+    normalize(a) == normalize(b)
+}
+
+/// Find the client a command line runs from `wow_path`, if any
+///
+/// Only the first few arguments are looked at, which covers both the
+/// client itself and `wine WoW.exe` style launchers.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn client_in_cmdline(args: &[&str], cwd: Option<&Path>, wow_path: &Path, prefix: Option<&Path>) -> Option<&'static str> {
+    args.iter().take(3).find_map(|arg| {
+        let exe = client_exe(arg)?;
+        unix_dir(arg, cwd, prefix).filter(|dir| same_dir(dir, wow_path)).map(|_| exe)
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn list_clients(wow_path: &Path) -> Vec<RunningClient> {
+    let prefix = super::wine::find_prefix(wow_path);
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+            let cmdline = std::fs::read(entry.path().join("cmdline")).ok()?;
+            let cmdline = String::from_utf8_lossy(&cmdline);
+            let args: Vec<&str> = cmdline.split('\0').filter(|a| !a.is_empty()).collect();
+            let cwd = std::fs::read_link(entry.path().join("cwd")).ok();
+            let exe = client_in_cmdline(&args, cwd.as_deref(), wow_path, prefix.as_deref())?;
+            Some(RunningClient { pid, exe: exe.to_string() })
+        })
+        .collect()
+}
+
+#[cfg(windows)]
+fn list_clients(wow_path: &Path) -> Vec<RunningClient> {
+    use std::os::windows::ffi::OsStringExt;
+    use windows_sys::Win32::Foundation::{CloseHandle, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS,
+    };
+    use windows_sys::Win32::System::Threading::{OpenProcess, QueryFullProcessImageNameW, PROCESS_QUERY_LIMITED_INFORMATION};
+
+    let wide_to_string = |wide: &[u16]| {
+        let len = wide.iter().position(|&c| c == 0).unwrap_or(wide.len());
+        std::ffi::OsString::from_wide(&wide[..len]).to_string_lossy().into_owned()
+    };
+
+    // SAFETY: the snapshot handle is checked and closed; entry.dwSize is
+    // set before use as Process32FirstW requires
+    let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) };
+    if snapshot == INVALID_HANDLE_VALUE {
+        return Vec::new();
+    }
+    let mut clients = Vec::new();
+    let mut entry: PROCESSENTRY32W = unsafe { std::mem::zeroed() };
+    entry.dwSize = std::mem::size_of::<PROCESSENTRY32W>() as u32;
+    let mut more = unsafe { Process32FirstW(snapshot, &mut entry) } != 0;
+    while more {
+        if let Some(exe) = client_exe(&wide_to_string(&entry.szExeFile)) {
+            // SAFETY: the process handle is checked and closed; size holds
+            // the buffer length in characters
+            let image = unsafe {
+                let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, entry.th32ProcessID);
+                let mut buffer = [0u16; 1024];
+                let mut size = buffer.len() as u32;
+                let ok = !process.is_null() && QueryFullProcessImageNameW(process, 0, buffer.as_mut_ptr(), &mut size) != 0;
+                if !process.is_null() {
+                    CloseHandle(process);
+                }
+                ok.then(|| wide_to_string(&buffer[..size as usize]))
+            };
+            let in_folder = image
+                .as_deref()
+                .and_then(|image| Path::new(image).parent())
+                .is_some_and(|dir| same_dir(dir, wow_path));
+            if in_folder {
+                clients.push(RunningClient { pid: entry.th32ProcessID, exe: exe.to_string() });
+            }
+        }
+        more = unsafe { Process32NextW(snapshot, &mut entry) } != 0;
+    }
+    unsafe { CloseHandle(snapshot) };
+    clients
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn list_clients(_wow_path: &Path) -> Vec<RunningClient> {
+    Vec::new()
+}
+
+/// List the clients running from a WoW folder
+pub fn running_clients(wow_path: &Path) -> Vec<RunningClient> {
+    list_clients(wow_path)
+}
+
+/// Fail with `GameRunning` while a client runs from a WoW folder
+pub fn ensure_not_running(wow_path: &Path) -> Result<(), InstallError> {
+    match running_clients(wow_path).into_iter().next() {
+        Some(client) => Err(InstallError::GameRunning { exe: client.exe, pid: client.pid }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_client_in_cmdline() {
+        let root = tempdir().unwrap();
+        let prefix = root.path().join("pfx");
+        let wow = prefix.join("drive_c/Games/TurtleWoW");
+        std::fs::create_dir_all(&wow).unwrap();
+        let other = root.path().join("other");
+        std::fs::create_dir_all(&other).unwrap();
+        let p = Some(prefix.as_path());
+
+        // Wine shows the Windows path, in any casing
+        assert_eq!(client_in_cmdline(&["C:\\Games\\TurtleWoW\\WoW.exe"], None, &wow, p), Some("WoW.exe"));
+        assert_eq!(client_in_cmdline(&["c:\\games\\turtlewow\\TURTLE-WOW.EXE"], None, &wow, p), Some("turtle-wow.exe"));
+        let z_path = format!("Z:{}", wow.join("WoW.exe").display()).replace('/', "\\");
+        assert_eq!(client_in_cmdline(&[&z_path], None, &wow, None), Some("WoW.exe"));
+
+        // Launchers, Unix paths and relative paths against the cwd
+        let unix = wow.join("WoW.exe").display().to_string();
+        assert_eq!(client_in_cmdline(&["/usr/bin/wine", &unix], None, &wow, p), Some("WoW.exe"));
+        assert_eq!(client_in_cmdline(&["WoW.exe"], Some(&wow), &wow, p), Some("WoW.exe"));
+
+        // Other folders and other programs do not count
+        assert_eq!(client_in_cmdline(&["WoW.exe"], Some(&other), &wow, p), None);
+        assert_eq!(client_in_cmdline(&["C:\\Other\\WoW.exe"], None, &wow, p), None);
+        assert_eq!(client_in_cmdline(&["C:\\Games\\TurtleWoW\\Launcher.exe"], None, &wow, p), None);
+        assert_eq!(client_in_cmdline(&["C:\\Games\\TurtleWoW\\WoW.exe"], None, &wow, None), None);
+    }
+
+    #[test]
+    fn test_no_client_running_in_fresh_folder() {
+        let wow = tempdir().unwrap();
+        assert!(running_clients(wow.path()).is_empty());
+        assert!(ensure_not_running(wow.path()).is_ok());
+    }
+}
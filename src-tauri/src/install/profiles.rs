@@ -0,0 +1,240 @@
+//! Named installation profiles
+//!
+//! A profile pairs a WoW folder with its own module selection, chosen
+//! variants and install manifest, so a main client and a test copy can be
+//! managed side by side. Exactly one profile is active at a time; commands
+//! that take an optional profile ID fall back to it.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+use super::copier::InstallError;
+use super::manifest::InstallManifest;
+use super::requirements::RequirementRecord;
+use crate::models::PatchId;
+
+/// ID of the profile created for existing single-path settings
+pub const DEFAULT_PROFILE_ID: &str = "default";
+
+/// A single WoW installation and its patcher state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    pub wow_path: Option<PathBuf>,
+    #[serde(default)]
+    pub selected_modules: Vec<PatchId>,
+    /// Chosen variant per patch ID
+    #[serde(default)]
+    pub variants: HashMap<PatchId, String>,
+    #[serde(default)]
+    pub manifest: InstallManifest,
+    /// Requirements installed through the patcher, by requirement ID
+    #[serde(default)]
+    pub requirements: BTreeMap<String, RequirementRecord>,
+}
+
+impl Profile {
+    pub fn new(id: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            wow_path: None,
+            selected_modules: Vec::new(),
+            variants: HashMap::new(),
+            manifest: InstallManifest::default(),
+            requirements: BTreeMap::new(),
+        }
+    }
+}
+
+/// All profiles plus the active profile ID
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profiles {
+    pub active: String,
+    pub profiles: Vec<Profile>,
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_PROFILE_ID.to_string(),
+            profiles: vec![Profile::new(DEFAULT_PROFILE_ID, "Default")],
+        }
+    }
+}
+
+impl Profiles {
+    /// Get a profile by ID
+    pub fn get(&self, id: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.id == id)
+    }
+
+    /// Get a mutable profile by ID
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut Profile> {
+        self.profiles.iter_mut().find(|p| p.id == id)
+    }
+
+    /// Get the active profile
+    pub fn active(&self) -> &Profile {
+        self.get(&self.active)
+            .or_else(|| self.profiles.first())
+            .expect("profiles list is never empty")
+    }
+
+    /// Resolve an optional profile ID, falling back to the active profile
+    pub fn resolve(&self, id: Option<&str>) -> Result<&Profile, InstallError> {
+        match id {
+            Some(id) => self.get(id).ok_or_else(|| InstallError::ProfileNotFound(id.to_string())),
+            None => Ok(self.active()),
+        }
+    }
+
+    /// Resolve an optional profile ID for mutation
+    pub fn resolve_mut(&mut self, id: Option<&str>) -> Result<&mut Profile, InstallError> {
+        let id = id.map(str::to_string).unwrap_or_else(|| self.active().id.clone());
+        self.get_mut(&id).ok_or(InstallError::ProfileNotFound(id))
+    }
+
+    /// Create a new profile with an ID derived from its name
+    pub fn create(&mut self, name: &str, wow_path: Option<PathBuf>) -> Result<&Profile, InstallError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(InstallError::InvalidProfileName);
+        }
+
+        let base = slugify(name);
+        let mut id = base.clone();
+        let mut suffix = 2;
+        while self.get(&id).is_some() {
+            id = format!("{}-{}", base, suffix);
+            suffix += 1;
+        }
+
+        let mut profile = Profile::new(id, name);
+        profile.wow_path = wow_path;
+        self.profiles.push(profile);
+        Ok(self.profiles.last().unwrap())
+    }
+
+    /// Make a profile the active one
+    pub fn switch(&mut self, id: &str) -> Result<&Profile, InstallError> {
+        if self.get(id).is_none() {
+            return Err(InstallError::ProfileNotFound(id.to_string()));
+        }
+        self.active = id.to_string();
+        Ok(self.active())
+    }
+
+    /// Delete a profile
+    ///
+    /// The last remaining profile cannot be deleted. Deleting the active
+    /// profile makes the first remaining one active.
+    pub fn delete(&mut self, id: &str) -> Result<Profile, InstallError> {
+        let index = self
+            .profiles
+            .iter()
+            .position(|p| p.id == id)
+            .ok_or_else(|| InstallError::ProfileNotFound(id.to_string()))?;
+
+        if self.profiles.len() == 1 {
+            return Err(InstallError::LastProfile);
+        }
+
+        let removed = self.profiles.remove(index);
+        if self.active == removed.id {
+            self.active = self.profiles[0].id.clone();
+        }
+        Ok(removed)
+    }
+}
+
+/// Turn a display name into a profile ID ("Test Client" -> "test-client")
+fn slugify(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_matches('-').to_string();
+    if slug.is_empty() {
+        "profile".to_string()
+    } else {
+        slug
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_has_active_profile() {
+        let profiles = Profiles::default();
+        assert_eq!(profiles.active().id, DEFAULT_PROFILE_ID);
+        assert!(profiles.resolve(None).is_ok());
+    }
+
+    #[test]
+    fn test_create_generates_unique_ids() {
+        let mut profiles = Profiles::default();
+        let first = profiles.create("PTR Client", None).unwrap().id.clone();
+        let second = profiles.create("PTR client", None).unwrap().id.clone();
+
+        assert_eq!(first, "ptr-client");
+        assert_eq!(second, "ptr-client-2");
+    }
+
+    #[test]
+    fn test_create_rejects_empty_name() {
+        let mut profiles = Profiles::default();
+        assert!(matches!(profiles.create("  ", None), Err(InstallError::InvalidProfileName)));
+    }
+
+    #[test]
+    fn test_switch_and_resolve() {
+        let mut profiles = Profiles::default();
+        let id = profiles.create("Test", Some(PathBuf::from("/games/test"))).unwrap().id.clone();
+
+        profiles.switch(&id).unwrap();
+        assert_eq!(profiles.resolve(None).unwrap().id, id);
+        assert_eq!(profiles.resolve(Some(DEFAULT_PROFILE_ID)).unwrap().id, DEFAULT_PROFILE_ID);
+        assert!(matches!(
+            profiles.resolve(Some("missing")),
+            Err(InstallError::ProfileNotFound(_))
+        ));
+        assert!(profiles.switch("missing").is_err());
+    }
+
+    #[test]
+    fn test_delete_active_falls_back() {
+        let mut profiles = Profiles::default();
+        let id = profiles.create("Test", None).unwrap().id.clone();
+        profiles.switch(&id).unwrap();
+
+        profiles.delete(&id).unwrap();
+        assert_eq!(profiles.active().id, DEFAULT_PROFILE_ID);
+    }
+
+    #[test]
+    fn test_delete_last_profile_fails() {
+        let mut profiles = Profiles::default();
+        assert!(matches!(
+            profiles.delete(DEFAULT_PROFILE_ID),
+            Err(InstallError::LastProfile)
+        ));
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Main Client"), "main-client");
+        assert_eq!(slugify("  Turtle / PTR!  "), "turtle-ptr");
+        assert_eq!(slugify("???"), "profile");
+    }
+}
@@ -1,20 +1,21 @@
 //! Repair functionality for patch installations
 //!
-//! Repair works by re-copying from the downloads folder.
+//! Repair works by re-installing from the downloads folder (or download store).
 //! If the download is missing, the repair fails (requires re-download first).
 
 use std::path::Path;
 use tauri::ipc::Channel;
 
-use super::copier::{install_mpq, get_mpq_filename, InstallEvent};
+use super::copier::{install_file, get_mpq_filename, InstallEvent};
+use super::strategy::InstallStrategy;
 use super::verifier::VerifyResult;
 
 /// Result of a repair operation
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase", tag = "status")]
 pub enum RepairResult {
-    /// Repair succeeded
-    Repaired { patch_id: String },
+    /// Repair succeeded, placing the file with `mode`
+    Repaired { patch_id: String, mode: InstallStrategy },
 
     /// Download missing, need to re-download first
     DownloadMissing { patch_id: String },
@@ -23,17 +24,17 @@ pub enum RepairResult {
     Failed { patch_id: String, error: String },
 }
 
-/// Repair a single patch by re-copying from downloads
+/// Repair a single patch by re-installing from `download_path`
 ///
 /// Returns RepairResult indicating success or what went wrong.
 pub async fn repair_patch(
     patch_id: &str,
     data_folder: &Path,
-    downloads_folder: &Path,
+    download_path: &Path,
+    strategy: InstallStrategy,
     on_event: Channel<InstallEvent>,
 ) -> RepairResult {
-    let mpq_name = get_mpq_filename(patch_id);
-    let download_path = downloads_folder.join(&mpq_name);
+    let dest_path = data_folder.join(get_mpq_filename(patch_id));
 
     // Check if download exists
     if !download_path.exists() {
@@ -42,10 +43,11 @@ pub async fn repair_patch(
         };
     }
 
-    // Re-copy (install_mpq overwrites existing)
-    match install_mpq(&download_path, data_folder, patch_id.to_string(), on_event).await {
-        Ok(_) => RepairResult::Repaired {
+    // Re-install (install_file replaces the existing file or link)
+    match install_file(download_path, &dest_path, strategy, patch_id.to_string(), on_event).await {
+        Ok(mode) => RepairResult::Repaired {
             patch_id: patch_id.to_string(),
+            mode,
         },
         Err(e) => RepairResult::Failed {
             patch_id: patch_id.to_string(),
@@ -56,7 +58,8 @@ pub async fn repair_patch(
 
 /// Repair multiple patches
 ///
-/// Attempts to repair each patch in sequence.
+/// Attempts to repair each patch in sequence from `Patch-X.mpq` files
+/// in `downloads_folder`.
 pub async fn repair_all(
     patch_ids: &[&str],
     data_folder: &Path,
@@ -66,7 +69,8 @@ pub async fn repair_all(
     let mut results = Vec::with_capacity(patch_ids.len());
 
     for id in patch_ids {
-        let result = repair_patch(id, data_folder, downloads_folder, on_event.clone()).await;
+        let download_path = downloads_folder.join(get_mpq_filename(id));
+        let result = repair_patch(id, data_folder, &download_path, InstallStrategy::Copy, on_event.clone()).await;
         results.push(result);
     }
 
@@ -80,6 +84,9 @@ pub fn patches_needing_repair(verify_results: &[(String, VerifyResult)]) -> Vec<
         .filter_map(|(id, result)| match result {
             VerifyResult::NotInstalled => Some(id.clone()),
             VerifyResult::SizeMismatch { .. } => Some(id.clone()),
+            VerifyResult::BrokenLink { .. } => Some(id.clone()),
+            VerifyResult::ConfigMismatch { .. } => Some(id.clone()),
+            VerifyResult::CaseDuplicate { .. } => Some(id.clone()),
             VerifyResult::Error { .. } => Some(id.clone()),
             VerifyResult::Installed { verified: false } => None, // Can't verify, assume OK
             VerifyResult::Installed { verified: true } => None,
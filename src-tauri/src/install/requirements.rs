@@ -0,0 +1,534 @@
+//! Catalog-driven requirements
+//!
+//! Checks, installs and removes the prerequisites declared in the
+//! `requirements` section of patches.json (VanillaHelpers, DXVK and the
+//! like). A requirement is detected by one file in the WoW folder,
+//! optionally with a minimum PE file version or a list of accepted
+//! hashes. Its download is mapped with the same `files` rules patches
+//! use. Installs made through the patcher are recorded per profile so
+//! they can be updated and uninstalled; a file put there by hand is
+//! detected but left alone. A file an install would overwrite (say a
+//! `d3d9.dll` from another wrapper) is kept as `<file>.<id>-backup` and
+//! put back on uninstall; an update keeps the version it replaces as
+//! `<file>.<id>-previous`.
+//!
+//! Records carry the installed version (the release tag for GitHub
+//! sources) and the download's sha256, checked against the checksum the
+//! release published when it has one. A pinned record keeps its version
+//! when reinstalled and never reports an update.
+
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use super::copier::InstallError;
+use super::extract::{extract_archive, ArchiveKind};
+use super::manager::target_dest;
+use super::manifest::InstalledFile;
+use super::pe::{file_version, mentions, product_name, FileVersion};
+use super::store::hash_file;
+use super::strategy::{remove_existing, InstallStrategy};
+use super::targets::{map_extracted, map_single};
+use crate::download::providers::github::{GitHubRef, Release};
+use crate::download::providers::glob_match;
+use crate::models::{PatchFile, PatchId, Requirement, RequirementLevel};
+
+/// Placeholder in a requirement's source replaced by the chosen version
+const VERSION_PLACEHOLDER: &str = "{version}";
+
+/// A requirement installed through the patcher
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequirementRecord {
+    /// Version chosen at install time, for versioned sources
+    #[serde(default)]
+    pub version: Option<String>,
+    pub files: Vec<InstalledFile>,
+    /// Files the patcher wrote besides the download (e.g. `dxvk.conf`);
+    /// kept across updates
+    #[serde(default)]
+    pub generated: Vec<String>,
+    /// Targets whose earlier file was moved aside, to restore on uninstall
+    #[serde(default)]
+    pub backups: Vec<String>,
+    /// sha256 of the download
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// The download matched a checksum published by its source
+    #[serde(default)]
+    pub verified: bool,
+    /// Reinstall `version` and skip update checks
+    #[serde(default)]
+    pub pinned: bool,
+    /// Version kept as `<file>.<id>-previous` by the last update
+    #[serde(default)]
+    pub previous_version: Option<String>,
+    /// RFC 3339 timestamp of the install
+    pub installed_at: String,
+}
+
+impl RequirementRecord {
+    /// Every path the install owns
+    pub fn managed_paths(&self) -> Vec<String> {
+        self.files.iter().map(|f| f.path.clone()).chain(self.generated.iter().cloned()).collect()
+    }
+}
+
+/// State of one requirement in a WoW folder
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequirementStatus {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub level: RequirementLevel,
+    /// Versions to choose from, the default first
+    pub versions: Vec<String>,
+    /// The detected file exists
+    pub installed: bool,
+    /// PE file version of the detected file, if it has one
+    pub file_version: Option<FileVersion>,
+    /// Product name from the detected file's version strings
+    pub product: Option<String>,
+    /// Version chosen when the patcher installed it
+    pub installed_version: Option<String>,
+    /// sha256 of the download it was installed from
+    pub sha256: Option<String>,
+    /// The download matched its published checksum
+    pub verified: bool,
+    /// Pinned to `installed_version`
+    pub pinned: bool,
+    /// Newest version known, from the catalog or the source's releases
+    pub latest_version: Option<String>,
+    /// Installed through the patcher, not pinned, and older than `latest_version`
+    pub update_available: bool,
+    /// Installed and passing the version and hash checks
+    pub satisfied: bool,
+    /// Installed through the patcher, so it can be uninstalled
+    pub managed: bool,
+    /// Files the install moved aside, put back on uninstall
+    pub backups: Vec<String>,
+    /// Patches in the profile that declare the requirement
+    pub required_by: Vec<PatchId>,
+}
+
+impl RequirementStatus {
+    /// Fill in the newest known version and whether to offer it
+    ///
+    /// Only a newer version is offered; versions that do not parse as
+    /// dotted numbers never count as updates.
+    pub fn with_latest(mut self, latest: Option<String>) -> Self {
+        let parse = |version: Option<&String>| version.and_then(|v| FileVersion::parse(v));
+        let newer = match (parse(latest.as_ref()), parse(self.installed_version.as_ref())) {
+            (Some(latest), Some(installed)) => latest > installed,
+            _ => false,
+        };
+        self.update_available = self.managed && !self.pinned && newer;
+        self.latest_version = latest;
+        self
+    }
+}
+
+fn valid_version(version: &&str) -> bool {
+    !version.is_empty() && !version.contains(['/', '\\', '?', '#'])
+}
+
+/// Get the download link for a requirement, with the version filled in
+///
+/// Versioned sources default to the first listed version.
+pub fn source_url(requirement: &Requirement, version: Option<&str>) -> Result<(String, Option<String>), InstallError> {
+    if !requirement.source.contains(VERSION_PLACEHOLDER) {
+        return Ok((requirement.source.clone(), None));
+    }
+    let version = version
+        .or(requirement.versions.first().map(String::as_str))
+        .filter(valid_version)
+        .ok_or_else(|| InstallError::InvalidTarget(format!("{}: no valid version", requirement.id)))?;
+    Ok((requirement.source.replace(VERSION_PLACEHOLDER, version), Some(version.to_string())))
+}
+
+/// GitHub release a requirement downloads from, with the tag set to
+/// `version` when one is given; `None` for other sources
+pub fn github_ref(requirement: &Requirement, version: Option<&str>) -> Result<Option<GitHubRef>, InstallError> {
+    let Some(mut reference) = GitHubRef::parse(&requirement.source) else {
+        return Ok(None);
+    };
+    if let Some(version) = version {
+        if !valid_version(&version) {
+            return Err(InstallError::InvalidTarget(format!("{}: invalid version {}", requirement.id, version)));
+        }
+        reference.tag = Some(version.to_string());
+    }
+    Ok(Some(reference))
+}
+
+/// Tags of the stable releases that ship a reference's asset, newest first
+pub fn release_tags(releases: &[Release], reference: &GitHubRef) -> Vec<String> {
+    releases
+        .iter()
+        .filter(|r| !r.draft && !r.prerelease)
+        .filter(|r| r.assets.iter().any(|a| glob_match(&reference.asset, &a.name)))
+        .map(|r| r.tag_name.clone())
+        .collect()
+}
+
+/// Hash a download and check it against the checksum its source published
+pub async fn verify_download(download: &Path, name: &str, expected: Option<&str>) -> Result<String, InstallError> {
+    let sha256 = hash_file(download).await?;
+    match expected {
+        Some(expected) if !expected.eq_ignore_ascii_case(&sha256) => Err(InstallError::ChecksumMismatch {
+            name: name.to_string(),
+            expected: expected.to_lowercase(),
+            actual: sha256,
+        }),
+        _ => Ok(sha256),
+    }
+}
+
+/// Files a requirement installs; by default its download is the detected file
+fn install_files(requirement: &Requirement) -> Vec<PatchFile> {
+    if !requirement.install.is_empty() {
+        return requirement.install.clone();
+    }
+    let (destination, name) = match requirement.detect.file.rsplit_once('/') {
+        Some((folder, name)) => (folder.to_string(), name.to_string()),
+        None => (String::new(), requirement.detect.file.clone()),
+    };
+    vec![PatchFile { source: None, destination, rename: Some(name) }]
+}
+
+/// Check a requirement in a WoW folder
+pub async fn check_requirement(
+    wow_path: &Path,
+    requirement: &Requirement,
+    record: Option<&RequirementRecord>,
+    required_by: Vec<PatchId>,
+) -> Result<RequirementStatus, InstallError> {
+    let detected = target_dest(wow_path, &requirement.detect.file)?;
+    let data = match fs::read(&detected).await {
+        Ok(data) => Some(data),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    let file_version = data.as_deref().and_then(|d| file_version(d).ok().flatten());
+    let mut satisfied = data.is_some();
+    if let Some(min) = requirement.detect.min_version.as_deref().and_then(FileVersion::parse) {
+        satisfied &= file_version.is_some_and(|v| v >= min);
+    }
+    if let (Some(data), Some(product)) = (&data, &requirement.detect.product) {
+        satisfied &= mentions(data, product);
+    }
+    if satisfied && !requirement.detect.sha256.is_empty() {
+        let sha256 = hash_file(&detected).await?;
+        satisfied = requirement.detect.sha256.iter().any(|h| h.eq_ignore_ascii_case(&sha256));
+    }
+
+    Ok(RequirementStatus {
+        id: requirement.id.clone(),
+        name: requirement.name.clone(),
+        description: requirement.description.clone(),
+        level: requirement.level,
+        versions: requirement.versions.clone(),
+        installed: data.is_some(),
+        file_version,
+        product: data.as_deref().and_then(product_name),
+        installed_version: record.and_then(|r| r.version.clone()),
+        sha256: record.and_then(|r| r.sha256.clone()),
+        verified: record.is_some_and(|r| r.verified),
+        pinned: record.is_some_and(|r| r.pinned),
+        latest_version: None,
+        update_available: false,
+        satisfied,
+        managed: record.is_some(),
+        backups: record.map(|r| r.backups.clone()).unwrap_or_default(),
+        required_by,
+    })
+}
+
+/// Path a file is moved to before a requirement replaces it
+pub fn backup_path(dest: &Path, id: &str) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}-backup", id));
+    dest.with_file_name(name)
+}
+
+/// Path an update keeps the version it replaces at
+pub fn previous_path(dest: &Path, id: &str) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}-previous", id));
+    dest.with_file_name(name)
+}
+
+/// Path a file is moved to when `backup_path` is already taken
+///
+/// Numbered from 2, e.g. `d3d9.dll.dxvk-backup-2`.
+fn extra_backup_path(dest: &Path, id: &str) -> PathBuf {
+    let name = backup_path(dest, id).file_name().unwrap_or_default().to_os_string();
+    (2..)
+        .map(|n| {
+            let mut name = name.clone();
+            name.push(format!("-{}", n));
+            dest.with_file_name(name)
+        })
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+/// Move a file the patcher does not manage out of a requirement's way
+///
+/// An existing backup is older and so closer to the original; it stays
+/// the one restored on uninstall, and a file that appeared since goes to
+/// `extra_backup_path` rather than being overwritten. Returns whether a
+/// backup now exists.
+pub async fn back_up(wow_path: &Path, target: &str, id: &str) -> Result<bool, InstallError> {
+    let dest = target_dest(wow_path, target)?;
+    let backup = backup_path(&dest, id);
+    if dest.exists() {
+        let to = if backup.exists() { extra_backup_path(&dest, id) } else { backup.clone() };
+        fs::rename(&dest, &to).await?;
+        log::info!("[Requirements] Backed up {} to {:?} before installing {}", target, to, id);
+    }
+    Ok(backup.exists())
+}
+
+/// Put back the files a requirement moved aside
+pub async fn restore_backups(wow_path: &Path, targets: &[String], id: &str) -> Result<(), InstallError> {
+    for target in targets {
+        let dest = target_dest(wow_path, target)?;
+        let backup = backup_path(&dest, id);
+        if backup.exists() {
+            remove_existing(&dest).await?;
+            fs::rename(&backup, &dest).await?;
+            log::info!("[Requirements] Restored {} from backup", target);
+        }
+    }
+    Ok(())
+}
+
+/// Place a requirement's downloaded files into a WoW folder
+///
+/// Archives are unpacked into `staging` and mapped with the requirement's
+/// install rules. Files are copied, since they are small and not kept in
+/// the download store. Existing files outside `managed` are backed up
+/// first and managed ones kept as the previous version; returns the
+/// installed files and the targets backed up.
+pub async fn place_requirement(
+    wow_path: &Path,
+    requirement: &Requirement,
+    download: &Path,
+    staging: &Path,
+    managed: &[String],
+) -> Result<(Vec<InstalledFile>, Vec<String>), InstallError> {
+    let declared = install_files(requirement);
+    let mapped: Vec<(PathBuf, String)> = if ArchiveKind::detect(download).await?.is_some() {
+        let files = extract_archive(download, staging).await?;
+        map_extracted(&files, &declared)?
+    } else {
+        vec![(download.to_path_buf(), map_single(&declared)?)]
+    };
+
+    let mut installed = Vec::with_capacity(mapped.len());
+    let mut backups = Vec::new();
+    for (source, target) in mapped {
+        let dest = target_dest(wow_path, &target)?;
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        if !managed.iter().any(|m| m.eq_ignore_ascii_case(&target)) {
+            if back_up(wow_path, &target, &requirement.id).await? {
+                backups.push(target.clone());
+            }
+        } else if dest.exists() {
+            let previous = previous_path(&dest, &requirement.id);
+            remove_existing(&previous).await?;
+            fs::rename(&dest, &previous).await?;
+        }
+        remove_existing(&dest).await?;
+        let size = fs::copy(&source, &dest).await?;
+        log::info!("[Requirements] Installed {} for {}", target, requirement.id);
+        installed.push(InstalledFile {
+            path: target,
+            size,
+            sha256: Some(hash_file(&dest).await?),
+            mode: InstallStrategy::Copy,
+        });
+    }
+    Ok((installed, backups))
+}
+
+/// Remove installed requirement files from a WoW folder
+pub async fn remove_requirement_files(wow_path: &Path, files: &[String]) -> Result<(), InstallError> {
+    for file in files {
+        remove_existing(&target_dest(wow_path, file)?).await?;
+        log::info!("[Requirements] Removed {}", file);
+    }
+    Ok(())
+}
+
+/// Remove the previous versions an update kept of requirement files
+pub async fn remove_previous(wow_path: &Path, files: &[String], id: &str) -> Result<(), InstallError> {
+    for file in files {
+        remove_existing(&previous_path(&target_dest(wow_path, file)?, id)).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::install::pe::test_pe;
+    use crate::models::RequirementDetect;
+    use tempfile::tempdir;
+
+    fn requirement(file: &str, min_version: Option<&str>) -> Requirement {
+        Requirement {
+            id: "helper".to_string(),
+            name: "Helper".to_string(),
+            description: String::new(),
+            level: RequirementLevel::Required,
+            source: "https://example.com/v{version}/helper.dll".to_string(),
+            versions: vec!["2.0".to_string(), "1.0".to_string()],
+            detect: RequirementDetect {
+                file: file.to_string(),
+                min_version: min_version.map(str::to_string),
+                sha256: Vec::new(),
+                product: None,
+            },
+            install: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_source_url() {
+        let req = requirement("Helper.dll", None);
+        assert_eq!(
+            source_url(&req, None).unwrap(),
+            ("https://example.com/v2.0/helper.dll".to_string(), Some("2.0".to_string()))
+        );
+        assert_eq!(source_url(&req, Some("1.0")).unwrap().0, "https://example.com/v1.0/helper.dll");
+        assert!(source_url(&req, Some("../x")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_github_pin_and_checksum() {
+        let mut req = requirement("Helper.dll", None);
+        assert!(github_ref(&req, None).unwrap().is_none());
+        req.source = "github:owner/helper@latest/*.dll".to_string();
+        assert_eq!(github_ref(&req, None).unwrap().unwrap().tag, None);
+        assert_eq!(github_ref(&req, Some("v1.1")).unwrap().unwrap().tag.as_deref(), Some("v1.1"));
+        assert!(github_ref(&req, Some("v1/../x")).is_err());
+
+        let downloads = tempdir().unwrap();
+        let download = downloads.path().join("helper.download");
+        std::fs::write(&download, b"hello").unwrap();
+        let hello = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        assert_eq!(verify_download(&download, "Helper", None).await.unwrap(), hello);
+        assert_eq!(verify_download(&download, "Helper", Some(&hello.to_uppercase())).await.unwrap(), hello);
+        assert!(matches!(
+            verify_download(&download, "Helper", Some(&"0".repeat(64))).await,
+            Err(InstallError::ChecksumMismatch { .. })
+        ));
+
+        let wow = tempdir().unwrap();
+        std::fs::write(wow.path().join("Helper.dll"), b"hello").unwrap();
+        let mut record = RequirementRecord {
+            version: Some("v1.1".to_string()),
+            files: Vec::new(),
+            generated: Vec::new(),
+            backups: Vec::new(),
+            sha256: Some(hello.to_string()),
+            verified: true,
+            pinned: false,
+            previous_version: None,
+            installed_at: String::new(),
+        };
+        let status = check_requirement(wow.path(), &req, Some(&record), Vec::new()).await.unwrap();
+        assert!(status.verified);
+        assert!(status.clone().with_latest(Some("v1.2".to_string())).update_available);
+        assert!(!status.clone().with_latest(Some("v1.1".to_string())).update_available);
+        // Older or unparseable releases are not offered as updates
+        assert!(!status.clone().with_latest(Some("1.0.9".to_string())).update_available);
+        assert!(!status.clone().with_latest(Some("nightly".to_string())).update_available);
+        assert!(status.clone().with_latest(Some("1.1.1".to_string())).update_available);
+        record.pinned = true;
+        let status = check_requirement(wow.path(), &req, Some(&record), Vec::new()).await.unwrap();
+        assert!(!status.with_latest(Some("v1.2".to_string())).update_available);
+    }
+
+    #[tokio::test]
+    async fn test_check_place_and_remove() {
+        let wow = tempdir().unwrap();
+        let downloads = tempdir().unwrap();
+        let req = requirement("Helper.dll", Some("1.2"));
+
+        let status = check_requirement(wow.path(), &req, None, vec!["A".to_string()]).await.unwrap();
+        assert!(!status.installed && !status.satisfied && !status.managed);
+        assert_eq!(status.required_by, vec!["A".to_string()]);
+
+        // An old version is detected but does not satisfy the requirement
+        let download = downloads.path().join("helper.download");
+        std::fs::write(&download, test_pe::build(0x0102, Some((1, 1, 0, 0)))).unwrap();
+        let files = place_requirement(wow.path(), &req, &download, &downloads.path().join("staging"), &[]).await.unwrap().0;
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "Helper.dll");
+        let status = check_requirement(wow.path(), &req, None, Vec::new()).await.unwrap();
+        assert!(status.installed && !status.satisfied);
+        assert_eq!(status.file_version.unwrap().to_string(), "1.1.0.0");
+
+        std::fs::write(&download, test_pe::build(0x0102, Some((1, 2, 0, 0)))).unwrap();
+        let managed = vec!["Helper.dll".to_string()];
+        let (_, backups) = place_requirement(wow.path(), &req, &download, &downloads.path().join("staging"), &managed).await.unwrap();
+        assert!(backups.is_empty());
+        assert!(wow.path().join("Helper.dll.helper-previous").exists());
+        assert!(check_requirement(wow.path(), &req, None, Vec::new()).await.unwrap().satisfied);
+
+        remove_requirement_files(wow.path(), &["Helper.dll".to_string()]).await.unwrap();
+        assert!(!wow.path().join("Helper.dll").exists());
+    }
+
+    #[tokio::test]
+    async fn test_hash_rule() {
+        let wow = tempdir().unwrap();
+        let mut req = requirement("Helper.dll", None);
+        std::fs::write(wow.path().join("Helper.dll"), b"hello").unwrap();
+        req.detect.sha256 = vec!["2CF24DBA5FB0A30E26E83B2AC5B9E29E1B161E5C1FA7425E73043362938B9824".to_string()];
+        assert!(check_requirement(wow.path(), &req, None, Vec::new()).await.unwrap().satisfied);
+
+        req.detect.sha256 = vec!["00".to_string()];
+        assert!(!check_requirement(wow.path(), &req, None, Vec::new()).await.unwrap().satisfied);
+    }
+
+    #[tokio::test]
+    async fn test_other_product_is_backed_up_and_restored() {
+        let wow = tempdir().unwrap();
+        let downloads = tempdir().unwrap();
+        let mut req = requirement("d3d9.dll", None);
+        req.id = "dxvk".to_string();
+        req.detect.product = Some("DXVK".to_string());
+
+        let reshade = test_pe::build_with_strings(0x2102, None, &[("ProductName", "ReShade")]);
+        std::fs::write(wow.path().join("d3d9.dll"), &reshade).unwrap();
+        let status = check_requirement(wow.path(), &req, None, Vec::new()).await.unwrap();
+        assert!(status.installed && !status.satisfied);
+        assert_eq!(status.product.as_deref(), Some("ReShade"));
+
+        let download = downloads.path().join("dxvk.download");
+        std::fs::write(&download, test_pe::build_with_strings(0x2102, None, &[("ProductName", "DXVK")])).unwrap();
+        let (_, backups) = place_requirement(wow.path(), &req, &download, &downloads.path().join("staging"), &[]).await.unwrap();
+        assert_eq!(backups, vec!["d3d9.dll".to_string()]);
+        assert_eq!(std::fs::read(wow.path().join("d3d9.dll.dxvk-backup")).unwrap(), reshade);
+        assert!(check_requirement(wow.path(), &req, None, Vec::new()).await.unwrap().satisfied);
+
+        // A file put there by hand since is kept next to the first backup
+        let other = test_pe::build_with_strings(0x2102, None, &[("ProductName", "Other")]);
+        std::fs::write(wow.path().join("d3d9.dll"), &other).unwrap();
+        place_requirement(wow.path(), &req, &download, &downloads.path().join("staging"), &[]).await.unwrap();
+        assert_eq!(std::fs::read(wow.path().join("d3d9.dll.dxvk-backup")).unwrap(), reshade);
+        assert_eq!(std::fs::read(wow.path().join("d3d9.dll.dxvk-backup-2")).unwrap(), other);
+
+        restore_backups(wow.path(), &backups, "dxvk").await.unwrap();
+        assert_eq!(std::fs::read(wow.path().join("d3d9.dll")).unwrap(), reshade);
+        assert!(!wow.path().join("d3d9.dll.dxvk-backup").exists());
+    }
+}
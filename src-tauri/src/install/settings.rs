@@ -5,9 +5,16 @@ use tauri::AppHandle;
 use tauri_plugin_store::StoreExt;
 use thiserror::Error;
 
+use super::profiles::Profiles;
+use super::strategy::InstallStrategy;
+use super::wdb::WdbPolicy;
+
 const SETTINGS_FILE: &str = "settings.json";
 const KEY_WOW_PATH: &str = "wow_path";
 const KEY_SELECTED_MODULES: &str = "selected_modules";
+const KEY_WDB_POLICY: &str = "wdb_policy";
+const KEY_PROFILES: &str = "profiles";
+const KEY_INSTALL_STRATEGY: &str = "install_strategy";
 
 #[derive(Debug, Error)]
 pub enum SettingsError {
@@ -82,4 +89,87 @@ impl<'a> Settings<'a> {
             .map_err(|e| SettingsError::SaveError(e.to_string()))?;
         Ok(())
     }
+
+    /// Get the saved WDB cache policy (defaults to clear-and-block)
+    pub fn get_wdb_policy(&self) -> WdbPolicy {
+        self.app
+            .store(SETTINGS_FILE)
+            .ok()
+            .and_then(|store| store.get(KEY_WDB_POLICY))
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the WDB cache policy
+    pub fn set_wdb_policy(&self, policy: WdbPolicy) -> Result<(), SettingsError> {
+        let store = self
+            .app
+            .store(SETTINGS_FILE)
+            .map_err(|e| SettingsError::StoreError(e.to_string()))?;
+        store.set(KEY_WDB_POLICY, json!(policy));
+        store
+            .save()
+            .map_err(|e| SettingsError::SaveError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Get the saved install strategy (defaults to copy)
+    pub fn get_install_strategy(&self) -> InstallStrategy {
+        self.app
+            .store(SETTINGS_FILE)
+            .ok()
+            .and_then(|store| store.get(KEY_INSTALL_STRATEGY))
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the install strategy
+    pub fn set_install_strategy(&self, strategy: InstallStrategy) -> Result<(), SettingsError> {
+        let store = self
+            .app
+            .store(SETTINGS_FILE)
+            .map_err(|e| SettingsError::StoreError(e.to_string()))?;
+        store.set(KEY_INSTALL_STRATEGY, json!(strategy));
+        store
+            .save()
+            .map_err(|e| SettingsError::SaveError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Get the saved installation profiles
+    ///
+    /// Settings from before profiles existed are migrated into the
+    /// default profile (single `wow_path` and `selected_modules`).
+    pub fn get_profiles(&self) -> Profiles {
+        let saved = self
+            .app
+            .store(SETTINGS_FILE)
+            .ok()
+            .and_then(|store| store.get(KEY_PROFILES))
+            .and_then(|v| serde_json::from_value::<Profiles>(v).ok())
+            .filter(|p| !p.profiles.is_empty());
+
+        if let Some(profiles) = saved {
+            return profiles;
+        }
+
+        let mut profiles = Profiles::default();
+        let default = &mut profiles.profiles[0];
+        default.wow_path = self.get_wow_path().map(Into::into);
+        default.selected_modules = self.get_selected_modules();
+        profiles
+    }
+
+    /// Save all installation profiles
+    pub fn set_profiles(&self, profiles: &Profiles) -> Result<(), SettingsError> {
+        let store = self
+            .app
+            .store(SETTINGS_FILE)
+            .map_err(|e| SettingsError::StoreError(e.to_string()))?;
+        store.set(KEY_PROFILES, json!(profiles));
+        store
+            .save()
+            .map_err(|e| SettingsError::SaveError(e.to_string()))?;
+        Ok(())
+    }
 }
@@ -0,0 +1,405 @@
+//! Content-addressed download store
+//!
+//! Downloads are kept once per unique content, keyed by their sha256, in
+//! `store/objects/<first two hex chars>/<sha256>`. A name index maps the
+//! download name (e.g. `Patch-A.mpq`) to the latest content for it, so
+//! variants and newer versions no longer overwrite each other and every
+//! profile can install from the same copy.
+//!
+//! Objects stay alive while they are the current target of a name or are
+//! referenced by an install manifest; everything else is removed by
+//! `collect_garbage`.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use tokio::io::AsyncReadExt;
+use tokio::sync::Mutex;
+
+use super::copier::InstallError;
+
+/// Name of the store folder inside the downloads folder
+const STORE_DIR: &str = "store";
+
+/// Name of the index file inside the store folder
+const INDEX_FILE: &str = "index.json";
+
+/// Buffer size for hashing (64KB)
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Persistent name index of the store
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoreIndex {
+    /// Download name -> sha256 of its current content
+    #[serde(default)]
+    names: BTreeMap<String, String>,
+}
+
+/// A stored object
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoreEntry {
+    pub sha256: String,
+    pub size: u64,
+    pub path: PathBuf,
+}
+
+/// Size summary of the store
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoreStats {
+    pub total_bytes: u64,
+    pub objects: usize,
+    pub names: usize,
+}
+
+/// Result of a garbage collection run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcResult {
+    pub removed: Vec<String>,
+    pub freed_bytes: u64,
+}
+
+/// Content-addressed store shared by all profiles
+pub struct ContentStore {
+    root: PathBuf,
+    index: Mutex<Option<StoreIndex>>,
+}
+
+impl ContentStore {
+    /// Create a store inside the given downloads folder
+    ///
+    /// Living next to the downloads keeps imports a cheap rename.
+    pub fn new(downloads_path: &Path) -> Self {
+        Self {
+            root: downloads_path.join(STORE_DIR),
+            index: Mutex::new(None),
+        }
+    }
+
+    /// Path of the object for a given hash
+    pub fn object_path(&self, sha256: &str) -> PathBuf {
+        let prefix = sha256.get(..2).unwrap_or("00");
+        self.root.join("objects").join(prefix).join(sha256)
+    }
+
+    /// Load the index on first use
+    async fn load_index(&self) -> Result<tokio::sync::MutexGuard<'_, Option<StoreIndex>>, InstallError> {
+        let mut guard = self.index.lock().await;
+        if guard.is_none() {
+            let path = self.root.join(INDEX_FILE);
+            let index = match fs::read(&path).await {
+                Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                    log::warn!("[Store] Ignoring unreadable index {:?}: {}", path, e);
+                    StoreIndex::default()
+                }),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => StoreIndex::default(),
+                Err(e) => return Err(e.into()),
+            };
+            *guard = Some(index);
+        }
+        Ok(guard)
+    }
+
+    /// Write the index back to disk
+    async fn save_index(&self, index: &StoreIndex) -> Result<(), InstallError> {
+        fs::create_dir_all(&self.root).await?;
+        let json = serde_json::to_vec_pretty(index)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let tmp_path = self.root.join(format!("{}.tmp", INDEX_FILE));
+        fs::write(&tmp_path, json).await?;
+        fs::rename(&tmp_path, self.root.join(INDEX_FILE)).await?;
+        Ok(())
+    }
+
+    /// Move a downloaded file into the store under the given name
+    ///
+    /// The file is hashed, moved to its object path (or dropped if the same
+    /// content is already stored) and the name is pointed at it.
+    pub async fn import(&self, source: &Path, name: &str) -> Result<StoreEntry, InstallError> {
+        let sha256 = hash_file(source).await?;
+        let object_path = self.object_path(&sha256);
+
+        if object_path.exists() {
+            fs::remove_file(source).await?;
+        } else {
+            if let Some(parent) = object_path.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            if fs::rename(source, &object_path).await.is_err() {
+                // Different filesystem: copy, then remove the original
+                fs::copy(source, &object_path).await?;
+                fs::remove_file(source).await?;
+            }
+        }
+
+        let size = fs::metadata(&object_path).await?.len();
+        log::info!("[Store] Imported {} as {} ({} bytes)", name, sha256, size);
+
+        let mut guard = self.load_index().await?;
+        let index = guard.as_mut().unwrap();
+        index.names.insert(name.to_string(), sha256.clone());
+        self.save_index(index).await?;
+
+        Ok(StoreEntry { sha256, size, path: object_path })
+    }
+
+    /// Look up the current object for a name
+    pub async fn get(&self, name: &str) -> Result<Option<StoreEntry>, InstallError> {
+        let sha256 = {
+            let guard = self.load_index().await?;
+            match guard.as_ref().unwrap().names.get(name) {
+                Some(sha) => sha.clone(),
+                None => return Ok(None),
+            }
+        };
+        self.get_by_hash(&sha256).await
+    }
+
+    /// Look up the current objects of every name starting with `prefix`
+    ///
+    /// Names whose object has gone missing are skipped.
+    pub async fn get_prefixed(&self, prefix: &str) -> Result<Vec<(String, StoreEntry)>, InstallError> {
+        let names: Vec<(String, String)> = {
+            let guard = self.load_index().await?;
+            guard
+                .as_ref()
+                .unwrap()
+                .names
+                .range(prefix.to_string()..)
+                .take_while(|(name, _)| name.starts_with(prefix))
+                .map(|(name, sha)| (name.clone(), sha.clone()))
+                .collect()
+        };
+
+        let mut entries = Vec::with_capacity(names.len());
+        for (name, sha256) in names {
+            if let Some(entry) = self.get_by_hash(&sha256).await? {
+                entries.push((name, entry));
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Drop every name starting with `prefix`
+    ///
+    /// The objects stay until garbage collection, so installs that still
+    /// reference them keep working.
+    pub async fn forget_prefixed(&self, prefix: &str) -> Result<(), InstallError> {
+        let mut guard = self.load_index().await?;
+        let index = guard.as_mut().unwrap();
+        let before = index.names.len();
+        index.names.retain(|name, _| !name.starts_with(prefix));
+        if index.names.len() != before {
+            self.save_index(index).await?;
+        }
+        Ok(())
+    }
+
+    /// Look up an object by its hash
+    pub async fn get_by_hash(&self, sha256: &str) -> Result<Option<StoreEntry>, InstallError> {
+        let path = self.object_path(sha256);
+        match fs::metadata(&path).await {
+            Ok(meta) => Ok(Some(StoreEntry {
+                sha256: sha256.to_string(),
+                size: meta.len(),
+                path,
+            })),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// List the hashes of all stored objects
+    async fn list_objects(&self) -> Result<Vec<(String, u64)>, InstallError> {
+        let mut objects = Vec::new();
+        let objects_dir = self.root.join("objects");
+        if !objects_dir.exists() {
+            return Ok(objects);
+        }
+
+        let mut prefixes = fs::read_dir(&objects_dir).await?;
+        while let Some(prefix) = prefixes.next_entry().await? {
+            if !prefix.file_type().await?.is_dir() {
+                continue;
+            }
+            let mut entries = fs::read_dir(prefix.path()).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let meta = entry.metadata().await?;
+                if meta.is_file() {
+                    objects.push((entry.file_name().to_string_lossy().to_string(), meta.len()));
+                }
+            }
+        }
+        Ok(objects)
+    }
+
+    /// Summarize the store's disk usage
+    pub async fn stats(&self) -> Result<StoreStats, InstallError> {
+        let objects = self.list_objects().await?;
+        let names = self.load_index().await?.as_ref().unwrap().names.len();
+        Ok(StoreStats {
+            total_bytes: objects.iter().map(|(_, size)| size).sum(),
+            objects: objects.len(),
+            names,
+        })
+    }
+
+    /// Remove objects that no name points to and no manifest references
+    ///
+    /// Names whose object has gone missing are dropped from the index.
+    pub async fn collect_garbage(&self, referenced: &HashSet<String>) -> Result<GcResult, InstallError> {
+        let mut guard = self.load_index().await?;
+        let index = guard.as_mut().unwrap();
+
+        let mut live: HashSet<String> = referenced.clone();
+        live.extend(index.names.values().cloned());
+
+        let mut result = GcResult::default();
+        for (sha256, size) in self.list_objects().await? {
+            if !live.contains(&sha256) {
+                log::info!("[Store] Removing unreferenced object {}", sha256);
+                fs::remove_file(self.object_path(&sha256)).await?;
+                result.freed_bytes += size;
+                result.removed.push(sha256);
+            }
+        }
+
+        let before = index.names.len();
+        index.names.retain(|_, sha| self.object_path(sha).exists());
+        if index.names.len() != before || !result.removed.is_empty() {
+            self.save_index(index).await?;
+        }
+
+        Ok(result)
+    }
+}
+
+/// Compute the sha256 of a file as lowercase hex
+pub async fn hash_file(path: &Path) -> Result<String, InstallError> {
+    let mut file = fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+
+    loop {
+        let bytes_read = file.read(&mut buffer).await?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    #[tokio::test]
+    async fn test_hash_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("hello.txt");
+        fs::write(&path, b"hello").await.unwrap();
+
+        assert_eq!(hash_file(&path).await.unwrap(), HELLO_SHA256);
+    }
+
+    #[tokio::test]
+    async fn test_import_and_get() {
+        let dir = tempdir().unwrap();
+        let store = ContentStore::new(dir.path());
+        let download = dir.path().join("Patch-A.mpq");
+        fs::write(&download, b"hello").await.unwrap();
+
+        let entry = store.import(&download, "Patch-A.mpq").await.unwrap();
+        assert_eq!(entry.sha256, HELLO_SHA256);
+        assert_eq!(entry.size, 5);
+        assert!(!download.exists());
+        assert!(entry.path.exists());
+
+        assert_eq!(store.get("Patch-A.mpq").await.unwrap(), Some(entry));
+        assert_eq!(store.get("Patch-B.mpq").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_identical_content_is_stored_once() {
+        let dir = tempdir().unwrap();
+        let store = ContentStore::new(dir.path());
+
+        for name in ["Patch-A.mpq", "Patch-A-variant.mpq"] {
+            let download = dir.path().join(name);
+            fs::write(&download, b"same bytes").await.unwrap();
+            store.import(&download, name).await.unwrap();
+        }
+
+        let stats = store.stats().await.unwrap();
+        assert_eq!(stats.objects, 1);
+        assert_eq!(stats.names, 2);
+        assert_eq!(stats.total_bytes, 10);
+    }
+
+    #[tokio::test]
+    async fn test_index_persists_across_instances() {
+        let dir = tempdir().unwrap();
+        let download = dir.path().join("Patch-A.mpq");
+        fs::write(&download, b"hello").await.unwrap();
+        ContentStore::new(dir.path()).import(&download, "Patch-A.mpq").await.unwrap();
+
+        let reopened = ContentStore::new(dir.path());
+        assert!(reopened.get("Patch-A.mpq").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_prefixed_names() {
+        let dir = tempdir().unwrap();
+        let store = ContentStore::new(dir.path());
+        for (name, data) in [("Z/d3d9.dll", "a"), ("Z/Data/Patch-Z.mpq", "b"), ("Patch-Z.mpq", "c")] {
+            let download = dir.path().join("download");
+            fs::write(&download, data).await.unwrap();
+            store.import(&download, name).await.unwrap();
+        }
+
+        let names: Vec<String> = store.get_prefixed("Z/").await.unwrap().into_iter().map(|(n, _)| n).collect();
+        assert_eq!(names, vec!["Z/Data/Patch-Z.mpq".to_string(), "Z/d3d9.dll".to_string()]);
+
+        store.forget_prefixed("Z/").await.unwrap();
+        assert!(store.get_prefixed("Z/").await.unwrap().is_empty());
+        assert!(store.get("Patch-Z.mpq").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_gc_keeps_named_and_referenced_objects() {
+        let dir = tempdir().unwrap();
+        let store = ContentStore::new(dir.path());
+        let download = dir.path().join("Patch-A.mpq");
+
+        fs::write(&download, b"version 1").await.unwrap();
+        let v1 = store.import(&download, "Patch-A.mpq").await.unwrap();
+        fs::write(&download, b"version 2").await.unwrap();
+        let v2 = store.import(&download, "Patch-A.mpq").await.unwrap();
+        fs::write(&download, b"version 3").await.unwrap();
+        let v3 = store.import(&download, "Patch-A.mpq").await.unwrap();
+
+        // v1 is still installed somewhere, v2 is superseded, v3 is current
+        let referenced: HashSet<String> = [v1.sha256.clone()].into_iter().collect();
+        let result = store.collect_garbage(&referenced).await.unwrap();
+
+        assert_eq!(result.removed, vec![v2.sha256.clone()]);
+        assert_eq!(result.freed_bytes, 9);
+        assert!(v1.path.exists());
+        assert!(!v2.path.exists());
+        assert!(v3.path.exists());
+    }
+}
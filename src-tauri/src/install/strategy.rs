@@ -0,0 +1,175 @@
+//! Install strategies for placing files into the Data folder
+//!
+//! Copying every patch duplicates gigabytes between the download store and
+//! `Data/`. Hardlinks, reflinks (copy-on-write clones via `FICLONE`) and
+//! symlinks avoid that. Whenever the chosen strategy is not possible
+//! (different filesystems, unsupported filesystem or platform, missing
+//! privileges) installation falls back to a plain copy.
+
+use std::io;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+/// How installed files are placed into the Data folder
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InstallStrategy {
+    /// Full chunked copy (always works)
+    #[default]
+    Copy,
+    /// Hardlink to the stored download (same filesystem only)
+    Hardlink,
+    /// Copy-on-write clone (Btrfs, XFS and similar; Linux only)
+    Reflink,
+    /// Symbolic link to the stored download
+    Symlink,
+}
+
+/// Remove whatever currently sits at `dest` without following links
+///
+/// Writing through an existing hardlink or symlink would modify the
+/// stored download itself, so the old entry is always unlinked first.
+pub async fn remove_existing(dest: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(dest).await {
+        Ok(_) => fs::remove_file(dest).await,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Place `dest` as a link or clone of `source`
+///
+/// `dest` must not exist. Returns an error when the strategy is not
+/// possible here; the caller is expected to fall back to copying.
+/// `InstallStrategy::Copy` is handled by the caller and always fails here.
+pub async fn link_file(source: &Path, dest: &Path, strategy: InstallStrategy) -> io::Result<()> {
+    let source = source.to_path_buf();
+    let dest = dest.to_path_buf();
+
+    tokio::task::spawn_blocking(move || match strategy {
+        InstallStrategy::Copy => Err(io::Error::new(io::ErrorKind::Unsupported, "copy is not a link")),
+        InstallStrategy::Hardlink => std::fs::hard_link(&source, &dest),
+        InstallStrategy::Reflink => reflink(&source, &dest),
+        InstallStrategy::Symlink => symlink(&source, &dest),
+    })
+    .await
+    .map_err(|e| io::Error::other(e.to_string()))?
+}
+
+/// Clone a file with the `FICLONE` ioctl
+#[cfg(target_os = "linux")]
+fn reflink(source: &Path, dest: &Path) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let src = std::fs::File::open(source)?;
+    let dst = std::fs::File::create(dest)?;
+
+    // SAFETY: both descriptors are open for the duration of the call
+    let ret = unsafe { libc::ioctl(dst.as_raw_fd(), libc::FICLONE, src.as_raw_fd()) };
+    if ret == -1 {
+        let err = io::Error::last_os_error();
+        drop(dst);
+        let _ = std::fs::remove_file(dest);
+        return Err(err);
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn reflink(_source: &Path, _dest: &Path) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "reflink is only supported on Linux"))
+}
+
+#[cfg(unix)]
+fn symlink(source: &Path, dest: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(source, dest)
+}
+
+/// Requires Developer Mode or admin rights on Windows
+#[cfg(windows)]
+fn symlink(source: &Path, dest: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(source, dest)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn symlink(_source: &Path, _dest: &Path) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "symlinks are not supported"))
+}
+
+/// Check whether `path` is a symlink whose target no longer exists
+pub async fn is_broken_symlink(path: &Path) -> bool {
+    match fs::symlink_metadata(path).await {
+        Ok(meta) => meta.file_type().is_symlink() && fs::metadata(path).await.is_err(),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_hardlink_shares_inode() {
+        use std::os::unix::fs::MetadataExt;
+
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("object");
+        let dest = dir.path().join("Patch-A.mpq");
+        fs::write(&source, b"content").await.unwrap();
+
+        link_file(&source, &dest, InstallStrategy::Hardlink).await.unwrap();
+
+        let src_meta = fs::metadata(&source).await.unwrap();
+        let dst_meta = fs::metadata(&dest).await.unwrap();
+        assert_eq!(src_meta.ino(), dst_meta.ino());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlink_and_broken_detection() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("object");
+        let dest = dir.path().join("Patch-A.mpq");
+        fs::write(&source, b"content").await.unwrap();
+
+        link_file(&source, &dest, InstallStrategy::Symlink).await.unwrap();
+        assert_eq!(fs::read(&dest).await.unwrap(), b"content");
+        assert!(!is_broken_symlink(&dest).await);
+
+        fs::remove_file(&source).await.unwrap();
+        assert!(is_broken_symlink(&dest).await);
+    }
+
+    #[tokio::test]
+    async fn test_reflink_leaves_no_partial_file_on_failure() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("object");
+        let dest = dir.path().join("Patch-A.mpq");
+        fs::write(&source, b"content").await.unwrap();
+
+        // Either the filesystem supports clones or nothing is left behind
+        match link_file(&source, &dest, InstallStrategy::Reflink).await {
+            Ok(()) => assert_eq!(fs::read(&dest).await.unwrap(), b"content"),
+            Err(_) => assert!(!dest.exists()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remove_existing_does_not_touch_link_target() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("object");
+        let dest = dir.path().join("Patch-A.mpq");
+        fs::write(&source, b"content").await.unwrap();
+        link_file(&source, &dest, InstallStrategy::Hardlink).await.unwrap();
+
+        remove_existing(&dest).await.unwrap();
+        assert!(!dest.exists());
+        assert_eq!(fs::read(&source).await.unwrap(), b"content");
+
+        // Removing a missing file is not an error
+        remove_existing(&dest).await.unwrap();
+    }
+}
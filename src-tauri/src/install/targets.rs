@@ -0,0 +1,275 @@
+//! Catalog-declared install targets
+//!
+//! Most patches are one MPQ that ends up as `Data/Patch-<ID>.mpq`. A
+//! patches.json entry can instead declare `files`: globs selecting files
+//! from the download (or the download itself) and the folder each goes
+//! to. Either kind can also set Config.wtf CVars. Targets are paths
+//! relative to the WoW root, and destinations are limited to the folders
+//! a 1.12 client loads content from. Addon entries without `files` are
+//! mapped from the `.toc` files in their archive instead.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use super::copier::{get_mpq_filename, InstallError};
+use super::extract::{entry_matches, ExtractedFile};
+use crate::models::{PatchFile, PatchKind, PatchModule};
+
+/// How a patch is installed, taken from its catalog entry
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstallSpec {
+    /// Globs picking the Data MPQ from an archive download
+    pub extract: Vec<String>,
+    /// Declared files; empty for a plain `Data/Patch-<ID>.mpq` patch
+    pub files: Vec<PatchFile>,
+    /// Config.wtf CVars to set
+    pub cvars: BTreeMap<String, String>,
+    /// Addon archive installed into `Interface/AddOns`
+    pub addon: bool,
+}
+
+impl InstallSpec {
+    pub fn from_module(module: &PatchModule) -> Self {
+        Self {
+            extract: module.extract.clone(),
+            files: module.files.clone(),
+            cvars: module.cvars.clone(),
+            addon: module.kind == PatchKind::Addon,
+        }
+    }
+
+    /// Whether the patch installs its own files instead of a single Data MPQ
+    pub fn is_declared(&self) -> bool {
+        !self.files.is_empty() || self.addon
+    }
+}
+
+/// Name of a patch's download in the downloads folder and store
+///
+/// Declared patches may download anything (DLLs, archives, text), so
+/// they do not get an `.mpq` name.
+pub fn download_filename(patch_id: &str, spec: &InstallSpec) -> String {
+    if spec.is_declared() {
+        format!("Patch-{}.download", patch_id.to_uppercase())
+    } else {
+        get_mpq_filename(patch_id)
+    }
+}
+
+/// Target of a patch that does not declare files
+pub fn default_target(patch_id: &str) -> String {
+    format!("Data/{}", get_mpq_filename(patch_id))
+}
+
+/// Store name of one installed file of a declared patch
+pub fn store_name(patch_id: &str, target: &str) -> String {
+    format!("{}/{}", patch_id, target)
+}
+
+/// Check a single path component (file or folder name)
+fn valid_component(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\', ':'])
+}
+
+/// Normalize and check a declared destination folder
+///
+/// Accepts the WoW root (empty), `Data/`, `Data/<locale>/` and
+/// `Interface/AddOns/<name>/`, with or without the trailing slash and in
+/// any case. Returns the destination with a trailing `/` (or empty).
+pub fn validate_destination(destination: &str) -> Result<String, InstallError> {
+    let normalized = destination.replace('\\', "/");
+    let parts: Vec<&str> = normalized
+        .split('/')
+        .filter(|p| !p.is_empty() && *p != ".")
+        .collect();
+
+    let valid = match parts.as_slice() {
+        [] => true,
+        [data] => data.eq_ignore_ascii_case("Data"),
+        [data, locale] => {
+            data.eq_ignore_ascii_case("Data")
+                && locale.len() == 4
+                && locale.chars().all(|c| c.is_ascii_alphabetic())
+        }
+        [interface, addons, name] => {
+            interface.eq_ignore_ascii_case("Interface")
+                && addons.eq_ignore_ascii_case("AddOns")
+                && valid_component(name)
+        }
+        _ => false,
+    };
+    if !valid {
+        return Err(InstallError::InvalidTarget(destination.to_string()));
+    }
+
+    Ok(parts.iter().map(|p| format!("{}/", p)).collect())
+}
+
+/// Path of a matched file below its destination
+///
+/// Keeps the folders below the pattern's literal directory prefix, so
+/// `MyAddon/*` installs `MyAddon/Textures/a.blp` as `Textures/a.blp`.
+fn relative_to_pattern(pattern: &str, name: &str) -> String {
+    if !pattern.contains('/') {
+        return name.rsplit('/').next().unwrap_or_default().to_string();
+    }
+    let literal = &pattern[..pattern.find('*').unwrap_or(pattern.len())];
+    let prefix_len = literal.rfind('/').map(|i| i + 1).unwrap_or(0);
+    name.get(prefix_len..).unwrap_or(name).to_string()
+}
+
+/// Join a destination and a relative file path, checking every component
+fn target_path(destination: &str, relative: &str) -> Result<String, InstallError> {
+    let relative = relative.replace('\\', "/");
+    if !relative.split('/').all(valid_component) {
+        return Err(InstallError::InvalidTarget(relative));
+    }
+    Ok(format!("{}{}", destination, relative))
+}
+
+/// Work out where each file extracted from an archive download goes
+///
+/// Returns (staged file, target path relative to the WoW root). A file
+/// without a `source` takes the whole archive. Every declared file must
+/// match something, and a `rename` must match exactly one file.
+pub fn map_extracted(
+    extracted: &[ExtractedFile],
+    declared: &[PatchFile],
+) -> Result<Vec<(PathBuf, String)>, InstallError> {
+    let mut mapped = Vec::new();
+    for file in declared {
+        let destination = validate_destination(&file.destination)?;
+        let pattern = file.source.as_deref().unwrap_or("*");
+        let matches: Vec<&ExtractedFile> = extracted
+            .iter()
+            .filter(|f| file.source.is_none() || entry_matches(pattern, &f.name))
+            .collect();
+
+        if matches.is_empty() || (file.rename.is_some() && matches.len() != 1) {
+            return Err(InstallError::ArchiveSelection {
+                pattern: pattern.to_string(),
+                matches: matches.len(),
+                available: extracted.iter().map(|f| f.name.clone()).collect(),
+            });
+        }
+
+        for matched in matches {
+            let relative = match (&file.rename, &file.source) {
+                (Some(rename), _) => rename.clone(),
+                (None, Some(source)) => relative_to_pattern(source, &matched.name),
+                (None, None) => matched.name.clone(),
+            };
+            mapped.push((matched.path.clone(), target_path(&destination, &relative)?));
+        }
+    }
+    Ok(mapped)
+}
+
+/// Work out where a download that is the file itself goes
+///
+/// Needs exactly one declared file without a `source`, and a `rename`
+/// giving the installed name.
+pub fn map_single(declared: &[PatchFile]) -> Result<String, InstallError> {
+    match declared {
+        [PatchFile { source: None, destination, rename: Some(rename) }] => {
+            target_path(&validate_destination(destination)?, rename)
+        }
+        _ => Err(InstallError::InvalidTarget(
+            "a plain download needs exactly one file with a rename and no source".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn declared(source: Option<&str>, destination: &str, rename: Option<&str>) -> PatchFile {
+        PatchFile {
+            source: source.map(str::to_string),
+            destination: destination.to_string(),
+            rename: rename.map(str::to_string),
+        }
+    }
+
+    fn extracted(names: &[&str]) -> Vec<ExtractedFile> {
+        names
+            .iter()
+            .map(|n| ExtractedFile { name: n.to_string(), path: PathBuf::from("/staging").join(n) })
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_destination() {
+        assert_eq!(validate_destination("").unwrap(), "");
+        assert_eq!(validate_destination("Data").unwrap(), "Data/");
+        assert_eq!(validate_destination("data/enUS/").unwrap(), "data/enUS/");
+        assert_eq!(validate_destination("Interface\\AddOns\\pfUI").unwrap(), "Interface/AddOns/pfUI/");
+
+        for invalid in ["WTF/", "Data/enUS/extra/", "Data/../..", "Interface/AddOns/", "Interface/AddOns/../x", "C:/Data"] {
+            assert!(validate_destination(invalid).is_err(), "{} was accepted", invalid);
+        }
+    }
+
+    #[test]
+    fn test_map_extracted() {
+        let files = extracted(&[
+            "pfUI-master/pfUI.toc",
+            "pfUI-master/img/bar.tga",
+            "patch/Patch-Z.mpq",
+            "patch/readme.txt",
+        ]);
+        let declared = vec![
+            declared(Some("pfUI-master/*"), "Interface/AddOns/pfUI/", None),
+            declared(Some("*.mpq"), "Data/", Some("Patch-Z.mpq")),
+        ];
+
+        let mapped = map_extracted(&files, &declared).unwrap();
+        let targets: Vec<&str> = mapped.iter().map(|(_, t)| t.as_str()).collect();
+        assert_eq!(
+            targets,
+            vec!["Interface/AddOns/pfUI/pfUI.toc", "Interface/AddOns/pfUI/img/bar.tga", "Data/Patch-Z.mpq"]
+        );
+        assert_eq!(mapped[2].0, PathBuf::from("/staging/patch/Patch-Z.mpq"));
+    }
+
+    #[test]
+    fn test_map_extracted_requires_matches() {
+        let files = extracted(&["a.mpq", "b.mpq"]);
+        assert!(matches!(
+            map_extracted(&files, &[declared(Some("*.dll"), "", None)]),
+            Err(InstallError::ArchiveSelection { matches: 0, .. })
+        ));
+        // A rename needs a single file to rename
+        assert!(matches!(
+            map_extracted(&files, &[declared(Some("*.mpq"), "Data/", Some("Patch-Z.mpq"))]),
+            Err(InstallError::ArchiveSelection { matches: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_map_single() {
+        assert_eq!(
+            map_single(&[declared(None, "", Some("VanillaHelpers.dll"))]).unwrap(),
+            "VanillaHelpers.dll"
+        );
+        assert!(map_single(&[declared(None, "", None)]).is_err());
+        assert!(map_single(&[declared(None, "", Some("../evil.dll"))]).is_err());
+        assert!(map_single(&[]).is_err());
+    }
+
+    #[test]
+    fn test_download_filename() {
+        let mut spec = InstallSpec::default();
+        assert_eq!(download_filename("a", &spec), "Patch-A.mpq");
+        spec.cvars.insert("gxApi".to_string(), "d3d9".to_string());
+        assert_eq!(download_filename("a", &spec), "Patch-A.mpq");
+        spec.files.push(declared(None, "", Some("d3d9.dll")));
+        assert_eq!(download_filename("a", &spec), "Patch-A.download");
+        let addon = InstallSpec { addon: true, ..Default::default() };
+        assert_eq!(download_filename("a", &addon), "Patch-A.download");
+    }
+}
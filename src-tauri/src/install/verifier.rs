@@ -1,14 +1,24 @@
 //! Installation verification for patch files
 //!
 //! Verifies installed patches by checking:
-//! - File existence in WoW Data folder
+//! - File existence in WoW Data folder (or wherever the patch installs)
 //! - File size matches downloaded version (if download exists)
+//! - Config.wtf CVars set by the patch (checked by the manager)
+//! - No second copy of a file differing only in case
+//!
+//! Files are looked up ignoring case, as the client does.
+//!
+//! Hardlinked, reflinked and symlinked installs are verified the same way
+//! as copies (metadata follows links); a symlink whose stored download was
+//! removed is reported as a broken link instead of "not installed".
 
 use std::path::{Path, PathBuf};
 use tokio::fs;
 use serde::Serialize;
 
+use super::casefold::{case_variants, resolve};
 use super::copier::get_mpq_filename;
+use super::strategy::is_broken_symlink;
 
 /// Result of verifying a single patch installation
 #[derive(Debug, Clone, Serialize)]
@@ -26,6 +36,20 @@ pub enum VerifyResult {
         expected_size: u64
     },
 
+    /// Patch is a symlink whose target no longer exists
+    BrokenLink { target: String },
+
+    /// A Config.wtf CVar set by the patch no longer holds its value
+    ConfigMismatch {
+        key: String,
+        expected: String,
+        actual: Option<String>,
+    },
+
+    /// Several files differ from the installed one only in case, and the
+    /// game may load any of them
+    CaseDuplicate { paths: Vec<String> },
+
     /// Error during verification
     Error { message: String },
 }
@@ -33,23 +57,41 @@ pub enum VerifyResult {
 /// Verify a single patch installation
 ///
 /// Checks that the MPQ file exists in the Data folder and
-/// optionally compares size with the downloaded file.
+/// optionally compares size with the downloaded file at `download_path`
+/// (a loose download or an object in the download store).
 pub async fn verify_patch(
     patch_id: &str,
     data_folder: &Path,
-    downloads_folder: &Path,
+    download_path: &Path,
 ) -> VerifyResult {
     let mpq_name = get_mpq_filename(patch_id);
-    let installed_path = data_folder.join(&mpq_name);
-    let download_path = downloads_folder.join(&mpq_name);
+    verify_file(&resolve(data_folder, &mpq_name), download_path).await
+}
+
+/// Verify one installed file against the download it came from
+pub async fn verify_file(installed_path: &Path, download_path: &Path) -> VerifyResult {
+    if is_broken_symlink(installed_path).await {
+        let target = fs::read_link(installed_path)
+            .await
+            .map(|t| t.to_string_lossy().to_string())
+            .unwrap_or_default();
+        return VerifyResult::BrokenLink { target };
+    }
 
     // Check if installed
     if !installed_path.exists() {
         return VerifyResult::NotInstalled;
     }
 
+    let variants = case_variants(installed_path);
+    if variants.len() > 1 {
+        return VerifyResult::CaseDuplicate {
+            paths: variants.iter().map(|p| p.to_string_lossy().to_string()).collect(),
+        };
+    }
+
     // Get installed file size
-    let installed_size = match fs::metadata(&installed_path).await {
+    let installed_size = match fs::metadata(installed_path).await {
         Ok(m) => m.len(),
         Err(e) => return VerifyResult::Error {
             message: format!("Failed to read installed file: {}", e)
@@ -63,7 +105,7 @@ pub async fn verify_patch(
     }
 
     // Compare sizes
-    let download_size = match fs::metadata(&download_path).await {
+    let download_size = match fs::metadata(download_path).await {
         Ok(m) => m.len(),
         Err(e) => return VerifyResult::Error {
             message: format!("Failed to read download file: {}", e)
@@ -80,8 +122,32 @@ pub async fn verify_patch(
     VerifyResult::Installed { verified: true }
 }
 
+/// Combine the results for the files of one patch
+///
+/// The first problem wins; otherwise the patch is verified only if every
+/// file was.
+pub fn combine_results(results: impl IntoIterator<Item = VerifyResult>) -> VerifyResult {
+    let mut verified = true;
+    let mut any = false;
+    for result in results {
+        match result {
+            VerifyResult::Installed { verified: file_verified } => {
+                verified &= file_verified;
+                any = true;
+            }
+            problem => return problem,
+        }
+    }
+    if any {
+        VerifyResult::Installed { verified }
+    } else {
+        VerifyResult::NotInstalled
+    }
+}
+
 /// Verify multiple patches at once
 ///
+/// Compares against `Patch-X.mpq` files in `downloads_folder`.
 /// Returns a vector of (patch_id, result) tuples.
 pub async fn verify_all(
     patch_ids: &[&str],
@@ -91,7 +157,8 @@ pub async fn verify_all(
     let mut results = Vec::with_capacity(patch_ids.len());
 
     for id in patch_ids {
-        let result = verify_patch(id, data_folder, downloads_folder).await;
+        let download_path = downloads_folder.join(get_mpq_filename(id));
+        let result = verify_patch(id, data_folder, &download_path).await;
         results.push((id.to_string(), result));
     }
 
@@ -101,8 +168,7 @@ pub async fn verify_all(
 /// Check if a specific patch is installed (quick existence check)
 pub async fn is_patch_installed(patch_id: &str, data_folder: &Path) -> bool {
     let mpq_name = get_mpq_filename(patch_id);
-    let installed_path = data_folder.join(&mpq_name);
-    installed_path.exists()
+    resolve(data_folder, &mpq_name).exists()
 }
 
 /// Get paths for installed patches
@@ -116,7 +182,7 @@ pub async fn get_installed_patches(
 
     for id in patch_ids {
         let mpq_name = get_mpq_filename(id);
-        let path = data_folder.join(&mpq_name);
+        let path = resolve(data_folder, &mpq_name);
         if path.exists() {
             installed.push(path);
         }
@@ -142,7 +208,8 @@ mod tests {
         let data_dir = tempdir().unwrap();
         let downloads_dir = tempdir().unwrap();
 
-        let result = verify_patch("A", data_dir.path(), downloads_dir.path()).await;
+        let download = downloads_dir.path().join("Patch-A.mpq");
+        let result = verify_patch("A", data_dir.path(), &download).await;
         assert!(matches!(result, VerifyResult::NotInstalled));
     }
 
@@ -155,7 +222,8 @@ mod tests {
         let installed = data_dir.path().join("Patch-A.mpq");
         create_test_file(&installed, b"test content").await;
 
-        let result = verify_patch("A", data_dir.path(), downloads_dir.path()).await;
+        let download = downloads_dir.path().join("Patch-A.mpq");
+        let result = verify_patch("A", data_dir.path(), &download).await;
         assert!(matches!(result, VerifyResult::Installed { verified: false }));
     }
 
@@ -173,7 +241,7 @@ mod tests {
         let download = downloads_dir.path().join("Patch-B.mpq");
         create_test_file(&download, content).await;
 
-        let result = verify_patch("B", data_dir.path(), downloads_dir.path()).await;
+        let result = verify_patch("B", data_dir.path(), &download).await;
         assert!(matches!(result, VerifyResult::Installed { verified: true }));
     }
 
@@ -190,10 +258,49 @@ mod tests {
         let download = downloads_dir.path().join("Patch-C.mpq");
         create_test_file(&download, b"much longer content here").await;
 
-        let result = verify_patch("C", data_dir.path(), downloads_dir.path()).await;
+        let result = verify_patch("C", data_dir.path(), &download).await;
         assert!(matches!(result, VerifyResult::SizeMismatch { .. }));
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_verify_linked_installs() {
+        let data_dir = tempdir().unwrap();
+        let downloads_dir = tempdir().unwrap();
+
+        let download = downloads_dir.path().join("object");
+        create_test_file(&download, b"stored content").await;
+
+        let hardlinked = data_dir.path().join("Patch-A.mpq");
+        fs::hard_link(&download, &hardlinked).await.unwrap();
+        let result = verify_patch("A", data_dir.path(), &download).await;
+        assert!(matches!(result, VerifyResult::Installed { verified: true }));
+
+        let symlinked = data_dir.path().join("Patch-B.mpq");
+        fs::symlink(&download, &symlinked).await.unwrap();
+        let result = verify_patch("B", data_dir.path(), &download).await;
+        assert!(matches!(result, VerifyResult::Installed { verified: true }));
+
+        fs::remove_file(&download).await.unwrap();
+        let result = verify_patch("B", data_dir.path(), &download).await;
+        assert!(matches!(result, VerifyResult::BrokenLink { .. }));
+
+        // The hardlink keeps its own copy of the data
+        let result = verify_patch("A", data_dir.path(), &download).await;
+        assert!(matches!(result, VerifyResult::Installed { verified: false }));
+    }
+
+    #[test]
+    fn test_combine_results() {
+        let ok = || VerifyResult::Installed { verified: true };
+        let unverified = || VerifyResult::Installed { verified: false };
+
+        assert!(matches!(combine_results([ok(), ok()]), VerifyResult::Installed { verified: true }));
+        assert!(matches!(combine_results([ok(), unverified()]), VerifyResult::Installed { verified: false }));
+        assert!(matches!(combine_results([ok(), VerifyResult::NotInstalled]), VerifyResult::NotInstalled));
+        assert!(matches!(combine_results([]), VerifyResult::NotInstalled));
+    }
+
     #[tokio::test]
     async fn test_verify_all() {
         let data_dir = tempdir().unwrap();
@@ -244,4 +351,28 @@ mod tests {
         assert!(installed.iter().any(|p| p.ends_with("Patch-A.mpq")));
         assert!(installed.iter().any(|p| p.ends_with("Patch-C.mpq")));
     }
+
+    #[tokio::test]
+    async fn test_case_variants_found_and_reported() {
+        let data_dir = tempdir().unwrap();
+        let downloads_dir = tempdir().unwrap();
+        let download = downloads_dir.path().join("Patch-A.mpq");
+        create_test_file(&download, b"test").await;
+
+        create_test_file(&data_dir.path().join("patch-a.MPQ"), b"test").await;
+        if data_dir.path().join("Patch-A.mpq").exists() {
+            return; // case-insensitive filesystem
+        }
+        assert!(is_patch_installed("A", data_dir.path()).await);
+        assert!(matches!(
+            verify_patch("A", data_dir.path(), &download).await,
+            VerifyResult::Installed { verified: true }
+        ));
+
+        create_test_file(&data_dir.path().join("Patch-A.mpq"), b"test").await;
+        match verify_patch("A", data_dir.path(), &download).await {
+            VerifyResult::CaseDuplicate { paths } => assert_eq!(paths.len(), 2),
+            other => panic!("expected a case duplicate, got {:?}", other),
+        }
+    }
 }
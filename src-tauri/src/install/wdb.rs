@@ -0,0 +1,222 @@
+//! WDB cache handling
+//!
+//! The WDB folder holds the client's creature, item and quest caches. Mods
+//! need it cleared when they are installed or updated, but deleting it for
+//! good throws away data the client would otherwise keep. Clearing therefore
+//! moves the folder aside as `WDB.bak`, and `restore_wdb` puts it back once
+//! no managed patches remain.
+
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use super::casefold::resolve;
+use super::copier::InstallError;
+
+/// Name of the cache folder inside the WoW directory
+const WDB_NAME: &str = "WDB";
+
+/// Name of the backup folder the cache is moved to before clearing
+const WDB_BACKUP_NAME: &str = "WDB.bak";
+
+/// How the WDB cache is handled when patches are installed or removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WdbPolicy {
+    /// Clear the cache and replace it with an empty file so the client
+    /// cannot recreate it (the official guide's recommendation)
+    #[default]
+    ClearAndBlock,
+    /// Clear the cache but let the client rebuild it
+    ClearOnly,
+    /// Never touch the cache
+    LeaveAlone,
+}
+
+/// Current state of the WDB path in a WoW folder
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WdbState {
+    /// No WDB folder or file exists
+    Missing,
+    /// A regular cache folder exists
+    Folder,
+    /// An empty file blocks the client from recreating the cache
+    Blocked,
+}
+
+/// Get the WDB path for a WoW installation
+pub fn wdb_path(wow_path: &Path) -> PathBuf {
+    resolve(wow_path, WDB_NAME)
+}
+
+/// Get the WDB backup path for a WoW installation
+pub fn wdb_backup_path(wow_path: &Path) -> PathBuf {
+    resolve(wow_path, WDB_BACKUP_NAME)
+}
+
+/// Inspect the WDB path of a WoW installation
+pub fn wdb_state(wow_path: &Path) -> WdbState {
+    let path = wdb_path(wow_path);
+    if path.is_dir() {
+        WdbState::Folder
+    } else if path.exists() {
+        WdbState::Blocked
+    } else {
+        WdbState::Missing
+    }
+}
+
+/// Clear the WDB cache according to the given policy
+///
+/// An existing cache folder is moved to `WDB.bak` (replacing any older
+/// backup) rather than deleted. With `ClearAndBlock`, an empty file named
+/// `WDB` is created afterwards so the client cannot recreate the cache.
+pub async fn clear_wdb(wow_path: &Path, policy: WdbPolicy) -> Result<(), InstallError> {
+    if policy == WdbPolicy::LeaveAlone {
+        log::info!("[WDB] Policy is LeaveAlone, not touching cache");
+        return Ok(());
+    }
+
+    let path = wdb_path(wow_path);
+    let backup_path = wdb_backup_path(wow_path);
+
+    match wdb_state(wow_path) {
+        WdbState::Folder => {
+            if backup_path.is_dir() {
+                fs::remove_dir_all(&backup_path).await?;
+            } else if backup_path.exists() {
+                fs::remove_file(&backup_path).await?;
+            }
+            log::info!("[WDB] Backing up {:?} to {:?}", path, backup_path);
+            fs::rename(&path, &backup_path).await?;
+        }
+        WdbState::Blocked if policy == WdbPolicy::ClearOnly => {
+            log::info!("[WDB] Removing blocking file so the client can rebuild the cache");
+            fs::remove_file(&path).await?;
+        }
+        WdbState::Blocked | WdbState::Missing => {}
+    }
+
+    if policy == WdbPolicy::ClearAndBlock && !path.exists() {
+        log::info!("[WDB] Creating empty WDB file to prevent cache recreation");
+        fs::File::create(&path).await?;
+    }
+
+    Ok(())
+}
+
+/// Undo `clear_wdb`
+///
+/// Removes the blocking file (if any) and moves the `WDB.bak` backup back
+/// into place when no live cache folder exists. Returns true if anything
+/// was changed.
+pub async fn restore_wdb(wow_path: &Path) -> Result<bool, InstallError> {
+    let path = wdb_path(wow_path);
+    let backup_path = wdb_backup_path(wow_path);
+    let mut changed = false;
+
+    if wdb_state(wow_path) == WdbState::Blocked {
+        log::info!("[WDB] Removing blocking file {:?}", path);
+        fs::remove_file(&path).await?;
+        changed = true;
+    }
+
+    if backup_path.is_dir() {
+        if path.exists() {
+            // The client already rebuilt a cache; keep it and drop the stale backup
+            fs::remove_dir_all(&backup_path).await?;
+        } else {
+            log::info!("[WDB] Restoring backup {:?}", backup_path);
+            fs::rename(&backup_path, &path).await?;
+        }
+        changed = true;
+    }
+
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    async fn create_cache(wow_path: &Path) {
+        let path = wdb_path(wow_path).join("enUS");
+        fs::create_dir_all(&path).await.unwrap();
+        fs::write(path.join("creaturecache.wdb"), b"cache").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_clear_and_block_backs_up_cache() {
+        let wow_dir = tempdir().unwrap();
+        create_cache(wow_dir.path()).await;
+
+        clear_wdb(wow_dir.path(), WdbPolicy::ClearAndBlock).await.unwrap();
+
+        assert_eq!(wdb_state(wow_dir.path()), WdbState::Blocked);
+        assert!(wdb_backup_path(wow_dir.path()).join("enUS/creaturecache.wdb").exists());
+    }
+
+    #[tokio::test]
+    async fn test_clear_replaces_backup_file() {
+        let wow_dir = tempdir().unwrap();
+        create_cache(wow_dir.path()).await;
+        fs::write(wdb_backup_path(wow_dir.path()), b"not a cache").await.unwrap();
+
+        clear_wdb(wow_dir.path(), WdbPolicy::ClearOnly).await.unwrap();
+
+        assert!(wdb_backup_path(wow_dir.path()).join("enUS/creaturecache.wdb").exists());
+    }
+
+    #[tokio::test]
+    async fn test_clear_only_leaves_no_blocking_file() {
+        let wow_dir = tempdir().unwrap();
+        create_cache(wow_dir.path()).await;
+
+        clear_wdb(wow_dir.path(), WdbPolicy::ClearOnly).await.unwrap();
+
+        assert_eq!(wdb_state(wow_dir.path()), WdbState::Missing);
+        assert!(wdb_backup_path(wow_dir.path()).is_dir());
+    }
+
+    #[tokio::test]
+    async fn test_clear_only_removes_existing_block() {
+        let wow_dir = tempdir().unwrap();
+        fs::File::create(wdb_path(wow_dir.path())).await.unwrap();
+
+        clear_wdb(wow_dir.path(), WdbPolicy::ClearOnly).await.unwrap();
+
+        assert_eq!(wdb_state(wow_dir.path()), WdbState::Missing);
+    }
+
+    #[tokio::test]
+    async fn test_leave_alone_keeps_cache() {
+        let wow_dir = tempdir().unwrap();
+        create_cache(wow_dir.path()).await;
+
+        clear_wdb(wow_dir.path(), WdbPolicy::LeaveAlone).await.unwrap();
+
+        assert_eq!(wdb_state(wow_dir.path()), WdbState::Folder);
+        assert!(!wdb_backup_path(wow_dir.path()).exists());
+    }
+
+    #[tokio::test]
+    async fn test_restore_wdb_puts_backup_back() {
+        let wow_dir = tempdir().unwrap();
+        create_cache(wow_dir.path()).await;
+        clear_wdb(wow_dir.path(), WdbPolicy::ClearAndBlock).await.unwrap();
+
+        assert!(restore_wdb(wow_dir.path()).await.unwrap());
+
+        assert_eq!(wdb_state(wow_dir.path()), WdbState::Folder);
+        assert!(wdb_path(wow_dir.path()).join("enUS/creaturecache.wdb").exists());
+        assert!(!wdb_backup_path(wow_dir.path()).exists());
+    }
+
+    #[tokio::test]
+    async fn test_restore_wdb_nothing_to_do() {
+        let wow_dir = tempdir().unwrap();
+        assert!(!restore_wdb(wow_dir.path()).await.unwrap());
+    }
+}
@@ -0,0 +1,475 @@
+//! Wine DLL overrides for DXVK
+//!
+//! Wine loads its builtin `d3d9.dll` even when DXVK's sits next to the
+//! executable, unless d3d9 is overridden to native-then-builtin. The
+//! override can live in two places:
+//! - the prefix's `user.reg`, under the executable's `AppDefaults` key
+//! - a Lutris game config's `wine.overrides`, which Lutris passes as
+//!   `WINEDLLOVERRIDES` and so wins over the registry
+//!
+//! The patcher writes to the Lutris config when one launches the WoW
+//! folder and to `user.reg` otherwise. The first edit of either file
+//! backs it up as `<file>.dxvk-backup`.
+//!
+//! wineserver rewrites `user.reg` when the prefix shuts down, so edits made
+//! while the game runs can be lost.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+use tokio::fs;
+
+use super::copier::InstallError;
+use super::detector::WowDetector;
+use super::dxvk::DXVK_REQUIREMENT;
+use super::prefixes::{lutris_configs, yaml_values};
+use super::process::ensure_not_running;
+use super::requirements::backup_path;
+
+/// Registry file holding the prefix's per-user settings
+const USER_REG: &str = "user.reg";
+
+/// DLL the override is for
+const D3D9: &str = "d3d9";
+
+/// Override value written to `user.reg`
+const REGISTRY_OVERRIDE: &str = "native,builtin";
+
+/// Override value written to Lutris configs, in Lutris' own spelling
+const LUTRIS_OVERRIDE: &str = "n,b";
+
+/// Prefix-wide overrides, used when the executable has none of its own
+const GLOBAL_OVERRIDES_KEY: &str = "Software\\\\Wine\\\\DllOverrides";
+
+/// Whether Wine will load DXVK's `d3d9.dll` for a WoW folder
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WineOverrideStatus {
+    /// Wine prefix the WoW folder lives in
+    pub prefix: Option<PathBuf>,
+    /// Lutris game config launching the WoW folder
+    pub lutris_config: Option<PathBuf>,
+    /// d3d9 override in `user.reg`, per executable or else prefix-wide
+    pub registry_value: Option<String>,
+    /// d3d9 override in the Lutris config
+    pub lutris_value: Option<String>,
+    /// Native d3d9 is loaded first
+    pub active: bool,
+    /// A `.dxvk-backup` of an edited file exists
+    pub has_backup: bool,
+}
+
+/// Prefix owning a path inside its `drive_c`
+pub fn find_prefix(wow_path: &Path) -> Option<PathBuf> {
+    wow_path
+        .ancestors()
+        .find(|dir| dir.file_name().is_some_and(|n| n.eq_ignore_ascii_case("drive_c")))
+        .and_then(Path::parent)
+        .filter(|prefix| prefix.join(USER_REG).is_file())
+        .map(Path::to_path_buf)
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Lutris config launching an executable in the WoW folder, else one
+/// using its prefix
+pub fn find_lutris_config(home: &Path, wow_path: &Path, prefix: Option<&Path>) -> Option<PathBuf> {
+    let configs: Vec<(PathBuf, String)> = lutris_configs(home)
+        .into_iter()
+        .filter_map(|config| std::fs::read_to_string(&config).ok().map(|text| (config, text)))
+        .collect();
+
+    let by_exe = configs.iter().find(|(_, text)| {
+        yaml_values(text, "exe")
+            .iter()
+            .any(|exe| Path::new(exe).parent().is_some_and(|dir| same_path(dir, wow_path)))
+    });
+    let by_prefix = || {
+        let prefix = prefix?;
+        configs.iter().find(|(_, text)| {
+            yaml_values(text, "prefix").iter().any(|p| same_path(Path::new(p), prefix))
+        })
+    };
+    by_exe.or_else(by_prefix).map(|(config, _)| config.clone())
+}
+
+/// Whether an override value loads the native DLL first
+pub fn is_native_first(value: &str) -> bool {
+    value.trim().to_ascii_lowercase().starts_with('n')
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value)
+}
+
+fn join_lines(lines: &[String]) -> String {
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
+
+/// `AppDefaults` override key for an executable, escaped as in `user.reg`
+pub fn app_overrides_key(exe: &str) -> String {
+    format!("Software\\\\Wine\\\\AppDefaults\\\\{}\\\\DllOverrides", exe)
+}
+
+fn reg_section(lines: &[String], key: &str) -> Option<Range<usize>> {
+    let header = lines.iter().position(|line| {
+        line.strip_prefix('[')
+            .and_then(|l| l.split_once(']'))
+            .is_some_and(|(k, _)| k.eq_ignore_ascii_case(key))
+    })?;
+    let end = (header + 1..lines.len())
+        .find(|&i| lines[i].starts_with('['))
+        .unwrap_or(lines.len());
+    Some(header..end)
+}
+
+fn reg_value_line(lines: &[String], section: Range<usize>, name: &str) -> Option<usize> {
+    let quoted = format!("\"{}\"=", name).to_ascii_lowercase();
+    section.skip(1).find(|&i| lines[i].to_ascii_lowercase().starts_with(&quoted))
+}
+
+/// String value of `name` under `key` in a Wine registry file
+pub fn reg_value(text: &str, key: &str, name: &str) -> Option<String> {
+    let lines: Vec<String> = text.lines().map(String::from).collect();
+    let section = reg_section(&lines, key)?;
+    let line = &lines[reg_value_line(&lines, section, name)?];
+    let (_, value) = line.split_once('=')?;
+    Some(unquote(value).to_string())
+}
+
+/// Set (or with `None` remove) the string value `name` under `key`,
+/// adding the key stamped with `now` when it is missing
+pub fn set_reg_value(text: &str, key: &str, name: &str, value: Option<&str>, now: u64) -> String {
+    let mut lines: Vec<String> = text.lines().map(String::from).collect();
+    let entry = value.map(|v| format!("\"{}\"=\"{}\"", name, v));
+    match (reg_section(&lines, key), entry) {
+        (Some(section), entry) => match (reg_value_line(&lines, section.clone(), name), entry) {
+            (Some(i), Some(entry)) => lines[i] = entry,
+            (Some(i), None) => {
+                lines.remove(i);
+            }
+            (None, Some(entry)) => {
+                let last = section.rev().find(|&i| !lines[i].trim().is_empty()).unwrap_or(0);
+                lines.insert(last + 1, entry);
+            }
+            (None, None) => {}
+        },
+        (None, Some(entry)) => {
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(format!("[{}] {}", key, now));
+            lines.push(entry);
+        }
+        (None, None) => {}
+    }
+    join_lines(&lines)
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// End of the YAML block opened by the line at `start`
+fn yaml_block_end(lines: &[String], start: usize) -> usize {
+    let depth = indent(&lines[start]);
+    (start + 1..lines.len())
+        .find(|&i| !lines[i].trim().is_empty() && indent(&lines[i]) <= depth)
+        .unwrap_or(lines.len())
+}
+
+/// Indentation of the children of the block opened by the line at `start`
+fn yaml_child_depth(lines: &[String], start: usize, end: usize) -> usize {
+    (start + 1..end)
+        .find(|&i| !lines[i].trim().is_empty())
+        .map(|i| indent(&lines[i]))
+        .unwrap_or(indent(&lines[start]) + 2)
+}
+
+fn yaml_key(lines: &[String], range: Range<usize>, depth: usize, key: &str) -> Option<usize> {
+    range.into_iter().find(|&i| {
+        indent(&lines[i]) == depth
+            && lines[i].trim_start().split_once(':').is_some_and(|(k, _)| k.trim() == key)
+    })
+}
+
+/// Line of the `wine.overrides` mapping and of its d3d9 entry
+fn lutris_override_lines(lines: &[String]) -> (Option<usize>, Option<usize>) {
+    let Some(wine) = yaml_key(lines, 0..lines.len(), 0, "wine") else {
+        return (None, None);
+    };
+    let end = yaml_block_end(lines, wine);
+    let depth = yaml_child_depth(lines, wine, end);
+    let Some(overrides) = yaml_key(lines, wine + 1..end, depth, "overrides") else {
+        return (None, None);
+    };
+    let end = yaml_block_end(lines, overrides);
+    let depth = yaml_child_depth(lines, overrides, end);
+    (Some(overrides), yaml_key(lines, overrides + 1..end, depth, D3D9))
+}
+
+/// d3d9 override in a Lutris game config
+pub fn lutris_override(text: &str) -> Option<String> {
+    let lines: Vec<String> = text.lines().map(String::from).collect();
+    let line = &lines[lutris_override_lines(&lines).1?];
+    let (_, value) = line.split_once(':')?;
+    Some(unquote(value).to_string())
+}
+
+/// Set (or with `None` remove) the d3d9 override in a Lutris game config
+pub fn set_lutris_override(text: &str, value: Option<&str>) -> String {
+    let mut lines: Vec<String> = text.lines().map(String::from).collect();
+    match (lutris_override_lines(&lines), value) {
+        ((_, Some(i)), Some(value)) => {
+            lines[i] = format!("{}{}: {}", " ".repeat(indent(&lines[i])), D3D9, value);
+        }
+        ((_, Some(i)), None) => {
+            lines.remove(i);
+        }
+        ((Some(overrides), None), Some(value)) => {
+            // An empty `overrides: {}` becomes a block mapping
+            let depth = indent(&lines[overrides]);
+            lines[overrides] = format!("{}overrides:", " ".repeat(depth));
+            lines.insert(overrides + 1, format!("{}{}: {}", " ".repeat(depth + 2), D3D9, value));
+        }
+        ((None, None), Some(value)) => match yaml_key(&lines, 0..lines.len(), 0, "wine") {
+            Some(wine) => {
+                let depth = yaml_child_depth(&lines, wine, yaml_block_end(&lines, wine));
+                lines.insert(wine + 1, format!("{}overrides:", " ".repeat(depth)));
+                lines.insert(wine + 2, format!("{}{}: {}", " ".repeat(depth + 2), D3D9, value));
+            }
+            None => {
+                lines.push("wine:".to_string());
+                lines.push("  overrides:".to_string());
+                lines.push(format!("    {}: {}", D3D9, value));
+            }
+        },
+        (_, None) => {}
+    }
+    join_lines(&lines)
+}
+
+fn exe_name(wow_path: &Path) -> String {
+    WowDetector::find_exe(wow_path)
+        .and_then(|exe| exe.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "WoW.exe".to_string())
+}
+
+async fn read_optional(path: &Path) -> Result<Option<String>, InstallError> {
+    match fs::read(path).await {
+        Ok(data) => Ok(Some(String::from_utf8_lossy(&data).into_owned())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Rewrite a file through `edit`, backing it up before its first change
+async fn edit_file(path: &Path, edit: impl FnOnce(&str) -> String) -> Result<bool, InstallError> {
+    let Some(text) = read_optional(path).await? else {
+        return Ok(false);
+    };
+    let edited = edit(&text);
+    if edited == text {
+        return Ok(false);
+    }
+
+    let backup = backup_path(path, DXVK_REQUIREMENT);
+    if !backup.exists() {
+        fs::copy(path, &backup).await?;
+    }
+    let mut partial = path.as_os_str().to_os_string();
+    partial.push(".partial");
+    fs::write(&partial, edited).await?;
+    fs::rename(&partial, path).await?;
+    log::info!("[Wine] Updated the d3d9 override in {}", path.display());
+    Ok(true)
+}
+
+/// Read where and how d3d9 is overridden for a WoW folder
+///
+/// `home` is searched for Lutris configs; without it only the prefix
+/// registry is read.
+pub async fn override_status(wow_path: &Path, home: Option<&Path>) -> Result<WineOverrideStatus, InstallError> {
+    let prefix = find_prefix(wow_path);
+    let lutris_config = home.and_then(|home| find_lutris_config(home, wow_path, prefix.as_deref()));
+
+    let mut status = WineOverrideStatus::default();
+    if let Some(prefix) = &prefix {
+        let user_reg = prefix.join(USER_REG);
+        if let Some(text) = read_optional(&user_reg).await? {
+            status.registry_value = reg_value(&text, &app_overrides_key(&exe_name(wow_path)), D3D9)
+                .or_else(|| reg_value(&text, GLOBAL_OVERRIDES_KEY, D3D9));
+        }
+        status.has_backup |= backup_path(&user_reg, DXVK_REQUIREMENT).exists();
+    }
+    if let Some(config) = &lutris_config {
+        if let Some(text) = read_optional(config).await? {
+            status.lutris_value = lutris_override(&text);
+        }
+        status.has_backup |= backup_path(config, DXVK_REQUIREMENT).exists();
+    }
+    status.active = status
+        .lutris_value
+        .as_deref()
+        .or(status.registry_value.as_deref())
+        .is_some_and(is_native_first);
+    status.prefix = prefix;
+    status.lutris_config = lutris_config;
+    Ok(status)
+}
+
+/// Turn the native d3d9 override for a WoW folder on or off
+///
+/// Enabling writes to the Lutris config when there is one, else to the
+/// prefix's `user.reg`. Disabling removes native-first overrides the
+/// patcher could have written from both, leaving other values alone.
+pub async fn set_d3d9_override(
+    wow_path: &Path,
+    home: Option<&Path>,
+    enabled: bool,
+) -> Result<WineOverrideStatus, InstallError> {
+    ensure_not_running(wow_path)?;
+    let prefix = find_prefix(wow_path);
+    let lutris_config = home.and_then(|home| find_lutris_config(home, wow_path, prefix.as_deref()));
+    let key = app_overrides_key(&exe_name(wow_path));
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+
+    if enabled {
+        match (&lutris_config, &prefix) {
+            (Some(config), _) => {
+                edit_file(config, |text| set_lutris_override(text, Some(LUTRIS_OVERRIDE))).await?;
+            }
+            (None, Some(prefix)) => {
+                edit_file(&prefix.join(USER_REG), |text| {
+                    set_reg_value(text, &key, D3D9, Some(REGISTRY_OVERRIDE), now)
+                })
+                .await?;
+            }
+            (None, None) => return Err(InstallError::WinePrefixNotFound(wow_path.display().to_string())),
+        }
+    } else {
+        if let Some(config) = &lutris_config {
+            edit_file(config, |text| match lutris_override(text) {
+                Some(value) if is_native_first(&value) => set_lutris_override(text, None),
+                _ => text.to_string(),
+            })
+            .await?;
+        }
+        if let Some(prefix) = &prefix {
+            edit_file(&prefix.join(USER_REG), |text| match reg_value(text, &key, D3D9) {
+                Some(value) if is_native_first(&value) => set_reg_value(text, &key, D3D9, None, now),
+                _ => text.to_string(),
+            })
+            .await?;
+        }
+    }
+    override_status(wow_path, home).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const USER_REG_TEXT: &str = "WINE REGISTRY Version 2\n\
+        ;; All keys relative to \\\\User\\\\S-1-5-21-0-0-0-1000\n\
+        \n\
+        #arch=win32\n\
+        \n\
+        [Software\\\\Wine\\\\DllOverrides] 1700000000\n\
+        #time=1da0000000000000\n\
+        \"*d3dx9_43\"=\"native,builtin\"\n\
+        \n\
+        [Software\\\\Wine\\\\Fonts] 1700000000\n\
+        \"LogPixels\"=dword:00000060\n";
+
+    #[test]
+    fn test_registry_override() {
+        let key = app_overrides_key("WoW.exe");
+        assert_eq!(reg_value(USER_REG_TEXT, GLOBAL_OVERRIDES_KEY, D3D9), None);
+
+        let added = set_reg_value(USER_REG_TEXT, &key, D3D9, Some(REGISTRY_OVERRIDE), 42);
+        assert!(added.starts_with(USER_REG_TEXT));
+        assert!(added.ends_with("\n[Software\\\\Wine\\\\AppDefaults\\\\WoW.exe\\\\DllOverrides] 42\n\"d3d9\"=\"native,builtin\"\n"));
+        assert_eq!(reg_value(&added, &app_overrides_key("wow.exe"), D3D9).as_deref(), Some("native,builtin"));
+
+        let global = set_reg_value(USER_REG_TEXT, GLOBAL_OVERRIDES_KEY, D3D9, Some("builtin"), 42);
+        assert!(global.contains("\"*d3dx9_43\"=\"native,builtin\"\n\"d3d9\"=\"builtin\"\n\n[Software\\\\Wine\\\\Fonts]"));
+        let replaced = set_reg_value(&global, GLOBAL_OVERRIDES_KEY, D3D9, Some("native"), 42);
+        assert_eq!(reg_value(&replaced, GLOBAL_OVERRIDES_KEY, D3D9).as_deref(), Some("native"));
+
+        let removed = set_reg_value(&added, &key, D3D9, None, 42);
+        assert_eq!(reg_value(&removed, &key, D3D9), None);
+        assert!(removed.contains("\"LogPixels\"=dword:00000060"));
+    }
+
+    #[test]
+    fn test_lutris_override() {
+        let config = "game:\n  exe: /games/wow/WoW.exe\n  prefix: /games/wow-prefix\nname: Turtle WoW\nwine:\n  dxvk: false\nsystem:\n  env: {}\n";
+        assert_eq!(lutris_override(config), None);
+
+        let added = set_lutris_override(config, Some("n,b"));
+        assert!(added.contains("wine:\n  overrides:\n    d3d9: n,b\n  dxvk: false\nsystem:"));
+        assert_eq!(lutris_override(&added).as_deref(), Some("n,b"));
+
+        let replaced = set_lutris_override(&added, Some("builtin"));
+        assert_eq!(lutris_override(&replaced).as_deref(), Some("builtin"));
+        assert_eq!(lutris_override(&set_lutris_override(&replaced, None)), None);
+
+        let empty = set_lutris_override("wine:\n  overrides: {}\n", Some("n,b"));
+        assert_eq!(empty, "wine:\n  overrides:\n    d3d9: n,b\n");
+        let appended = set_lutris_override("game:\n  exe: WoW.exe\n", Some("n,b"));
+        assert_eq!(lutris_override(&appended).as_deref(), Some("n,b"));
+        assert!(is_native_first("native,builtin") && !is_native_first("b,n"));
+    }
+
+    #[tokio::test]
+    async fn test_set_override_with_backup() {
+        let home = tempdir().unwrap();
+        let prefix = home.path().join("Games/turtle");
+        let wow = prefix.join("drive_c/Games/TurtleWoW");
+        std::fs::create_dir_all(&wow).unwrap();
+        std::fs::write(wow.join("WoW.exe"), b"MZ").unwrap();
+        std::fs::write(prefix.join(USER_REG), USER_REG_TEXT).unwrap();
+        assert_eq!(find_prefix(&wow), Some(prefix.clone()));
+
+        // Registry edit without a Lutris config
+        let status = set_d3d9_override(&wow, Some(home.path()), true).await.unwrap();
+        assert!(status.active && status.has_backup);
+        assert_eq!(status.registry_value.as_deref(), Some(REGISTRY_OVERRIDE));
+        let backup = backup_path(&prefix.join(USER_REG), DXVK_REQUIREMENT);
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), USER_REG_TEXT);
+        let status = set_d3d9_override(&wow, Some(home.path()), false).await.unwrap();
+        assert!(!status.active && status.registry_value.is_none());
+
+        // A Lutris config launching the folder takes the override instead
+        let games = home.path().join(".config/lutris/games");
+        std::fs::create_dir_all(&games).unwrap();
+        let config = games.join("turtle-wow-1.yml");
+        std::fs::write(&config, format!("game:\n  exe: {}\n", wow.join("WoW.exe").display())).unwrap();
+        let status = set_d3d9_override(&wow, Some(home.path()), true).await.unwrap();
+        assert_eq!(status.lutris_config.as_deref(), Some(config.as_path()));
+        assert_eq!(status.lutris_value.as_deref(), Some(LUTRIS_OVERRIDE));
+        assert!(status.active && status.registry_value.is_none());
+        assert!(backup_path(&config, DXVK_REQUIREMENT).exists());
+
+        let outside = tempdir().unwrap();
+        assert!(matches!(
+            set_d3d9_override(outside.path(), None, true).await,
+            Err(InstallError::WinePrefixNotFound(_))
+        ));
+    }
+}
@@ -2,6 +2,7 @@ mod parser;
 mod models;
 mod download;
 mod install;
+mod disk;
 
 use std::collections::HashSet;
 use std::fs::File;
@@ -12,12 +13,17 @@ use tauri_plugin_dialog::DialogExt;
 use log::LevelFilter;
 use simplelog::{CombinedLogger, Config, WriteLogger};
 
-use models::{PatchModule, PatchId, PatchGroup, DownloadLink, DownloadProvider as ProviderType};
+use models::{PatchModule, PatchId, PatchGroup, PatchFile, PatchKind, DownloadLink, Requirement};
 use parser::dependencies::{validate_module_selection, auto_select_dependencies};
-use download::{DownloadManager, progress::DownloadEvent};
+use download::{DownloadError, DownloadManager, FolderEntry, progress::DownloadEvent};
+use disk::SpaceCheck;
 use install::{
-    InstallManager, InstallEvent,
-    VerifyResult, RepairResult, WowDetector, Settings,
+    casefold,
+    InstallManager, InstallEvent, ClientInfo, LaaStatus,
+    VerifyResult, RepairResult, WowDetector, Settings, WdbPolicy, WdbState,
+    Profile, Profiles, GcResult, StoreStats, InstallStrategy,
+    InstallSpec, InstalledAddon, RequirementStatus, WowCandidate,
+    DxvkConfig, DxvkPreset, DxvkStatus, WineOverrideStatus, CvarStatus, RunningClient,
 };
 
 /// GitHub raw URL for patches.json
@@ -67,10 +73,16 @@ impl PatchesCache {
 pub struct PatchesResponse {
     pub patches: Vec<PatchModule>,
     pub groups: Vec<PatchGroup>,
+    /// Catalog problems that were worked around (e.g. misstated providers)
+    pub warnings: Vec<String>,
 }
 
 #[tauri::command]
-async fn fetch_patches(cache: State<'_, PatchesCache>) -> Result<PatchesResponse, String> {
+async fn fetch_patches(
+    cache: State<'_, PatchesCache>,
+    downloads: State<'_, DownloadManager>,
+    install: State<'_, InstallManager>,
+) -> Result<PatchesResponse, String> {
     let client = reqwest::Client::new();
 
     // Try to fetch from GitHub
@@ -93,21 +105,23 @@ async fn fetch_patches(cache: State<'_, PatchesCache>) -> Result<PatchesResponse
         .ok_or("Invalid patches.json format")?;
 
     let mut modules = Vec::new();
+    let mut warnings = Vec::new();
     for (id, patch) in patches {
         let links: Vec<DownloadLink> = patch["links"].as_array()
             .map(|arr| arr.iter().filter_map(|link| {
+                let url = link["url"].as_str()?;
+                let declared = link["provider"].as_str();
+                if let Some(warning) = downloads.registry().check_declared(declared, url) {
+                    log::warn!("[Patches] {}: {}", id, warning);
+                    warnings.push(format!("{}: {}", id, warning));
+                }
                 Some(DownloadLink {
-                    provider: match link["provider"].as_str()? {
-                        "mediafire" => ProviderType::Mediafire,
-                        "googledrive" | "gdrive" => ProviderType::GoogleDrive,
-                        "dropbox" => ProviderType::Dropbox,
-                        "transfer" => ProviderType::Transfer,
-                        "mega" => ProviderType::Mega,
-                        _ => ProviderType::Unknown,
-                    },
-                    url: link["url"].as_str()?.to_string(),
+                    // The provider field is optional; URLs are classified otherwise
+                    provider: downloads.registry().resolve(declared, url),
+                    url: url.to_string(),
                     file_name: link["file_name"].as_str().map(|s| s.to_string()),
                     variant: link["variant"].as_str().map(|s| s.to_string()),
+                    folder_file: link["folder_file"].as_str().map(|s| s.to_string()),
                 })
             }).collect())
             .unwrap_or_default();
@@ -126,6 +140,36 @@ async fn fetch_patches(cache: State<'_, PatchesCache>) -> Result<PatchesResponse
         let preview: Option<String> = patch["preview"].as_str().map(|s| s.to_string());
         let author: Option<String> = patch["author"].as_str().map(|s| s.to_string());
         let forum_url: Option<String> = patch["forumUrl"].as_str().map(|s| s.to_string());
+        let extract: Vec<String> = patch["extract"].as_array()
+            .map(|arr| arr.iter().filter_map(|p| p.as_str().map(|s| s.to_string())).collect())
+            .unwrap_or_default();
+        let files: Vec<PatchFile> = patch["files"].as_array()
+            .map(|arr| arr.iter().filter_map(|f| {
+                Some(PatchFile {
+                    source: f["source"].as_str().map(|s| s.to_string()),
+                    destination: f["destination"].as_str()?.to_string(),
+                    rename: f["rename"].as_str().map(|s| s.to_string()),
+                })
+            }).collect())
+            .unwrap_or_default();
+        let cvars = patch["cvars"].as_object()
+            .map(|obj| obj.iter().filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string()))).collect())
+            .unwrap_or_default();
+        let compatible_builds: Vec<u32> = patch["compatibleBuilds"].as_array()
+            .map(|arr| arr.iter().filter_map(|b| b.as_u64().map(|b| b as u32)).collect())
+            .unwrap_or_default();
+        let requires: Vec<String> = patch["requires"].as_array()
+            .map(|arr| arr.iter().filter_map(|r| r.as_str().map(|s| s.to_string())).collect())
+            .unwrap_or_default();
+        let kind = match patch["kind"].as_str() {
+            Some("addon") => PatchKind::Addon,
+            _ => PatchKind::Patch,
+        };
+        let file_size: Option<String> = match &patch["fileSize"] {
+            serde_json::Value::Number(n) => Some(n.to_string()),
+            serde_json::Value::String(s) => Some(s.clone()),
+            _ => None,
+        };
 
         modules.push(PatchModule {
             id: id.clone(),
@@ -134,12 +178,18 @@ async fn fetch_patches(cache: State<'_, PatchesCache>) -> Result<PatchesResponse
             downloads: links,
             dependencies,
             conflicts,
-            file_size: None,
+            file_size,
             last_updated: None,
             variants,
             preview,
             author,
             forum_url,
+            extract,
+            files,
+            cvars,
+            kind,
+            compatible_builds,
+            requires,
         });
     }
 
@@ -158,10 +208,30 @@ async fn fetch_patches(cache: State<'_, PatchesCache>) -> Result<PatchesResponse
         }).collect())
         .unwrap_or_default();
 
+    // Parse requirements, skipping (and reporting) malformed entries
+    let requirements: Vec<Requirement> = json["requirements"].as_object()
+        .map(|obj| obj.iter().filter_map(|(id, r)| {
+            match serde_json::from_value::<Requirement>(r.clone()) {
+                Ok(requirement) => Some(Requirement { id: id.clone(), ..requirement }),
+                Err(e) => {
+                    log::warn!("[Patches] requirement {}: {}", id, e);
+                    warnings.push(format!("requirement {}: {}", id, e));
+                    None
+                }
+            }
+        }).collect())
+        .unwrap_or_default();
+
     // Update cache for validation
     cache.update(modules.clone(), groups.clone());
-
-    Ok(PatchesResponse { patches: modules, groups })
+    install.set_requirements(requirements);
+    install.set_catalog(
+        modules.iter()
+            .map(|m| (m.id.clone(), InstallSpec::from_module(m)))
+            .collect(),
+    );
+
+    Ok(PatchesResponse { patches: modules, groups, warnings })
 }
 
 #[tauri::command]
@@ -204,29 +274,65 @@ fn get_conflicts(
         .collect()
 }
 
+/// Identify the client in a profile's WoW folder
+#[tauri::command]
+fn get_client_info(
+    manager: State<'_, InstallManager>,
+    profile_id: Option<String>,
+) -> Result<Option<ClientInfo>, String> {
+    let wow_path = manager.get_profile_wow_path(profile_id.as_deref())
+        .map_err(|e| e.to_string())?;
+    WowDetector::client_info(&wow_path).map_err(|e| e.to_string())
+}
+
+/// Get selected patches that do not support the profile's client build
+///
+/// Nothing is flagged when the build cannot be read.
+#[tauri::command]
+fn get_incompatible_patches(
+    cache: State<'_, PatchesCache>,
+    manager: State<'_, InstallManager>,
+    selected: Vec<String>,
+    profile_id: Option<String>,
+) -> Result<Vec<String>, String> {
+    let wow_path = manager.get_profile_wow_path(profile_id.as_deref())
+        .map_err(|e| e.to_string())?;
+    let build = WowDetector::client_info(&wow_path)
+        .map_err(|e| e.to_string())?
+        .and_then(|info| info.build);
+    let Some(build) = build else {
+        return Ok(Vec::new());
+    };
+
+    let patch_ids: HashSet<PatchId> = selected.into_iter().collect();
+    let modules = cache.get_modules();
+    Ok(parser::dependencies::get_incompatible(&patch_ids, &modules, build)
+        .into_iter()
+        .collect())
+}
+
 /// Start a download for a patch module
 ///
 /// Spawns an async download task that reports progress via the Channel.
 /// Returns the download_id immediately for tracking.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn start_download(
     manager: State<'_, DownloadManager>,
     share_url: String,
-    provider: String,
+    provider: Option<String>,
     dest_dir: String,
     on_progress: Channel<DownloadEvent>,
     target_filename: Option<String>,
+    expected_size: Option<u64>,
+    folder_file: Option<String>,
 ) -> Result<String, String> {
     let download_id = uuid::Uuid::new_v4().to_string();
 
-    let provider_type = match provider.to_lowercase().as_str() {
-        "googledrive" | "google_drive" | "gdrive" => ProviderType::GoogleDrive,
-        "mediafire" => ProviderType::Mediafire,
-        "dropbox" => ProviderType::Dropbox,
-        "transfer" => ProviderType::Transfer,
-        "mega" => ProviderType::Mega,
-        _ => ProviderType::Unknown,
-    };
+    if let Some(warning) = manager.registry().check_declared(provider.as_deref(), &share_url) {
+        log::warn!("[Download] {}", warning);
+    }
+    let provider_type = manager.registry().resolve(provider.as_deref(), &share_url);
 
     let dest_path = PathBuf::from(dest_dir);
 
@@ -243,20 +349,52 @@ async fn start_download(
                 download_id_clone.clone(),
                 on_progress.clone(),
                 target_filename,
+                expected_size,
+                folder_file,
             )
             .await;
 
-        if let Err(e) = result {
-            let _ = on_progress.send(DownloadEvent::Failed {
-                download_id: download_id_clone,
-                error: e.to_string(),
-            });
+        match result {
+            Ok(_) => {}
+            Err(DownloadError::Cancelled) => {
+                let _ = on_progress.send(DownloadEvent::Cancelled {
+                    download_id: download_id_clone,
+                });
+            }
+            Err(e) => {
+                let _ = on_progress.send(DownloadEvent::Failed {
+                    download_id: download_id_clone,
+                    error: e.to_string(),
+                });
+            }
         }
     });
 
     Ok(download_id)
 }
 
+/// List the files in a MEGA or Mediafire folder link
+///
+/// Lets catalog maintainers find the name to put in a link's `folder_file`.
+#[tauri::command]
+async fn list_folder(
+    manager: State<'_, DownloadManager>,
+    share_url: String,
+    provider: Option<String>,
+) -> Result<Vec<FolderEntry>, String> {
+    let provider_type = manager.registry().resolve(provider.as_deref(), &share_url);
+    manager
+        .list_folder(&share_url, provider_type)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Cancel a queued or running download, keeping its partial file
+#[tauri::command]
+fn cancel_download(manager: State<'_, DownloadManager>, download_id: String) -> bool {
+    manager.cancel(&download_id)
+}
+
 /// Get current active download count
 #[tauri::command]
 fn get_active_downloads(manager: State<'_, DownloadManager>) -> usize {
@@ -267,13 +405,22 @@ fn get_active_downloads(manager: State<'_, DownloadManager>) -> usize {
 // Installation Commands
 // ============================================================================
 
+/// Persist all installation profiles after a change
+fn save_profiles(app: &tauri::AppHandle, manager: &InstallManager) {
+    if let Err(e) = Settings::new(app).set_profiles(&manager.profiles()) {
+        log::warn!("[Profiles] Failed to save profiles: {}", e);
+    }
+}
+
 /// Select WoW folder via native dialog
 ///
 /// Opens folder picker and validates selection is a valid WoW installation.
+/// The folder is assigned to the given profile (or the active one).
 #[tauri::command]
 async fn select_wow_folder(
     app: tauri::AppHandle,
     manager: State<'_, InstallManager>,
+    profile_id: Option<String>,
 ) -> Result<Option<String>, String> {
     let folder = app.dialog()
         .file()
@@ -288,12 +435,11 @@ async fn select_wow_folder(
 
             if WowDetector::is_valid_wow_folder(&path) {
                 // Update manager
-                manager.set_wow_path(path.clone())
+                manager.set_profile_wow_path(profile_id.as_deref(), path.clone())
                     .map_err(|e| e.to_string())?;
 
                 // Save to settings
-                let settings = Settings::new(&app);
-                let _ = settings.set_wow_path(&path.to_string_lossy());
+                save_profiles(&app, &manager);
 
                 Ok(Some(path.to_string_lossy().to_string()))
             } else {
@@ -304,13 +450,15 @@ async fn select_wow_folder(
     }
 }
 
-/// Get the current WoW folder path
+/// Get the WoW folder path of a profile (or the active one)
 #[tauri::command]
-fn get_wow_path(manager: State<'_, InstallManager>) -> Option<String> {
-    manager.get_wow_path().map(|p| p.to_string_lossy().to_string())
+fn get_wow_path(manager: State<'_, InstallManager>, profile_id: Option<String>) -> Option<String> {
+    manager.get_profile_wow_path(profile_id.as_deref())
+        .ok()
+        .map(|p| p.to_string_lossy().to_string())
 }
 
-/// Try to auto-detect WoW folder
+/// Try to auto-detect WoW folder for the active profile
 #[tauri::command]
 fn auto_detect_wow(
     app: tauri::AppHandle,
@@ -320,8 +468,7 @@ fn auto_detect_wow(
         let path = manager.get_wow_path()?;
 
         // Save to settings
-        let settings = Settings::new(&app);
-        let _ = settings.set_wow_path(&path.to_string_lossy());
+        save_profiles(&app, &manager);
 
         Some(path.to_string_lossy().to_string())
     } else {
@@ -329,19 +476,32 @@ fn auto_detect_wow(
     }
 }
 
+/// List every WoW folder found on this machine, including Wine prefixes
+#[tauri::command]
+async fn list_wow_candidates() -> Result<Vec<WowCandidate>, String> {
+    tokio::task::spawn_blocking(WowDetector::list_candidates)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 /// Install patches to WoW Data folder
 #[tauri::command]
 async fn install_patches(
+    app: tauri::AppHandle,
     manager: State<'_, InstallManager>,
     patch_ids: Vec<String>,
     on_event: Channel<InstallEvent>,
+    profile_id: Option<String>,
 ) -> Result<Vec<String>, String> {
     log::info!("[Install] install_patches called for: {:?}", patch_ids);
-    log::info!("[Install] WoW path: {:?}", manager.get_wow_path());
+    log::info!("[Install] Profile: {:?}", profile_id);
+    log::info!("[Install] WoW path: {:?}", manager.get_profile_wow_path(profile_id.as_deref()));
     log::info!("[Install] Downloads path: {:?}", manager.get_downloads_folder());
 
     let ids: Vec<&str> = patch_ids.iter().map(|s| s.as_str()).collect();
-    let results = manager.install_patches(&ids, on_event).await;
+    let results = manager.install_patches(profile_id.as_deref(), &ids, on_event).await
+        .map_err(|e| e.to_string())?;
+    save_profiles(&app, &manager);
 
     let mut installed = Vec::new();
     let mut errors = Vec::new();
@@ -363,177 +523,600 @@ async fn install_patches(
     Ok(installed)
 }
 
+/// Check free disk space for downloading and installing patches
+///
+/// Uses stored downloads where present and catalog sizes otherwise.
+#[tauri::command]
+async fn check_disk_space(
+    manager: State<'_, InstallManager>,
+    cache: State<'_, PatchesCache>,
+    patch_ids: Vec<String>,
+    profile_id: Option<String>,
+) -> Result<Vec<SpaceCheck>, String> {
+    let catalog_sizes: std::collections::HashMap<String, u64> = cache
+        .get_modules()
+        .into_iter()
+        .filter_map(|m| Some((m.id, disk::parse_size(m.file_size.as_deref()?)?)))
+        .collect();
+
+    let ids: Vec<&str> = patch_ids.iter().map(|s| s.as_str()).collect();
+    manager.check_install_space(profile_id.as_deref(), &ids, &catalog_sizes).await
+        .map_err(|e| e.to_string())
+}
+
 /// Verify installed patches
 #[tauri::command]
 async fn verify_patches(
     manager: State<'_, InstallManager>,
     patch_ids: Vec<String>,
+    profile_id: Option<String>,
 ) -> Result<Vec<(String, VerifyResult)>, String> {
     let ids: Vec<&str> = patch_ids.iter().map(|s| s.as_str()).collect();
-    manager.verify_patches(&ids).await.map_err(|e| e.to_string())
+    manager.verify_patches(profile_id.as_deref(), &ids).await.map_err(|e| e.to_string())
 }
 
 /// Repair patches by re-copying from downloads
 #[tauri::command]
 async fn repair_patches(
+    app: tauri::AppHandle,
     manager: State<'_, InstallManager>,
     patch_ids: Vec<String>,
     on_event: Channel<InstallEvent>,
+    profile_id: Option<String>,
 ) -> Result<Vec<RepairResult>, String> {
     let ids: Vec<&str> = patch_ids.iter().map(|s| s.as_str()).collect();
-    manager.repair_patches(&ids, on_event).await.map_err(|e| e.to_string())
+    let results = manager.repair_patches(profile_id.as_deref(), &ids, on_event).await
+        .map_err(|e| e.to_string())?;
+    save_profiles(&app, &manager);
+    Ok(results)
 }
 
-/// Detect which patches are already installed in the WoW Data folder
+/// Detect which patches are already installed in a profile's WoW folder
 #[tauri::command]
 async fn detect_installed_patches(
     manager: State<'_, InstallManager>,
     patch_ids: Vec<String>,
+    profile_id: Option<String>,
 ) -> Result<Vec<String>, String> {
-    let wow_path = manager.get_wow_path()
-        .ok_or("WoW path not set")?;
+    let data_folder = manager.get_data_folder(profile_id.as_deref())
+        .map_err(|e| e.to_string())?;
 
-    let data_folder = wow_path.join("Data");
     if !data_folder.exists() {
         return Ok(Vec::new());
     }
 
     let ids: Vec<&str> = patch_ids.iter().map(|s| s.as_str()).collect();
-    let installed_paths = install::verifier::get_installed_patches(&ids, &data_folder).await;
-
-    // Extract just the patch IDs from the installed paths
-    let installed_ids: Vec<String> = installed_paths
-        .iter()
-        .filter_map(|path| {
-            path.file_stem()
-                .and_then(|s| s.to_str())
-                .and_then(|name| name.strip_prefix("Patch-"))
-                .map(|id| id.to_string())
-        })
-        .collect();
+    manager.detect_installed(profile_id.as_deref(), &ids).await
+        .map_err(|e| e.to_string())
+}
 
-    Ok(installed_ids)
+/// List the addons in a profile's WoW folder with their TOC versions
+#[tauri::command]
+async fn list_addons(
+    manager: State<'_, InstallManager>,
+    profile_id: Option<String>,
+) -> Result<Vec<InstalledAddon>, String> {
+    manager.list_addons(profile_id.as_deref()).await
+        .map_err(|e| e.to_string())
 }
 
 /// Uninstall patches by removing MPQ files from the WoW Data folder
 #[tauri::command]
 async fn uninstall_patches(
+    app: tauri::AppHandle,
     manager: State<'_, InstallManager>,
     patch_ids: Vec<String>,
+    profile_id: Option<String>,
 ) -> Result<Vec<String>, String> {
-    let wow_path = manager.get_wow_path()
-        .ok_or("WoW path not set")?;
+    let ids: Vec<&str> = patch_ids.iter().map(|s| s.as_str()).collect();
+    let uninstalled = manager.uninstall_patches(profile_id.as_deref(), &ids).await
+        .map_err(|e| e.to_string())?;
+    save_profiles(&app, &manager);
+    Ok(uninstalled)
+}
 
-    let data_folder = wow_path.join("Data");
-    if !data_folder.exists() {
-        return Err("Data folder not found".to_string());
-    }
+/// Get how patch files are placed into the Data folder
+#[tauri::command]
+fn get_install_strategy(manager: State<'_, InstallManager>) -> InstallStrategy {
+    manager.get_install_strategy()
+}
 
-    let mut uninstalled = Vec::new();
-    for patch_id in &patch_ids {
-        match install::copier::uninstall_mpq(&data_folder, patch_id).await {
-            Ok(_) => uninstalled.push(patch_id.clone()),
-            Err(e) => log::error!("[Uninstall] Failed to uninstall {}: {:?}", patch_id, e),
-        }
-    }
+/// Set how patch files are placed into the Data folder
+#[tauri::command]
+fn set_install_strategy(
+    app: tauri::AppHandle,
+    manager: State<'_, InstallManager>,
+    strategy: InstallStrategy,
+) -> Result<(), String> {
+    manager.set_install_strategy(strategy);
+    Settings::new(&app).set_install_strategy(strategy).map_err(|e| e.to_string())
+}
 
-    // Clear WDB cache after uninstalling
-    if let Err(e) = manager.clear_wdb_folder().await {
-        log::warn!("[Uninstall] Failed to clear WDB: {:?}", e);
-    }
+/// Get the total size of the shared download cache
+#[tauri::command]
+async fn get_download_cache_size(manager: State<'_, InstallManager>) -> Result<StoreStats, String> {
+    manager.download_cache_stats().await.map_err(|e| e.to_string())
+}
 
-    Ok(uninstalled)
+/// Remove cached downloads that no profile has installed
+#[tauri::command]
+async fn clean_download_cache(manager: State<'_, InstallManager>) -> Result<GcResult, String> {
+    manager.collect_download_garbage().await.map_err(|e| e.to_string())
+}
+
+/// Get the configured WDB cache policy
+#[tauri::command]
+fn get_wdb_policy(manager: State<'_, InstallManager>) -> WdbPolicy {
+    manager.get_wdb_policy()
+}
+
+/// Set and persist the WDB cache policy
+#[tauri::command]
+fn set_wdb_policy(
+    app: tauri::AppHandle,
+    manager: State<'_, InstallManager>,
+    policy: WdbPolicy,
+) -> Result<(), String> {
+    manager.set_wdb_policy(policy);
+    Settings::new(&app).set_wdb_policy(policy).map_err(|e| e.to_string())
+}
+
+/// Get the current state of the WDB cache in the WoW folder
+#[tauri::command]
+fn get_wdb_state(manager: State<'_, InstallManager>, profile_id: Option<String>) -> Option<WdbState> {
+    let wow_path = manager.get_profile_wow_path(profile_id.as_deref()).ok()?;
+    Some(install::wdb::wdb_state(&wow_path))
+}
+
+/// Remove the WDB blocking file and restore the cache backup
+///
+/// Refuses while the profile still has managed patches installed.
+#[tauri::command]
+async fn restore_wdb(
+    manager: State<'_, InstallManager>,
+    patch_ids: Vec<String>,
+    profile_id: Option<String>,
+) -> Result<bool, String> {
+    let ids: Vec<&str> = patch_ids.iter().map(|s| s.as_str()).collect();
+    manager.restore_wdb(profile_id.as_deref(), &ids).await.map_err(|e| e.to_string())
+}
+
+/// Get the Large Address Aware state of the profile's executable
+#[tauri::command]
+async fn get_laa_status(
+    manager: State<'_, InstallManager>,
+    profile_id: Option<String>,
+) -> Result<LaaStatus, String> {
+    let wow_path = manager.get_profile_wow_path(profile_id.as_deref())
+        .map_err(|e| e.to_string())?;
+    install::laa::laa_status(&wow_path).await.map_err(|e| e.to_string())
+}
+
+/// Set the Large Address Aware flag on the executable, backing it up first
+#[tauri::command]
+async fn enable_large_address_aware(
+    manager: State<'_, InstallManager>,
+    profile_id: Option<String>,
+) -> Result<LaaStatus, String> {
+    let wow_path = manager.get_profile_wow_path(profile_id.as_deref())
+        .map_err(|e| e.to_string())?;
+    install::laa::enable_laa(&wow_path).await.map_err(|e| e.to_string())
+}
+
+/// Restore the executable saved before the Large Address Aware patch
+#[tauri::command]
+async fn restore_exe_backup(
+    manager: State<'_, InstallManager>,
+    profile_id: Option<String>,
+) -> Result<LaaStatus, String> {
+    let wow_path = manager.get_profile_wow_path(profile_id.as_deref())
+        .map_err(|e| e.to_string())?;
+    install::laa::restore_exe(&wow_path).await.map_err(|e| e.to_string())
+}
+
+/// List the clients running from the profile's WoW folder
+#[tauri::command]
+async fn get_running_clients(
+    manager: State<'_, InstallManager>,
+    profile_id: Option<String>,
+) -> Result<Vec<RunningClient>, String> {
+    let wow_path = manager.get_profile_wow_path(profile_id.as_deref())
+        .map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || install::process::running_clients(&wow_path))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Compare the CVars recommended by the profile's patches with Config.wtf
+#[tauri::command]
+async fn get_cvar_status(
+    manager: State<'_, InstallManager>,
+    profile_id: Option<String>,
+) -> Result<Vec<CvarStatus>, String> {
+    manager.cvar_status(profile_id.as_deref()).await.map_err(|e| e.to_string())
+}
+
+/// Set an installed patch's recommended CVars, backing up Config.wtf first
+#[tauri::command]
+async fn apply_patch_cvars(
+    app: tauri::AppHandle,
+    manager: State<'_, InstallManager>,
+    patch_id: String,
+    profile_id: Option<String>,
+) -> Result<Vec<CvarStatus>, String> {
+    let result = manager.apply_patch_cvars(profile_id.as_deref(), &patch_id).await;
+    save_profiles(&app, &manager);
+    result.map_err(|e| e.to_string())?;
+    get_cvar_status(manager, profile_id).await
+}
+
+/// Put back the CVars an installed patch changed, backing up Config.wtf first
+#[tauri::command]
+async fn revert_patch_cvars(
+    app: tauri::AppHandle,
+    manager: State<'_, InstallManager>,
+    patch_id: String,
+    profile_id: Option<String>,
+) -> Result<Vec<CvarStatus>, String> {
+    let result = manager.revert_patch_cvars(profile_id.as_deref(), &patch_id).await;
+    save_profiles(&app, &manager);
+    result.map_err(|e| e.to_string())?;
+    get_cvar_status(manager, profile_id).await
 }
 
 /// Load saved settings on startup
+///
+/// Returns the active profile's WoW path if it is still a valid installation.
 #[tauri::command]
 fn load_saved_wow_path(
     app: tauri::AppHandle,
     manager: State<'_, InstallManager>,
 ) -> Option<String> {
-    let settings = Settings::new(&app);
-    if let Some(path_str) = settings.get_wow_path() {
-        let path = PathBuf::from(&path_str);
-        if WowDetector::is_valid_wow_folder(&path) {
-            let _ = manager.set_wow_path(path);
-            return Some(path_str);
-        }
+    manager.load_profiles(Settings::new(&app).get_profiles());
+    let path = manager.get_wow_path()?;
+    if WowDetector::is_valid_wow_folder(&path) {
+        Some(path.to_string_lossy().to_string())
+    } else {
+        None
     }
-    None
 }
 
-/// Check requirements status (VanillaHelpers and DXVK)
-#[derive(serde::Serialize)]
-pub struct RequirementsStatus {
-    vanilla_helpers: bool,
-    dxvk: bool,
+// ============================================================================
+// Profile Commands
+// ============================================================================
+
+/// List all installation profiles and the active profile ID
+#[tauri::command]
+fn list_profiles(manager: State<'_, InstallManager>) -> Profiles {
+    manager.profiles()
 }
 
+/// Create a new installation profile
 #[tauri::command]
-fn check_requirements(manager: State<'_, InstallManager>) -> Option<RequirementsStatus> {
-    let wow_path = manager.get_wow_path()?;
+fn create_profile(
+    app: tauri::AppHandle,
+    manager: State<'_, InstallManager>,
+    name: String,
+    wow_path: Option<String>,
+) -> Result<Profile, String> {
+    let profile = manager.create_profile(&name, wow_path.map(PathBuf::from))
+        .map_err(|e| e.to_string())?;
+    save_profiles(&app, &manager);
+    Ok(profile)
+}
 
-    // Check for VanillaHelpers.dll (case-insensitive)
-    let vanilla_helpers = wow_path.join("VanillaHelpers.dll").exists()
-        || wow_path.join("vanillahelpers.dll").exists();
+/// Make a profile the active one
+#[tauri::command]
+fn switch_profile(
+    app: tauri::AppHandle,
+    manager: State<'_, InstallManager>,
+    profile_id: String,
+) -> Result<Profile, String> {
+    let profile = manager.switch_profile(&profile_id).map_err(|e| e.to_string())?;
+    save_profiles(&app, &manager);
+    Ok(profile)
+}
 
-    // Check for d3d9.dll (DXVK)
-    let dxvk = wow_path.join("d3d9.dll").exists();
+/// Delete a profile (installed files are not touched)
+#[tauri::command]
+fn delete_profile(
+    app: tauri::AppHandle,
+    manager: State<'_, InstallManager>,
+    profile_id: String,
+) -> Result<(), String> {
+    manager.delete_profile(&profile_id).map_err(|e| e.to_string())?;
+    save_profiles(&app, &manager);
+    Ok(())
+}
 
-    Some(RequirementsStatus {
-        vanilla_helpers,
-        dxvk,
-    })
+/// Save the module selection and chosen variants of a profile
+#[tauri::command]
+fn save_profile_selection(
+    app: tauri::AppHandle,
+    manager: State<'_, InstallManager>,
+    selected_modules: Vec<String>,
+    variants: std::collections::HashMap<String, String>,
+    profile_id: Option<String>,
+) -> Result<(), String> {
+    manager.set_profile_selection(profile_id.as_deref(), selected_modules, variants)
+        .map_err(|e| e.to_string())?;
+    save_profiles(&app, &manager);
+    Ok(())
 }
 
-/// Install VanillaHelpers from GitHub
+/// Check every catalog requirement in a profile's WoW folder
 #[tauri::command]
-async fn install_vanilla_helpers(manager: State<'_, InstallManager>) -> Result<(), String> {
-    let wow_path = manager.get_wow_path()
-        .ok_or("WoW path not set")?;
+async fn check_requirements(
+    cache: State<'_, PatchesCache>,
+    manager: State<'_, InstallManager>,
+    profile_id: Option<String>,
+) -> Result<Vec<RequirementStatus>, String> {
+    manager.check_requirements(profile_id.as_deref(), &cache.get_modules())
+        .await
+        .map_err(|e| e.to_string())
+}
 
-    // GitHub releases API to get latest release
-    let client = reqwest::Client::new();
-    let releases_url = "https://api.github.com/repos/isfir/VanillaHelpers/releases/latest";
+/// Look up the newest release of each requirement, then check them all
+///
+/// GitHub-hosted requirements are compared against their latest release
+/// and DXVK against its newest stable one. Lookups that fail only warn.
+#[tauri::command]
+async fn check_requirement_updates(
+    cache: State<'_, PatchesCache>,
+    manager: State<'_, InstallManager>,
+    profile_id: Option<String>,
+) -> Result<Vec<RequirementStatus>, String> {
+    let provider = download::providers::GitHubReleaseProvider::new(reqwest::Client::new());
+    for requirement in manager.requirements() {
+        let latest = match install::requirements::github_ref(&requirement, None) {
+            Ok(Some(reference)) => provider
+                .fetch_release(&reference.owner, &reference.repo, None)
+                .await
+                .map(|release| Some(release.tag_name)),
+            _ if requirement.id == install::dxvk::DXVK_REQUIREMENT => provider
+                .fetch_releases(install::dxvk::DXVK_OWNER, install::dxvk::DXVK_REPO)
+                .await
+                .map(|releases| install::dxvk::release_versions(&releases).into_iter().next()),
+            _ => continue,
+        };
+        match latest {
+            Ok(Some(version)) => manager.set_latest_version(&requirement.id, version),
+            Ok(None) => {}
+            Err(e) => log::warn!("[Requirements] Could not look up the latest {}: {}", requirement.name, e),
+        }
+    }
+    check_requirements(cache, manager, profile_id).await
+}
 
-    let release: serde_json::Value = client
-        .get(releases_url)
-        .header("User-Agent", "WoW-HD-Patcher")
-        .send()
+/// List the versions a requirement can be installed or pinned at
+///
+/// GitHub-hosted requirements offer the tags of their stable releases,
+/// others the versions listed in the catalog.
+#[tauri::command]
+async fn list_requirement_versions(
+    manager: State<'_, InstallManager>,
+    requirement_id: String,
+) -> Result<Vec<String>, String> {
+    let requirement = manager.requirement(&requirement_id).map_err(|e| e.to_string())?;
+    let Some(reference) = install::requirements::github_ref(&requirement, None).map_err(|e| e.to_string())? else {
+        return Ok(requirement.versions);
+    };
+    let provider = download::providers::GitHubReleaseProvider::new(reqwest::Client::new());
+    let releases = provider
+        .fetch_releases(&reference.owner, &reference.repo)
         .await
-        .map_err(|e| format!("Failed to fetch releases: {}", e))?
-        .json()
+        .map_err(|e| format!("Failed to list {} releases: {}", requirement.name, e))?;
+    Ok(install::requirements::release_tags(&releases, &reference))
+}
+
+/// Pin a requirement to its installed version, or unpin it
+#[tauri::command]
+async fn pin_requirement(
+    app: tauri::AppHandle,
+    manager: State<'_, InstallManager>,
+    requirement_id: String,
+    pinned: bool,
+    profile_id: Option<String>,
+) -> Result<(), String> {
+    manager.set_requirement_pinned(profile_id.as_deref(), &requirement_id, pinned)
+        .map_err(|e| e.to_string())?;
+    save_profiles(&app, &manager);
+    Ok(())
+}
+
+/// Download and install (or update) a catalog requirement
+///
+/// `version` picks one of the requirement's versions (a release tag for
+/// GitHub-hosted ones); a pinned version is used when omitted, else the
+/// catalog's first version or the latest release. Downloads are checked
+/// against the checksum the release publishes, when it has one.
+#[tauri::command]
+async fn install_requirement(
+    app: tauri::AppHandle,
+    downloads: State<'_, DownloadManager>,
+    manager: State<'_, InstallManager>,
+    requirement_id: String,
+    version: Option<String>,
+    profile_id: Option<String>,
+) -> Result<(), String> {
+    let requirement = manager.requirement(&requirement_id).map_err(|e| e.to_string())?;
+    // Checked again before placing files; this saves a download that
+    // would only be thrown away
+    let wow_path = manager.get_profile_wow_path(profile_id.as_deref()).map_err(|e| e.to_string())?;
+    install::process::ensure_not_running(&wow_path).map_err(|e| e.to_string())?;
+    let version = match version {
+        Some(version) => Some(version),
+        None => manager.pinned_version(profile_id.as_deref(), &requirement_id).map_err(|e| e.to_string())?,
+    };
+    let reference = install::requirements::github_ref(&requirement, version.as_deref())
+        .map_err(|e| e.to_string())?;
+    let (url, version, published_sha256) = match reference {
+        Some(reference) => {
+            let provider = download::providers::GitHubReleaseProvider::new(reqwest::Client::new());
+            let (release, asset) = provider
+                .resolve_ref(&reference)
+                .await
+                .map_err(|e| format!("Failed to find the {} release: {}", requirement.name, e))?;
+            let sha256 = provider
+                .published_sha256(&release, &asset)
+                .await
+                .map_err(|e| format!("Failed to read the {} checksum: {}", requirement.name, e))?;
+            if sha256.is_none() {
+                log::warn!("[Requirements] {} {} publishes no checksum", requirement.name, release.tag_name);
+            }
+            if reference.tag.is_none() {
+                manager.set_latest_version(&requirement_id, release.tag_name.clone());
+            }
+            (asset.browser_download_url, Some(release.tag_name), sha256)
+        }
+        None => {
+            let (url, version) = install::requirements::source_url(&requirement, version.as_deref())
+                .map_err(|e| e.to_string())?;
+            (url, version, None)
+        }
+    };
+
+    let staging = manager.staging_dir("requirements");
+    std::fs::create_dir_all(&staging)
+        .map_err(|e| format!("Failed to create staging folder: {}", e))?;
+    let provider_type = downloads.registry().classify(&url);
+    let download = downloads
+        .download(
+            url,
+            provider_type,
+            staging,
+            uuid::Uuid::new_v4().to_string(),
+            Channel::new(|_| Ok(())),
+            Some(format!("{}.download", requirement_id)),
+            None,
+            None,
+        )
         .await
-        .map_err(|e| format!("Failed to parse releases: {}", e))?;
+        .map_err(|e| format!("Failed to download {}: {}", requirement.name, e))?;
+
+    let result = manager
+        .install_requirement(
+            profile_id.as_deref(),
+            &requirement_id,
+            version,
+            std::path::Path::new(&download),
+            published_sha256.as_deref(),
+        )
+        .await;
+    save_profiles(&app, &manager);
+    result.map_err(|e| e.to_string())?;
+
+    if requirement_id == install::dxvk::DXVK_REQUIREMENT {
+        update_wine_override(&manager, profile_id.as_deref(), true).await;
+    }
+    Ok(())
+}
 
-    // Find the DLL asset
-    let assets = release["assets"].as_array()
-        .ok_or("No assets found in release")?;
+/// Home folder searched for Lutris configs, on Linux only
+fn wine_home() -> Option<std::path::PathBuf> {
+    if cfg!(windows) {
+        return None;
+    }
+    std::env::var_os("HOME").map(std::path::PathBuf::from)
+}
 
-    let dll_asset = assets.iter()
-        .find(|a| a["name"].as_str().map(|n: &str| n.to_lowercase().ends_with(".dll")).unwrap_or(false))
-        .ok_or("VanillaHelpers.dll not found in release")?;
+/// Follow a DXVK install or uninstall with the Wine d3d9 override
+///
+/// Failures only warn: outside a Wine prefix or Lutris game there is
+/// nothing to override.
+async fn update_wine_override(manager: &InstallManager, profile_id: Option<&str>, enabled: bool) {
+    let Ok(wow_path) = manager.get_profile_wow_path(profile_id) else {
+        return;
+    };
+    let home = wine_home();
+    match install::wine::set_d3d9_override(&wow_path, home.as_deref(), enabled).await {
+        Ok(_) | Err(install::InstallError::WinePrefixNotFound(_)) => {}
+        Err(e) => log::warn!("[Wine] Could not update the d3d9 override: {}", e),
+    }
+}
 
-    let download_url = dll_asset["browser_download_url"].as_str()
-        .ok_or("No download URL for DLL")?;
+/// Remove a catalog requirement's files
+#[tauri::command]
+async fn uninstall_requirement(
+    app: tauri::AppHandle,
+    manager: State<'_, InstallManager>,
+    requirement_id: String,
+    profile_id: Option<String>,
+) -> Result<(), String> {
+    let result = manager.uninstall_requirement(profile_id.as_deref(), &requirement_id).await;
+    save_profiles(&app, &manager);
+    result.map_err(|e| e.to_string())?;
+
+    if requirement_id == install::dxvk::DXVK_REQUIREMENT {
+        update_wine_override(&manager, profile_id.as_deref(), false).await;
+    }
+    Ok(())
+}
 
-    // Download the DLL
-    let response = client
-        .get(download_url)
-        .header("User-Agent", "WoW-HD-Patcher")
-        .send()
+/// List the stable DXVK releases, newest first
+#[tauri::command]
+async fn list_dxvk_versions() -> Result<Vec<String>, String> {
+    let provider = download::providers::GitHubReleaseProvider::new(reqwest::Client::new());
+    let releases = provider
+        .fetch_releases(install::dxvk::DXVK_OWNER, install::dxvk::DXVK_REPO)
         .await
-        .map_err(|e| format!("Failed to download: {}", e))?;
+        .map_err(|e| format!("Failed to list DXVK releases: {}", e))?;
+    Ok(install::dxvk::release_versions(&releases))
+}
 
-    let bytes = response.bytes().await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
+/// Identify `d3d9.dll` and read the managed `dxvk.conf` of a profile's WoW folder
+#[tauri::command]
+async fn get_dxvk_status(
+    manager: State<'_, InstallManager>,
+    profile_id: Option<String>,
+) -> Result<DxvkStatus, String> {
+    let wow_path = manager.get_profile_wow_path(profile_id.as_deref())
+        .map_err(|e| e.to_string())?;
+    install::dxvk::dxvk_status(&wow_path).await.map_err(|e| e.to_string())
+}
 
-    // Write to WoW folder
-    let dest_path = wow_path.join("VanillaHelpers.dll");
-    std::fs::write(&dest_path, bytes)
-        .map_err(|e| format!("Failed to write DLL: {}", e))?;
+/// Write a managed `dxvk.conf` from a preset
+#[tauri::command]
+async fn write_dxvk_conf(
+    app: tauri::AppHandle,
+    manager: State<'_, InstallManager>,
+    preset: DxvkPreset,
+    max_frame_rate: Option<u32>,
+    profile_id: Option<String>,
+) -> Result<DxvkStatus, String> {
+    let config = DxvkConfig::from_preset(preset, max_frame_rate);
+    manager.write_dxvk_conf(profile_id.as_deref(), &config)
+        .await
+        .map_err(|e| e.to_string())?;
+    save_profiles(&app, &manager);
+    get_dxvk_status(manager, profile_id).await
+}
 
-    Ok(())
+/// Read whether Wine loads DXVK's `d3d9.dll` for a profile's WoW folder
+#[tauri::command]
+async fn get_wine_override_status(
+    manager: State<'_, InstallManager>,
+    profile_id: Option<String>,
+) -> Result<WineOverrideStatus, String> {
+    let wow_path = manager.get_profile_wow_path(profile_id.as_deref())
+        .map_err(|e| e.to_string())?;
+    let home = wine_home();
+    install::wine::override_status(&wow_path, home.as_deref()).await.map_err(|e| e.to_string())
+}
+
+/// Turn the native d3d9 override for a profile's WoW folder on or off
+#[tauri::command]
+async fn set_wine_override(
+    manager: State<'_, InstallManager>,
+    enabled: bool,
+    profile_id: Option<String>,
+) -> Result<WineOverrideStatus, String> {
+    let wow_path = manager.get_profile_wow_path(profile_id.as_deref())
+        .map_err(|e| e.to_string())?;
+    let home = wine_home();
+    install::wine::set_d3d9_override(&wow_path, home.as_deref(), enabled)
+        .await
+        .map_err(|e| e.to_string())
 }
 
 /// Check for app updates from GitHub
@@ -643,7 +1226,7 @@ async fn check_patch_freshness(
     // Get local file modification time
     let wow_path = manager.get_wow_path();
     let local_modified = wow_path.and_then(|p| {
-        let patch_file = p.join("Data").join(format!("Patch-{}.mpq", patch_id.to_uppercase()));
+        let patch_file = casefold::resolve(&p, format!("Data/Patch-{}.mpq", patch_id.to_uppercase()));
         std::fs::metadata(&patch_file).ok()
             .and_then(|m| m.modified().ok())
             .map(|t| {
@@ -683,56 +1266,6 @@ async fn check_patch_freshness(
     })
 }
 
-/// Install DXVK from GitHub
-#[tauri::command]
-async fn install_dxvk(manager: State<'_, InstallManager>, version: Option<String>) -> Result<(), String> {
-    let wow_path = manager.get_wow_path()
-        .ok_or("WoW path not set")?;
-
-    // Select version - default to 2.7.1 (recommended for NVIDIA)
-    let ver = version.as_deref().unwrap_or("2.7.1");
-    let download_url = match ver {
-        "2.5.3" => "https://github.com/doitsujin/dxvk/releases/download/v2.5.3/dxvk-2.5.3.tar.gz",
-        "2.7.1" | _ => "https://github.com/doitsujin/dxvk/releases/download/v2.7.1/dxvk-2.7.1.tar.gz",
-    };
-
-    let client = reqwest::Client::new();
-    let response = client
-        .get(download_url)
-        .header("User-Agent", "WoW-HD-Patcher")
-        .send()
-        .await
-        .map_err(|e| format!("Failed to download DXVK: {}", e))?;
-
-    let bytes = response.bytes().await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
-
-    // Extract d3d9.dll from x32 folder in tar.gz
-    use std::io::Read;
-    let decoder = flate2::read::GzDecoder::new(&bytes[..]);
-    let mut archive = tar::Archive::new(decoder);
-
-    for entry in archive.entries().map_err(|e| format!("Failed to read archive: {}", e))? {
-        let mut entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
-        let path = entry.path().map_err(|e| format!("Invalid path: {}", e))?;
-
-        // Look for x32/d3d9.dll
-        if path.to_string_lossy().contains("x32/d3d9.dll") || path.to_string_lossy().contains("x32\\d3d9.dll") {
-            let mut contents = Vec::new();
-            entry.read_to_end(&mut contents)
-                .map_err(|e| format!("Failed to read DLL: {}", e))?;
-
-            let dest_path = wow_path.join("d3d9.dll");
-            std::fs::write(&dest_path, contents)
-                .map_err(|e| format!("Failed to write DLL: {}", e))?;
-
-            return Ok(());
-        }
-    }
-
-    Err("d3d9.dll not found in DXVK archive".to_string())
-}
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -764,6 +1297,10 @@ pub fn run() {
 
             // Create and register InstallManager
             let install_manager = InstallManager::new(downloads_path);
+            let settings = Settings::new(app.handle());
+            install_manager.set_wdb_policy(settings.get_wdb_policy());
+            install_manager.set_install_strategy(settings.get_install_strategy());
+            install_manager.load_profiles(settings.get_profiles());
             app.manage(install_manager);
 
             Ok(())
@@ -776,22 +1313,58 @@ pub fn run() {
             validate_selection,
             auto_select_deps,
             get_conflicts,
+            get_client_info,
+            get_incompatible_patches,
             // Download commands
             start_download,
+            cancel_download,
+            list_folder,
             get_active_downloads,
             // Install commands
             select_wow_folder,
             get_wow_path,
             auto_detect_wow,
+            list_wow_candidates,
             install_patches,
             verify_patches,
             repair_patches,
             detect_installed_patches,
+            list_addons,
             uninstall_patches,
+            check_disk_space,
+            get_download_cache_size,
+            get_install_strategy,
+            set_install_strategy,
+            clean_download_cache,
             load_saved_wow_path,
+            get_wdb_policy,
+            set_wdb_policy,
+            get_wdb_state,
+            restore_wdb,
+            // Profile commands
+            list_profiles,
+            create_profile,
+            switch_profile,
+            delete_profile,
+            save_profile_selection,
             check_requirements,
-            install_vanilla_helpers,
-            install_dxvk,
+            check_requirement_updates,
+            list_requirement_versions,
+            pin_requirement,
+            install_requirement,
+            uninstall_requirement,
+            list_dxvk_versions,
+            get_dxvk_status,
+            write_dxvk_conf,
+            get_wine_override_status,
+            set_wine_override,
+            get_laa_status,
+            enable_large_address_aware,
+            restore_exe_backup,
+            get_running_clients,
+            get_cvar_status,
+            apply_patch_cvars,
+            revert_patch_cvars,
             // Update commands
             check_for_updates,
             check_patch_freshness,
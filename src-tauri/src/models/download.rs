@@ -9,6 +9,9 @@ pub struct DownloadLink {
     pub file_name: Option<String>,
     /// Variant name for patches with multiple options (e.g., "Regular Version", "Less Thicc Version")
     pub variant: Option<String>,
+    /// File to take from a folder link, by exact name or `*` glob
+    #[serde(default)]
+    pub folder_file: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -18,5 +21,9 @@ pub enum DownloadProvider {
     Dropbox,
     Transfer,
     Mega,
+    /// GitHub release asset (`github:owner/repo@tag/asset`)
+    GitHub,
+    /// Plain HTTP(S) link on a host without a dedicated provider
+    Http,
     Unknown,
 }
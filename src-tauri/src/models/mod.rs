@@ -1,8 +1,10 @@
 pub mod patch;
 pub mod download;
+pub mod requirement;
 
-pub use patch::{PatchModule, PatchId, PatchGroup};
+pub use patch::{PatchModule, PatchId, PatchGroup, PatchFile, PatchKind};
 pub use download::{DownloadLink, DownloadProvider};
+pub use requirement::{Requirement, RequirementDetect, RequirementLevel};
 
 /// Parsed forum post content
 #[derive(Debug)]
@@ -1,5 +1,6 @@
 //! Patch module data structures
 
+use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
 use crate::models::DownloadLink;
 
@@ -28,6 +29,54 @@ pub struct PatchModule {
     pub author: Option<String>,
     /// Link to forum post or source page
     pub forum_url: Option<String>,
+    /// Globs picking the MPQ from a download packaged as an archive,
+    /// tried in order against the path inside the archive
+    #[serde(default)]
+    pub extract: Vec<String>,
+    /// Files the patch installs, when it is more than one MPQ in `Data/`
+    #[serde(default)]
+    pub files: Vec<PatchFile>,
+    /// Config.wtf CVars the patch sets
+    #[serde(default)]
+    pub cvars: BTreeMap<String, String>,
+    /// What the download contains
+    #[serde(default)]
+    pub kind: PatchKind,
+    /// Client builds the patch works with; empty for any build
+    #[serde(default)]
+    pub compatible_builds: Vec<u32>,
+    /// IDs of requirements (e.g. VanillaHelpers) the patch needs
+    #[serde(default)]
+    pub requires: Vec<String>,
+}
+
+/// What a catalog entry installs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PatchKind {
+    /// MPQ patch (or files declared with `files`)
+    #[default]
+    Patch,
+    /// Zip of addon folders, installed into `Interface/AddOns` by their
+    /// `.toc` names unless `files` says otherwise
+    Addon,
+}
+
+/// A file (or set of files) a patch installs, declared in patches.json
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchFile {
+    /// Glob selecting files inside an archive download; omitted when the
+    /// download is the file itself
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Folder relative to the WoW root: `Data/`, `Data/<locale>/`, the
+    /// root itself (empty) or `Interface/AddOns/<name>/`
+    #[serde(default)]
+    pub destination: String,
+    /// Installed file name, for a source matching a single file
+    #[serde(default)]
+    pub rename: Option<String>,
 }
 
 /// Group definition for organizing patches in the UI
@@ -50,6 +99,8 @@ pub struct PatchesConfig {
     pub patches: std::collections::HashMap<PatchId, PatchData>,
     #[serde(default)]
     pub groups: Vec<PatchGroup>,
+    #[serde(default)]
+    pub requirements: std::collections::HashMap<String, crate::models::Requirement>,
 }
 
 /// Raw patch data from JSON (before conversion to PatchModule)
@@ -67,4 +118,16 @@ pub struct PatchData {
     pub preview: Option<String>,
     pub author: Option<String>,
     pub forum_url: Option<String>,
+    #[serde(default)]
+    pub extract: Vec<String>,
+    #[serde(default)]
+    pub files: Vec<PatchFile>,
+    #[serde(default)]
+    pub cvars: BTreeMap<String, String>,
+    #[serde(default)]
+    pub kind: PatchKind,
+    #[serde(default)]
+    pub compatible_builds: Vec<u32>,
+    #[serde(default)]
+    pub requires: Vec<String>,
 }
@@ -0,0 +1,63 @@
+//! Requirement data structures
+//!
+//! Requirements are prerequisites like VanillaHelpers or DXVK that live
+//! next to the client rather than in `Data/`. patches.json declares how to
+//! detect, download and install each one, and patches list the ones they
+//! need in `requires`.
+
+use serde::{Deserialize, Serialize};
+use crate::models::PatchFile;
+
+/// A prerequisite declared in the `requirements` section of patches.json
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Requirement {
+    /// Key of the entry in patches.json
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub level: RequirementLevel,
+    /// Download link (URL or `github:owner/repo@tag/asset`); `{version}`
+    /// is replaced by the chosen version
+    pub source: String,
+    /// Versions to choose from, the default first; empty when the source
+    /// is not versioned
+    #[serde(default)]
+    pub versions: Vec<String>,
+    pub detect: RequirementDetect,
+    /// Files to install, as for patches; defaults to the download being
+    /// the detected file itself
+    #[serde(default)]
+    pub install: Vec<PatchFile>,
+}
+
+/// How strongly a requirement is suggested when no selected patch needs it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RequirementLevel {
+    Required,
+    #[default]
+    Recommended,
+    Optional,
+}
+
+/// How to tell whether a requirement is installed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequirementDetect {
+    /// File relative to the WoW root, e.g. `VanillaHelpers.dll`
+    pub file: String,
+    /// Lowest acceptable PE file version, e.g. `1.2.0.0`
+    #[serde(default)]
+    pub min_version: Option<String>,
+    /// Accepted sha256 hashes of the file; empty to accept any
+    #[serde(default)]
+    pub sha256: Vec<String>,
+    /// Name the file must carry in its version strings (or contents) to
+    /// count, e.g. `DXVK` to tell it from other `d3d9.dll` wrappers
+    #[serde(default)]
+    pub product: Option<String>,
+}
@@ -135,3 +135,50 @@ pub fn get_conflicts(
 
     conflicts
 }
+
+/// Get selected patches that do not support a client build
+///
+/// Patches without `compatibleBuilds` work with any build.
+pub fn get_incompatible(
+    selected: &HashSet<PatchId>,
+    modules: &[PatchModule],
+    build: u32,
+) -> HashSet<PatchId> {
+    modules
+        .iter()
+        .filter(|m| selected.contains(&m.id))
+        .filter(|m| !m.compatible_builds.is_empty() && !m.compatible_builds.contains(&build))
+        .map(|m| m.id.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module(id: &str, compatible_builds: &[u32]) -> PatchModule {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": id,
+            "description": "",
+            "links": [],
+            "compatibleBuilds": compatible_builds,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_get_incompatible() {
+        let modules = vec![module("A", &[5875]), module("B", &[7000]), module("C", &[])];
+        let all: HashSet<PatchId> = ["A", "B", "C"].iter().map(|s| s.to_string()).collect();
+
+        // Each build flags the patch made for the other; C works with both
+        assert_eq!(get_incompatible(&all, &modules, 5875), HashSet::from(["B".to_string()]));
+        assert_eq!(get_incompatible(&all, &modules, 7000), HashSet::from(["A".to_string()]));
+
+        // Patches that are not selected are never reported
+        let only_a = HashSet::from(["A".to_string()]);
+        assert!(get_incompatible(&only_a, &modules, 5875).is_empty());
+        assert_eq!(get_incompatible(&only_a, &modules, 7000), only_a);
+    }
+}
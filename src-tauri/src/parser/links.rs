@@ -68,6 +68,7 @@ pub fn extract_download_links(content: &str) -> Vec<DownloadLink> {
             url,
             file_name: None,
             variant,
+            folder_file: None,
         });
     }
 
@@ -80,6 +81,7 @@ pub fn extract_download_links(content: &str) -> Vec<DownloadLink> {
             url,
             file_name: None,
             variant,
+            folder_file: None,
         });
     }
 
@@ -92,6 +94,7 @@ pub fn extract_download_links(content: &str) -> Vec<DownloadLink> {
             url,
             file_name: None,
             variant,
+            folder_file: None,
         });
     }
 
@@ -104,6 +107,7 @@ pub fn extract_download_links(content: &str) -> Vec<DownloadLink> {
             url,
             file_name: Some(cap[2].to_string()),
             variant,
+            folder_file: None,
         });
     }
 
@@ -116,6 +120,7 @@ pub fn extract_download_links(content: &str) -> Vec<DownloadLink> {
             url,
             file_name: Some(cap[2].to_string()),
             variant,
+            folder_file: None,
         });
     }
 
@@ -128,6 +133,7 @@ pub fn extract_download_links(content: &str) -> Vec<DownloadLink> {
             url,
             file_name: None,
             variant,
+            folder_file: None,
         });
     }
 
@@ -5,6 +5,7 @@ import { PresetSelector } from '@/components/PresetSelector';
 import { ModuleList } from '@/components/ModuleList';
 import { FolderPicker } from '@/components/FolderPicker';
 import { RequirementsPanel } from '@/components/RequirementsPanel';
+import { ConfigPanel } from '@/components/ConfigPanel';
 import { DownloadProgress } from '@/components/DownloadProgress';
 import { InstallProgress } from '@/components/InstallProgress';
 import { UpdateBanner } from '@/components/UpdateBanner';
@@ -12,8 +13,8 @@ import { usePatches } from '@/hooks/usePatches';
 import { useWowPath } from '@/hooks/useWowPath';
 import { useDownload } from '@/hooks/useDownload';
 import { useInstall } from '@/hooks/useInstall';
-import { detectInstalledPatches, uninstallPatches } from '@/lib/tauri';
-import { Loader2, Plus, Minus, RefreshCw } from 'lucide-react';
+import { detectInstalledPatches, uninstallPatches, getRunningClients } from '@/lib/tauri';
+import { Loader2, Plus, Minus, RefreshCw, AlertTriangle } from 'lucide-react';
 import './App.css';
 
 type AppState = 'configure' | 'downloading' | 'installing' | 'uninstalling' | 'complete';
@@ -27,6 +28,7 @@ function App() {
   const [variantSelections, setVariantSelections] = useState<Map<string, number>>(new Map());
   const [installedPatches, setInstalledPatches] = useState<Set<string>>(new Set());
   const [detecting, setDetecting] = useState(false);
+  const [runningExe, setRunningExe] = useState<string | null>(null);
   const [applying, setApplying] = useState(false);
 
   // Calculate what needs to change
@@ -78,6 +80,11 @@ function App() {
   const handleApply = async () => {
     if (!wowPath) return;
 
+    // Files the client has open must not be replaced
+    const running = await getRunningClients().catch(() => []);
+    setRunningExe(running[0]?.exe ?? null);
+    if (running.length > 0) return;
+
     setApplying(true);
     setInstalls(new Map());
 
@@ -180,10 +187,17 @@ function App() {
           <CardContent className="space-y-6">
             <FolderPicker path={wowPath} loading={pathLoading} onPick={handlePickFolder} />
             <RequirementsPanel wowPath={wowPath} />
+            <ConfigPanel wowPath={wowPath} installedPatches={installedPatches} />
 
             {/* Status summary */}
             {wowPath && !detecting && (
               <div className="rounded-lg border bg-muted/50 p-3 text-sm space-y-1">
+                {runningExe && (
+                  <div className="flex items-center gap-1 text-yellow-500">
+                    <AlertTriangle className="h-3 w-3" />
+                    {runningExe} is running from this folder; close the game before applying changes
+                  </div>
+                )}
                 {installedPatches.size > 0 && (
                   <div className="text-muted-foreground">
                     {installedPatches.size} mod{installedPatches.size !== 1 ? 's' : ''} currently installed
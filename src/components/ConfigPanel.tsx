@@ -0,0 +1,114 @@
+import { useEffect, useState } from 'react';
+import { CheckCircle, Loader2, Settings, Undo2 } from 'lucide-react';
+import { Button } from '@/components/ui/button';
+import {
+  getCvarStatus, applyPatchCvars, revertPatchCvars, CvarStatus,
+} from '@/lib/tauri';
+
+interface Props {
+  wowPath: string | null;
+  installedPatches: Set<string>;
+}
+
+/** Config.wtf settings recommended by the selected and installed patches */
+export function ConfigPanel({ wowPath, installedPatches }: Props) {
+  const [cvars, setCvars] = useState<CvarStatus[] | null>(null);
+  const [busy, setBusy] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (wowPath) {
+      getCvarStatus().then(setCvars).catch(() => setCvars(null));
+    }
+  }, [wowPath, installedPatches]);
+
+  const run = async (key: string, action: () => Promise<CvarStatus[]>) => {
+    setBusy(key);
+    setError(null);
+    try {
+      setCvars(await action());
+    } catch (e) {
+      setError(`Config.wtf: ${e}`);
+    } finally {
+      setBusy(null);
+    }
+  };
+
+  if (!wowPath || !cvars || cvars.length === 0) {
+    return null;
+  }
+
+  const patchIds = [...new Set(cvars.map((c) => c.patchId))];
+
+  return (
+    <div className="rounded-lg border p-3">
+      <div className="flex items-center gap-2 mb-3">
+        <Settings className="h-4 w-4 text-muted-foreground" />
+        <span className="text-sm font-semibold">Client Settings (Config.wtf)</span>
+      </div>
+
+      <div className="space-y-3">
+        {patchIds.map((patchId) => {
+          const rows = cvars.filter((c) => c.patchId === patchId);
+          const installed = installedPatches.has(patchId);
+          const pending = rows.some((c) => c.current !== c.recommended);
+          const managed = rows.some((c) => c.managed);
+          return (
+            <div key={patchId} className="space-y-1">
+              <div className="flex items-center justify-between">
+                <span className="text-xs font-medium">
+                  Patch {patchId}
+                  {!installed && <span className="text-muted-foreground ml-1">(applied on install)</span>}
+                </span>
+                <div className="flex items-center gap-2">
+                  {installed && pending && (
+                    <Button
+                      size="sm"
+                      variant="outline"
+                      onClick={() => run(patchId, () => applyPatchCvars(patchId))}
+                      disabled={busy !== null}
+                      className="h-6 text-xs"
+                    >
+                      {busy === patchId ? <Loader2 className="h-3 w-3 animate-spin" /> : 'Apply'}
+                    </Button>
+                  )}
+                  {installed && managed && (
+                    <Button
+                      size="sm"
+                      variant="outline"
+                      onClick={() => run(patchId, () => revertPatchCvars(patchId))}
+                      disabled={busy !== null}
+                      className="h-6 text-xs"
+                      title="Put back the values from before this patch"
+                    >
+                      <Undo2 className="h-3 w-3" />
+                    </Button>
+                  )}
+                </div>
+              </div>
+              {rows.map((c) => (
+                <div key={c.key} className="flex items-center justify-between text-xs pl-2">
+                  <span className="font-mono">{c.key}</span>
+                  <span className="flex items-center gap-1">
+                    {c.current === c.recommended ? (
+                      <CheckCircle className="h-3 w-3 text-green-500" />
+                    ) : (
+                      <span className="text-muted-foreground">{c.current ?? 'unset'} →</span>
+                    )}
+                    <span className="font-mono">{c.recommended}</span>
+                  </span>
+                </div>
+              ))}
+            </div>
+          );
+        })}
+      </div>
+
+      {error && (
+        <div className="mt-2 p-2 rounded bg-red-500/20 border border-red-500/50">
+          <p className="text-xs text-red-400 font-medium">{error}</p>
+        </div>
+      )}
+    </div>
+  );
+}
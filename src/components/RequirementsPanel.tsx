@@ -1,68 +1,176 @@
 import { useEffect, useState } from 'react';
-import { CheckCircle, XCircle, Download, Loader2, AlertTriangle } from 'lucide-react';
+import { CheckCircle, XCircle, Download, Loader2, AlertTriangle, RotateCcw, Trash2, Pin, PinOff, ShieldCheck } from 'lucide-react';
 import { Button } from '@/components/ui/button';
-import { checkRequirements, installVanillaHelpers, installDxvk, RequirementsStatus } from '@/lib/tauri';
+import {
+  checkRequirements, checkRequirementUpdates, listRequirementVersions, pinRequirement,
+  installRequirement, uninstallRequirement, RequirementStatus,
+  listDxvkVersions, getDxvkStatus, writeDxvkConf, DxvkStatus, DxvkPreset,
+  getWineOverrideStatus, setWineOverride, WineOverrideStatus,
+  getLaaStatus, enableLargeAddressAware, restoreExeBackup, LaaStatus,
+} from '@/lib/tauri';
 
 interface Props {
   wowPath: string | null;
 }
 
+const LEVEL_LABEL: Record<RequirementStatus['level'], { text: string; className: string }> = {
+  required: { text: '(required)', className: 'text-red-400' },
+  recommended: { text: '(recommended)', className: 'text-yellow-400' },
+  optional: { text: '(optional)', className: 'text-muted-foreground' },
+};
+
+/** Catalog ID of the DXVK requirement */
+const DXVK_ID = 'dxvk';
+
+/** Whether a missing requirement should be flagged */
+function isNeeded(req: RequirementStatus): boolean {
+  return req.level === 'required' || req.requiredBy.length > 0;
+}
+
 export function RequirementsPanel({ wowPath }: Props) {
-  const [status, setStatus] = useState<RequirementsStatus | null>(null);
-  const [installingVH, setInstallingVH] = useState(false);
-  const [installingDxvk, setInstallingDxvk] = useState(false);
-  const [dxvkVersion, setDxvkVersion] = useState<'2.7.1' | '2.5.3'>('2.7.1');
+  const [requirements, setRequirements] = useState<RequirementStatus[] | null>(null);
+  const [busy, setBusy] = useState<string | null>(null);
+  const [versions, setVersions] = useState<Record<string, string>>({});
+  const [dxvkVersions, setDxvkVersions] = useState<string[]>([]);
+  const [releases, setReleases] = useState<Record<string, string[]>>({});
+  const [dxvk, setDxvk] = useState<DxvkStatus | null>(null);
+  const [wine, setWine] = useState<WineOverrideStatus | null>(null);
+  const [preset, setPreset] = useState<DxvkPreset>('balanced');
+  const [frameLimit, setFrameLimit] = useState('');
+  const [laa, setLaa] = useState<LaaStatus | null>(null);
+  const [patchingLaa, setPatchingLaa] = useState(false);
   const [error, setError] = useState<string | null>(null);
   const [success, setSuccess] = useState<string | null>(null);
 
   const refreshStatus = async () => {
     if (wowPath) {
-      const s = await checkRequirements();
-      setStatus(s);
+      setRequirements(await checkRequirements().catch(() => null));
+      const dxvkStatus = await getDxvkStatus().catch(() => null);
+      setDxvk(dxvkStatus);
+      if (dxvkStatus?.preset) {
+        setPreset(dxvkStatus.preset);
+        setFrameLimit(dxvkStatus.maxFrameRate ? String(dxvkStatus.maxFrameRate) : '');
+      }
+      setWine(await getWineOverrideStatus().catch(() => null));
+      setLaa(await getLaaStatus().catch(() => null));
     }
   };
 
   useEffect(() => {
-    refreshStatus();
+    refreshStatus().then(async () => {
+      if (!wowPath) return;
+      const updated = await checkRequirementUpdates().catch(() => null);
+      if (updated) {
+        setRequirements(updated);
+        // Release tags of GitHub-hosted requirements, which the catalog does not list
+        for (const req of updated.filter((r) => r.versions.length === 0)) {
+          listRequirementVersions(req.id)
+            .then((tags) => setReleases((prev) => ({ ...prev, [req.id]: tags })))
+            .catch(() => {});
+        }
+      }
+    });
   }, [wowPath]);
 
-  const handleInstallVH = async () => {
-    setInstallingVH(true);
+  useEffect(() => {
+    listDxvkVersions().then(setDxvkVersions).catch(() => setDxvkVersions([]));
+  }, []);
+
+  /** Catalog versions first (they are the recommended ones), then all releases */
+  const versionsOf = (req: RequirementStatus): string[] =>
+    req.id === DXVK_ID ? [...new Set([...req.versions, ...dxvkVersions])] : req.versions.length > 0 ? req.versions : releases[req.id] ?? [];
+
+  /** Version an install uses: the selected one, else the catalog default (none means latest or pinned) */
+  const versionFor = (req: RequirementStatus): string | undefined => (versions[req.id] ?? req.versions[0]) || undefined;
+
+  const handlePin = async (req: RequirementStatus) => {
+    setBusy(req.id);
+    setError(null);
+    try {
+      await pinRequirement(req.id, !req.pinned);
+      setRequirements((prev) => prev?.map((r) => (r.id === req.id ? { ...r, pinned: !req.pinned, updateAvailable: false } : r)) ?? null);
+    } catch (e) {
+      setError(`${req.name}: ${e}`);
+    } finally {
+      setBusy(null);
+    }
+  };
+
+  const handleDxvkConf = async () => {
+    setBusy(DXVK_ID);
+    setError(null);
+    setSuccess(null);
+    try {
+      const limit = parseInt(frameLimit, 10);
+      setDxvk(await writeDxvkConf(preset, limit > 0 ? limit : undefined));
+      setSuccess('dxvk.conf written');
+      setTimeout(() => setSuccess(null), 3000);
+    } catch (e) {
+      setError(`dxvk.conf: ${e}`);
+    } finally {
+      setBusy(null);
+    }
+  };
+
+  const handleWineOverride = async () => {
+    setBusy(DXVK_ID);
     setError(null);
     setSuccess(null);
     try {
-      await installVanillaHelpers();
+      setWine(await setWineOverride(true));
+      setSuccess('d3d9 override set to native, builtin');
+      setTimeout(() => setSuccess(null), 3000);
+    } catch (e) {
+      setError(`Wine override: ${e}`);
+    } finally {
+      setBusy(null);
+    }
+  };
+
+  const handleRequirement = async (req: RequirementStatus, uninstall: boolean) => {
+    const version = versionFor(req);
+    setBusy(req.id);
+    setError(null);
+    setSuccess(null);
+    try {
+      if (uninstall) {
+        await uninstallRequirement(req.id);
+      } else {
+        await installRequirement(req.id, version);
+      }
       await refreshStatus();
-      setSuccess('VanillaHelpers installed successfully!');
+      const label = version && !uninstall ? `${req.name} ${version}` : req.name;
+      setSuccess(`${label} ${uninstall ? 'uninstalled' : 'installed'} successfully!`);
       setTimeout(() => setSuccess(null), 3000);
     } catch (e) {
-      setError(`VanillaHelpers: ${e}`);
+      setError(`${req.name}: ${e}`);
     } finally {
-      setInstallingVH(false);
+      setBusy(null);
     }
   };
 
-  const handleInstallDxvk = async () => {
-    setInstallingDxvk(true);
+  const handleLaa = async (restore: boolean) => {
+    setPatchingLaa(true);
     setError(null);
     setSuccess(null);
     try {
-      await installDxvk(dxvkVersion);
+      setLaa(restore ? await restoreExeBackup() : await enableLargeAddressAware());
       await refreshStatus();
-      setSuccess(`DXVK ${dxvkVersion} installed successfully!`);
+      setSuccess(restore ? 'Original executable restored' : 'Large Address Aware enabled');
       setTimeout(() => setSuccess(null), 3000);
     } catch (e) {
-      setError(`DXVK: ${e}`);
+      setError(`Large Address Aware: ${e}`);
     } finally {
-      setInstallingDxvk(false);
+      setPatchingLaa(false);
     }
   };
 
-  if (!wowPath || !status) {
+  if (!wowPath || !requirements) {
     return null;
   }
 
-  const allGood = status.vanilla_helpers && status.dxvk;
+  const missing = requirements.filter((r) => !r.satisfied && isNeeded(r));
+  const allGood = requirements.every((r) => r.satisfied);
 
   return (
     <div className={`rounded-lg border p-3 ${allGood ? 'border-green-500/30 bg-green-500/5' : 'border-yellow-500/30 bg-yellow-500/5'}`}>
@@ -78,78 +186,198 @@ export function RequirementsPanel({ wowPath }: Props) {
       </div>
 
       <div className="space-y-2">
-        {/* VanillaHelpers */}
-        <div className="flex items-center justify-between">
-          <div className="flex items-center gap-2">
-            {status.vanilla_helpers ? (
-              <CheckCircle className="h-4 w-4 text-green-500" />
-            ) : (
-              <XCircle className="h-4 w-4 text-red-500" />
-            )}
-            <div>
-              <span className="text-sm">VanillaHelpers</span>
-              <span className="text-xs text-red-400 ml-1">(required)</span>
+        {requirements.map((req) => (
+          <div key={req.id} className="space-y-2">
+            <div className="flex items-center justify-between">
+              <div className="flex items-center gap-2">
+                {req.satisfied ? (
+                  <CheckCircle className="h-4 w-4 text-green-500" />
+                ) : (
+                  <XCircle className={`h-4 w-4 ${isNeeded(req) ? 'text-red-500' : 'text-yellow-500'}`} />
+                )}
+                <div title={req.description}>
+                  <span className="text-sm">{req.name}</span>
+                  <span className={`text-xs ml-1 ${LEVEL_LABEL[req.level].className}`}>
+                    {LEVEL_LABEL[req.level].text}
+                  </span>
+                  {req.installedVersion && (
+                    <span className="text-xs text-muted-foreground ml-1" title={req.sha256 ? `sha256 ${req.sha256}` : undefined}>
+                      {req.installedVersion}
+                      {req.verified && <ShieldCheck className="inline h-3 w-3 ml-0.5 text-green-500" aria-label="checksum verified" />}
+                    </span>
+                  )}
+                  {req.updateAvailable && (
+                    <span className="text-xs text-blue-400 ml-1">{req.latestVersion} available</span>
+                  )}
+                  {req.installed && !req.satisfied && (
+                    <span className="text-xs text-yellow-400 ml-1">
+                      {req.product ? `found ${req.product}, backed up on install` : 'outdated'}
+                    </span>
+                  )}
+                </div>
+              </div>
+              <div className="flex items-center gap-2">
+                {versionsOf(req).length > 0 && (
+                  <select
+                    value={versions[req.id] ?? req.versions[0] ?? ''}
+                    onChange={(e) => setVersions({ ...versions, [req.id]: e.target.value })}
+                    disabled={busy === req.id}
+                    className="h-7 text-xs bg-background border rounded px-1"
+                  >
+                    {req.versions.length === 0 && (
+                      <option value="">{req.pinned ? `pinned (${req.installedVersion})` : 'latest'}</option>
+                    )}
+                    {versionsOf(req).map((v) => (
+                      <option key={v} value={v}>{req.versions.length === 0 ? v : `v${v}`}</option>
+                    ))}
+                  </select>
+                )}
+                <Button
+                  size="sm"
+                  variant="outline"
+                  onClick={() => handleRequirement(req, false)}
+                  disabled={busy !== null}
+                  className="h-7 text-xs"
+                >
+                  {busy === req.id ? (
+                    <Loader2 className="h-3 w-3 animate-spin" />
+                  ) : (
+                    <>
+                      <Download className="h-3 w-3 mr-1" />
+                      {req.installed ? 'Update' : 'Install'}
+                    </>
+                  )}
+                </Button>
+                {req.managed && (
+                  <Button
+                    size="sm"
+                    variant="outline"
+                    onClick={() => handlePin(req)}
+                    disabled={busy !== null}
+                    className="h-7 text-xs"
+                    title={req.pinned ? `Unpin ${req.name}` : `Pin ${req.name} to ${req.installedVersion ?? 'this version'}`}
+                  >
+                    {req.pinned ? <PinOff className="h-3 w-3" /> : <Pin className="h-3 w-3" />}
+                  </Button>
+                )}
+                {req.managed && (
+                  <Button
+                    size="sm"
+                    variant="outline"
+                    onClick={() => handleRequirement(req, true)}
+                    disabled={busy !== null}
+                    className="h-7 text-xs"
+                    title={`Uninstall ${req.name}`}
+                  >
+                    <Trash2 className="h-3 w-3" />
+                  </Button>
+                )}
+              </div>
             </div>
-          </div>
-          <Button
-            size="sm"
-            variant="outline"
-            onClick={handleInstallVH}
-            disabled={installingVH}
-            className="h-7 text-xs"
-          >
-            {installingVH ? (
-              <Loader2 className="h-3 w-3 animate-spin" />
-            ) : (
-              <>
-                <Download className="h-3 w-3 mr-1" />
-                {status.vanilla_helpers ? 'Update' : 'Install'}
-              </>
+            {req.id === DXVK_ID && req.managed && dxvk?.d3d9?.kind === 'dxvk' && (
+              <div className="flex items-center justify-end gap-2">
+                <span className="text-xs text-muted-foreground">
+                  dxvk.conf{dxvk.customConf ? ' (custom, backed up on apply)' : ''}
+                </span>
+                <select
+                  value={preset}
+                  onChange={(e) => setPreset(e.target.value as DxvkPreset)}
+                  disabled={busy !== null}
+                  className="h-7 text-xs bg-background border rounded px-1"
+                >
+                  <option value="quality">Quality</option>
+                  <option value="balanced">Balanced</option>
+                  <option value="performance">Performance</option>
+                </select>
+                <input
+                  type="number"
+                  min={0}
+                  placeholder="FPS limit"
+                  value={frameLimit}
+                  onChange={(e) => setFrameLimit(e.target.value)}
+                  disabled={busy !== null}
+                  className="h-7 w-20 text-xs bg-background border rounded px-1"
+                />
+                <Button
+                  size="sm"
+                  variant="outline"
+                  onClick={handleDxvkConf}
+                  disabled={busy !== null}
+                  className="h-7 text-xs"
+                >
+                  Apply
+                </Button>
+              </div>
             )}
-          </Button>
-        </div>
-
-        {/* DXVK */}
-        <div className="flex items-center justify-between">
-          <div className="flex items-center gap-2">
-            {status.dxvk ? (
-              <CheckCircle className="h-4 w-4 text-green-500" />
-            ) : (
-              <XCircle className="h-4 w-4 text-yellow-500" />
+            {req.id === DXVK_ID && dxvk?.d3d9?.kind === 'dxvk' && (wine?.prefix || wine?.lutrisConfig) && (
+              <div className="flex items-center justify-end gap-2">
+                <span className={`text-xs ${wine.active ? 'text-muted-foreground' : 'text-yellow-400'}`}>
+                  {wine.active
+                    ? `Wine loads DXVK (d3d9=${wine.lutrisValue ?? wine.registryValue}${wine.lutrisValue ? ', Lutris' : ''})`
+                    : 'Wine uses its builtin d3d9, DXVK is not loaded'}
+                </span>
+                {!wine.active && (
+                  <Button
+                    size="sm"
+                    variant="outline"
+                    onClick={handleWineOverride}
+                    disabled={busy !== null}
+                    className="h-7 text-xs"
+                  >
+                    Set override
+                  </Button>
+                )}
+              </div>
             )}
-            <div>
-              <span className="text-sm">DXVK</span>
-              <span className="text-xs text-yellow-400 ml-1">(recommended)</span>
-            </div>
           </div>
-          <div className="flex items-center gap-2">
-            <select
-              value={dxvkVersion}
-              onChange={(e) => setDxvkVersion(e.target.value as '2.7.1' | '2.5.3')}
-              disabled={installingDxvk}
-              className="h-7 text-xs bg-background border rounded px-1"
-            >
-              <option value="2.7.1">v2.7.1 (NVIDIA)</option>
-              <option value="2.5.3">v2.5.3 (AMD)</option>
-            </select>
-            <Button
-              size="sm"
-              variant="outline"
-              onClick={handleInstallDxvk}
-              disabled={installingDxvk}
-              className="h-7 text-xs"
-            >
-              {installingDxvk ? (
-                <Loader2 className="h-3 w-3 animate-spin" />
+        ))}
+
+        {/* Large Address Aware */}
+        {laa && (
+          <div className="flex items-center justify-between">
+            <div className="flex items-center gap-2">
+              {laa.enabled ? (
+                <CheckCircle className="h-4 w-4 text-green-500" />
               ) : (
-                <>
-                  <Download className="h-3 w-3 mr-1" />
-                  {status.dxvk ? 'Update' : 'Install'}
-                </>
+                <XCircle className="h-4 w-4 text-yellow-500" />
               )}
-            </Button>
+              <div>
+                <span className="text-sm">4 GB patch</span>
+                <span className="text-xs text-yellow-400 ml-1">(recommended for HD textures)</span>
+              </div>
+            </div>
+            {laa.enabled ? (
+              laa.hasBackup && (
+                <Button
+                  size="sm"
+                  variant="outline"
+                  onClick={() => handleLaa(true)}
+                  disabled={patchingLaa}
+                  className="h-7 text-xs"
+                >
+                  {patchingLaa ? (
+                    <Loader2 className="h-3 w-3 animate-spin" />
+                  ) : (
+                    <>
+                      <RotateCcw className="h-3 w-3 mr-1" />
+                      Restore
+                    </>
+                  )}
+                </Button>
+              )
+            ) : (
+              <Button
+                size="sm"
+                variant="outline"
+                onClick={() => handleLaa(false)}
+                disabled={patchingLaa}
+                className="h-7 text-xs"
+              >
+                {patchingLaa ? <Loader2 className="h-3 w-3 animate-spin" /> : `Patch ${laa.exe}`}
+              </Button>
+            )}
           </div>
-        </div>
+        )}
       </div>
 
       {error && (
@@ -164,11 +392,13 @@ export function RequirementsPanel({ wowPath }: Props) {
         </div>
       )}
 
-      {!status.vanilla_helpers && (
-        <p className="mt-2 text-xs text-yellow-500">
-          HD Patches require VanillaHelpers to work!
+      {missing.map((req) => (
+        <p key={req.id} className="mt-2 text-xs text-yellow-500">
+          {req.requiredBy.length > 0
+            ? `${req.requiredBy.join(', ')} require ${req.name} to work!`
+            : `HD Patches require ${req.name} to work!`}
         </p>
-      )}
+      ))}
     </div>
   );
 }
@@ -95,9 +95,12 @@ export function useDownload() {
         }
       };
 
-      const targetFilename = `Patch-${module.id.toUpperCase()}.mpq`;
+      // Addons and patches that declare their own files may download anything, not just an MPQ
+      const targetFilename = module.files?.length || module.kind === 'addon'
+        ? `Patch-${module.id.toUpperCase()}.download`
+        : `Patch-${module.id.toUpperCase()}.mpq`;
       console.log('[Download] Starting', module.id, link.url);
-      startDownload(link.url, link.provider, destDir, onProgress, targetFilename)
+      startDownload(link.url, link.provider, destDir, onProgress, targetFilename, link.folder_file)
         .then(id => console.log('[Download] Got ID', id, 'for', module.id))
         .catch(err => {
           console.log('[Download] Start failed for', module.id, err);
@@ -6,6 +6,14 @@ export interface DownloadLink {
   url: string;
   file_name?: string;
   variant?: string;
+  folder_file?: string;
+}
+
+// A file a patch installs (matches PatchFile in patch.rs with serde camelCase)
+export interface PatchFile {
+  source?: string;
+  destination: string;
+  rename?: string;
 }
 
 export interface PatchModule {
@@ -19,6 +27,12 @@ export interface PatchModule {
   preview?: string;
   author?: string;
   forumUrl?: string;
+  extract?: string[];
+  files?: PatchFile[];
+  cvars?: Record<string, string>;
+  kind?: 'patch' | 'addon';
+  compatibleBuilds?: number[];
+  requires?: string[];
 }
 
 export interface PatchGroup {
@@ -38,7 +52,8 @@ export type DownloadEvent =
   | { event: 'started'; data: { downloadId: string; fileName: string; totalBytes: number } }
   | { event: 'progress'; data: { downloadId: string; downloadedBytes: number; totalBytes: number; speedBps: number; percent: number } }
   | { event: 'completed'; data: { downloadId: string; filePath: string } }
-  | { event: 'failed'; data: { downloadId: string; error: string } };
+  | { event: 'failed'; data: { downloadId: string; error: string } }
+  | { event: 'cancelled'; data: { downloadId: string } };
 
 // Install events (matches InstallEvent in copier.rs with serde camelCase)
 export type InstallEvent =
@@ -49,7 +64,7 @@ export type InstallEvent =
 
 // Verification result from Rust
 export interface VerifyResult {
-  status: 'ok' | 'sizeMismatch' | 'missing' | 'noReference';
+  status: 'ok' | 'sizeMismatch' | 'missing' | 'noReference' | 'configMismatch' | 'caseDuplicate';
 }
 
 // Repair result from Rust
@@ -59,6 +74,15 @@ export interface RepairResult {
   error?: string;
 }
 
+// Client executable identity (matches ClientInfo in detector.rs with serde camelCase)
+export interface ClientInfo {
+  exe: string;
+  build?: number;
+  fileVersion?: string;
+  checksum: number;
+  sha256: string;
+}
+
 // Parser commands
 export async function fetchPatches(): Promise<PatchesResponse> {
   return invoke('fetch_patches');
@@ -68,6 +92,14 @@ export async function getConflicts(selected: string[]): Promise<string[]> {
   return invoke('get_conflicts', { selected });
 }
 
+export async function getClientInfo(): Promise<ClientInfo | null> {
+  return invoke('get_client_info');
+}
+
+export async function getIncompatiblePatches(selected: string[]): Promise<string[]> {
+  return invoke('get_incompatible_patches', { selected });
+}
+
 export async function validateSelection(selected: string[]): Promise<void> {
   return invoke('validate_selection', { selected });
 }
@@ -89,6 +121,18 @@ export async function autoDetectWow(): Promise<string | null> {
   return invoke('auto_detect_wow');
 }
 
+// WoW folder found by the detector (matches WowCandidate in prefixes.rs with serde camelCase)
+export interface WowCandidate {
+  path: string;
+  source: 'local' | 'lutris' | 'bottles' | 'heroic' | 'steam' | 'wine';
+  confidence: 'low' | 'medium' | 'high';
+  prefix?: string;
+}
+
+export async function listWowCandidates(): Promise<WowCandidate[]> {
+  return invoke('list_wow_candidates');
+}
+
 export async function loadSavedWowPath(): Promise<string | null> {
   return invoke('load_saved_wow_path');
 }
@@ -99,9 +143,25 @@ export async function startDownload(
   provider: string,
   destDir: string,
   onProgress: Channel<DownloadEvent>,
-  targetFilename?: string
+  targetFilename?: string,
+  folderFile?: string
 ): Promise<string> {
-  return invoke('start_download', { shareUrl, provider, destDir, onProgress, targetFilename });
+  return invoke('start_download', { shareUrl, provider, destDir, onProgress, targetFilename, folderFile });
+}
+
+// File in a folder link (matches FolderEntry in providers/mod.rs)
+export interface FolderEntry {
+  name: string;
+  size?: number;
+  url: string;
+}
+
+export async function listFolder(shareUrl: string, provider?: string): Promise<FolderEntry[]> {
+  return invoke('list_folder', { shareUrl, provider });
+}
+
+export async function cancelDownload(downloadId: string): Promise<boolean> {
+  return invoke('cancel_download', { downloadId });
 }
 
 export async function getActiveDownloads(): Promise<number> {
@@ -131,26 +191,160 @@ export async function detectInstalledPatches(patchIds: string[]): Promise<string
   return invoke('detect_installed_patches', { patchIds });
 }
 
+// Addon found in Interface/AddOns (matches InstalledAddon in addons.rs with serde camelCase)
+export interface InstalledAddon {
+  name: string;
+  title?: string;
+  version?: string;
+  interface?: number;
+  interfaceMismatch: boolean;
+  patchId?: string;
+}
+
+export async function listAddons(): Promise<InstalledAddon[]> {
+  return invoke('list_addons');
+}
+
 export async function uninstallPatches(patchIds: string[]): Promise<string[]> {
   return invoke('uninstall_patches', { patchIds });
 }
 
-// Requirements check
-export interface RequirementsStatus {
-  vanilla_helpers: boolean;
-  dxvk: boolean;
+// Catalog requirement state (matches RequirementStatus in requirements.rs with serde camelCase)
+export interface RequirementStatus {
+  id: string;
+  name: string;
+  description: string;
+  level: 'required' | 'recommended' | 'optional';
+  versions: string[];
+  installed: boolean;
+  fileVersion?: string;
+  product?: string;
+  installedVersion?: string;
+  sha256?: string;
+  verified: boolean;
+  pinned: boolean;
+  latestVersion?: string;
+  updateAvailable: boolean;
+  satisfied: boolean;
+  managed: boolean;
+  backups: string[];
+  requiredBy: string[];
+}
+
+// DXVK state (matches DxvkStatus in dxvk.rs with serde camelCase)
+export type D3d9Kind = { kind: 'dxvk' } | { kind: 'other'; product: string } | { kind: 'unknown' };
+export type DxvkPreset = 'quality' | 'balanced' | 'performance';
+
+export interface DxvkStatus {
+  d3d9?: D3d9Kind;
+  hasBackup: boolean;
+  preset?: DxvkPreset;
+  maxFrameRate?: number;
+  customConf: boolean;
+}
+
+// Wine d3d9 override state (matches WineOverrideStatus in wine.rs with serde camelCase)
+export interface WineOverrideStatus {
+  prefix?: string;
+  lutrisConfig?: string;
+  registryValue?: string;
+  lutrisValue?: string;
+  active: boolean;
+  hasBackup: boolean;
+}
+
+// Large Address Aware state of WoW.exe (matches LaaStatus in laa.rs with serde camelCase)
+export interface LaaStatus {
+  exe: string;
+  enabled: boolean;
+  hasBackup: boolean;
 }
 
-export async function checkRequirements(): Promise<RequirementsStatus | null> {
+// A client running from the WoW folder (matches RunningClient in process.rs with serde camelCase)
+export interface RunningClient {
+  pid: number;
+  exe: string;
+}
+
+export async function getRunningClients(): Promise<RunningClient[]> {
+  return invoke('get_running_clients');
+}
+
+// A CVar a patch recommends (matches CvarStatus in config_wtf.rs with serde camelCase)
+export interface CvarStatus {
+  patchId: string;
+  key: string;
+  current?: string;
+  recommended: string;
+  managed: boolean;
+}
+
+export async function getCvarStatus(): Promise<CvarStatus[]> {
+  return invoke('get_cvar_status');
+}
+
+export async function applyPatchCvars(patchId: string): Promise<CvarStatus[]> {
+  return invoke('apply_patch_cvars', { patchId });
+}
+
+export async function revertPatchCvars(patchId: string): Promise<CvarStatus[]> {
+  return invoke('revert_patch_cvars', { patchId });
+}
+
+export async function getLaaStatus(): Promise<LaaStatus> {
+  return invoke('get_laa_status');
+}
+
+export async function enableLargeAddressAware(): Promise<LaaStatus> {
+  return invoke('enable_large_address_aware');
+}
+
+export async function restoreExeBackup(): Promise<LaaStatus> {
+  return invoke('restore_exe_backup');
+}
+
+export async function checkRequirements(): Promise<RequirementStatus[]> {
   return invoke('check_requirements');
 }
 
-export async function installVanillaHelpers(): Promise<void> {
-  return invoke('install_vanilla_helpers');
+export async function checkRequirementUpdates(): Promise<RequirementStatus[]> {
+  return invoke('check_requirement_updates');
+}
+
+export async function listRequirementVersions(requirementId: string): Promise<string[]> {
+  return invoke('list_requirement_versions', { requirementId });
+}
+
+export async function pinRequirement(requirementId: string, pinned: boolean): Promise<void> {
+  return invoke('pin_requirement', { requirementId, pinned });
+}
+
+export async function installRequirement(requirementId: string, version?: string): Promise<void> {
+  return invoke('install_requirement', { requirementId, version });
+}
+
+export async function uninstallRequirement(requirementId: string): Promise<void> {
+  return invoke('uninstall_requirement', { requirementId });
+}
+
+export async function listDxvkVersions(): Promise<string[]> {
+  return invoke('list_dxvk_versions');
+}
+
+export async function getDxvkStatus(): Promise<DxvkStatus> {
+  return invoke('get_dxvk_status');
+}
+
+export async function writeDxvkConf(preset: DxvkPreset, maxFrameRate?: number): Promise<DxvkStatus> {
+  return invoke('write_dxvk_conf', { preset, maxFrameRate });
+}
+
+export async function getWineOverrideStatus(): Promise<WineOverrideStatus> {
+  return invoke('get_wine_override_status');
 }
 
-export async function installDxvk(version?: string): Promise<void> {
-  return invoke('install_dxvk', { version });
+export async function setWineOverride(enabled: boolean): Promise<WineOverrideStatus> {
+  return invoke('set_wine_override', { enabled });
 }
 
 // Update check types